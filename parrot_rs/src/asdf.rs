@@ -0,0 +1,392 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::file::normalize_path;
+use crate::reader::{included_forms, plist_get, read_all, Form, FormKind};
+use crate::tree::get_all_asd_files_in_folder;
+use crate::BackendResult;
+
+///
+/// A system as declared by a `defsystem` form in an .asd file.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AsdfSystem {
+    pub name: String,
+    pub asd_file: String,
+    // 0-based line of the defsystem form in the .asd file
+    pub line: usize,
+    // the directory components are resolved against, i.e. the .asd file's folder plus the system's :pathname
+    pub path: String,
+
+    pub description: Option<String>,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub license: Option<String>,
+
+    pub depends_on: Vec<String>,
+    pub defsystem_depends_on: Vec<String>,
+    pub serial: bool,
+    pub components: Vec<AsdfComponent>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AsdfComponent {
+    pub kind: AsdfComponentKind,
+    pub name: String,
+    // resolved path of the file, or of the directory for modules
+    pub path: String,
+    // names of sibling components
+    pub depends_on: Vec<String>,
+    pub serial: bool,
+    pub components: Vec<AsdfComponent>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AsdfComponentKind {
+    File,
+    Module,
+    StaticFile,
+    Other(String)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemGraph {
+    pub nodes: Vec<SystemGraphNode>,
+    pub edges: Vec<SystemGraphEdge>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemGraphNode {
+    pub name: String,
+    // true if the system is defined in the opened folder, false for e.g. Quicklisp systems
+    pub local: bool
+}
+
+///
+/// `from` depends on `to`
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemGraphEdge {
+    pub from: String,
+    pub to: String
+}
+
+///
+/// Holds the systems defined in the .asd files of the opened folder.
+///
+pub struct SystemIndex {
+    root_folder: Option<String>,
+    systems: Vec<AsdfSystem>
+}
+
+impl SystemIndex {
+    pub fn new() -> Self {
+        Self {
+            root_folder: None,
+            systems: vec![]
+        }
+    }
+
+    pub fn build(&mut self, folder: &str) {
+        self.root_folder = Some(folder.to_string());
+        self.systems = find_systems(folder);
+    }
+
+    /// call this after a file's content has been updated
+    pub fn handle_file_content_write(&mut self, file_path: &str) {
+        if !file_path.ends_with(".asd") {
+            return;
+        }
+        let asd_file = normalize_path(file_path);
+        self.systems.retain(|s| s.asd_file != asd_file);
+        if let Ok(mut systems) = parse_asd_file(file_path) {
+            self.systems.append(&mut systems);
+        }
+    }
+
+    pub fn get_systems(&self) -> Vec<AsdfSystem> {
+        self.systems.clone()
+    }
+
+    pub fn get_system(&self, name: &str) -> Option<&AsdfSystem> {
+        self.systems.iter().find(|s| s.name.eq_ignore_ascii_case(name))
+    }
+
+    ///
+    /// Get all systems that have the given file as one of their components.
+    ///
+    pub fn get_systems_for_file(&self, file_path: &str) -> Vec<&AsdfSystem> {
+        let file_path = normalize_path(file_path);
+        self.systems
+            .iter()
+            .filter(|s| s.source_files().contains(&file_path))
+            .collect()
+    }
+
+    pub fn graph(&self) -> SystemGraph {
+        SystemGraph::build(&self.systems)
+    }
+}
+
+impl Default for SystemIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsdfSystem {
+    ///
+    /// The paths of all source files of the system, in the order ASDF would load them.
+    ///
+    pub fn source_files(&self) -> Vec<String> {
+        let mut files = vec![];
+        collect_source_files(&self.components, &mut files);
+        files
+    }
+
+    ///
+    /// e.g. "foo" for "foo/tests"
+    ///
+    pub fn primary_name(&self) -> &str {
+        self.name.split('/').next().unwrap_or(&self.name)
+    }
+}
+
+impl SystemGraph {
+    pub fn build(systems: &[AsdfSystem]) -> Self {
+        let mut nodes: Vec<SystemGraphNode> = systems
+            .iter()
+            .map(|s| SystemGraphNode { name: s.name.clone(), local: true })
+            .collect();
+        let mut edges = vec![];
+        for s in systems {
+            for dep in s.defsystem_depends_on.iter().chain(s.depends_on.iter()) {
+                if !nodes.iter().any(|n| &n.name == dep) {
+                    nodes.push(SystemGraphNode { name: dep.clone(), local: false });
+                }
+                edges.push(SystemGraphEdge { from: s.name.clone(), to: dep.clone() });
+            }
+        }
+        Self { nodes, edges }
+    }
+
+    pub fn dependencies(&self, system: &str) -> Vec<String> {
+        self.edges
+            .iter()
+            .filter(|e| e.from == system)
+            .map(|e| e.to.clone())
+            .collect()
+    }
+
+    pub fn dependents(&self, system: &str) -> Vec<String> {
+        self.edges
+            .iter()
+            .filter(|e| e.to == system)
+            .map(|e| e.from.clone())
+            .collect()
+    }
+
+    ///
+    /// The given system and all its transitive dependencies, dependencies first.
+    ///
+    pub fn load_order(&self, system: &str) -> Vec<String> {
+        topological_order(&[system.to_string()], &|name| self.dependencies(name))
+    }
+}
+
+///
+/// Finds and parses all .asd files in the folder and its subfolders.
+/// Files that cannot be read are skipped.
+///
+pub fn find_systems(folder: &str) -> Vec<AsdfSystem> {
+    get_all_asd_files_in_folder(folder)
+        .iter()
+        .filter_map(|f| parse_asd_file(f).ok())
+        .flatten()
+        .collect()
+}
+
+pub fn parse_asd_file(path: &str) -> BackendResult<Vec<AsdfSystem>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(parse_asd(&content, path))
+}
+
+///
+/// Parses the defsystem forms of an .asd file without evaluating it.
+///
+pub fn parse_asd(text: &str, asd_file: &str) -> Vec<AsdfSystem> {
+    let forms = read_all(text);
+    included_forms(&forms)
+        .into_iter()
+        .filter_map(|f| parse_defsystem(f, text, asd_file))
+        .collect()
+}
+
+fn parse_defsystem(form: &Form, text: &str, asd_file: &str) -> Option<AsdfSystem> {
+    if !form.head_is("defsystem") {
+        return None;
+    }
+    let items = form.as_list()?;
+    let args = included_forms(&items[1..]);
+    let name = asdf_name(args.first()?)?;
+    let options = &args[1..];
+
+    let asd_dir = Path::new(asd_file).parent().map(|p| p.to_path_buf()).unwrap_or_default();
+    let dir = match plist_get(options, "pathname").and_then(|p| p.as_str()) {
+        Some(p) => asd_dir.join(p),
+        None => asd_dir
+    };
+    let serial = plist_get(options, "serial").map(is_true).unwrap_or(false);
+
+    Some(AsdfSystem {
+        name,
+        asd_file: normalize_path(asd_file),
+        line: text[..form.start].matches('\n').count(),
+        path: normalize_path(&dir),
+        description: plist_get(options, "description").and_then(|f| f.as_str()).map(|s| s.to_string()),
+        version: plist_get(options, "version").and_then(|f| f.as_str()).map(|s| s.to_string()),
+        author: plist_get(options, "author").and_then(|f| f.as_str()).map(|s| s.to_string()),
+        license: plist_get(options, "license")
+            .or_else(|| plist_get(options, "licence"))
+            .and_then(|f| f.as_str())
+            .map(|s| s.to_string()),
+        depends_on: plist_get(options, "depends-on").map(parse_dependencies).unwrap_or_default(),
+        defsystem_depends_on: plist_get(options, "defsystem-depends-on").map(parse_dependencies).unwrap_or_default(),
+        serial,
+        components: plist_get(options, "components").map(|c| parse_components(c, &dir)).unwrap_or_default()
+    })
+}
+
+fn parse_components(form: &Form, dir: &Path) -> Vec<AsdfComponent> {
+    match form.as_list() {
+        Some(items) => included_forms(items)
+            .into_iter()
+            .filter_map(|c| parse_component(c, dir))
+            .collect(),
+        None => vec![]
+    }
+}
+
+fn parse_component(form: &Form, dir: &Path) -> Option<AsdfComponent> {
+    let items = form.as_list()?;
+    let args = included_forms(items);
+    let kind = match args.first()?.symbol_name()?.to_lowercase().as_str() {
+        "file" | "cl-source-file" => AsdfComponentKind::File,
+        "module" => AsdfComponentKind::Module,
+        "static-file" | "doc-file" | "html-file" => AsdfComponentKind::StaticFile,
+        other => AsdfComponentKind::Other(other.to_string())
+    };
+    let name = asdf_name(args.get(1)?)?;
+    let options = &args[2..];
+    let pathname = plist_get(options, "pathname").and_then(|p| p.as_str());
+
+    let path: PathBuf = match (&kind, pathname) {
+        (AsdfComponentKind::Module, Some(p)) => dir.join(p),
+        (AsdfComponentKind::Module, None) => dir.join(&name),
+        (AsdfComponentKind::File, Some(p)) if Path::new(p).extension().is_none() => dir.join(format!("{}.lisp", p)),
+        (AsdfComponentKind::File, None) => dir.join(format!("{}.lisp", name)),
+        (_, Some(p)) => dir.join(p),
+        (_, None) => dir.join(&name)
+    };
+    let components = match kind {
+        AsdfComponentKind::Module => plist_get(options, "components").map(|c| parse_components(c, &path)).unwrap_or_default(),
+        _ => vec![]
+    };
+
+    Some(AsdfComponent {
+        kind,
+        name,
+        path: normalize_path(&path),
+        depends_on: plist_get(options, "depends-on").map(parse_dependencies).unwrap_or_default(),
+        serial: plist_get(options, "serial").map(is_true).unwrap_or(false),
+        components
+    })
+}
+
+fn parse_dependencies(form: &Form) -> Vec<String> {
+    match form.as_list() {
+        Some(items) => included_forms(items)
+            .into_iter()
+            .filter_map(parse_dependency)
+            .collect(),
+        None => vec![]
+    }
+}
+
+///
+/// e.g. "foo", :foo, #:foo, (:version "foo" "1.0"), (:feature :sbcl "foo"), (:require "sb-posix")
+///
+fn parse_dependency(form: &Form) -> Option<String> {
+    match &form.kind {
+        FormKind::List(items) => {
+            let head = items.first()?;
+            if head.is_keyword("feature") {
+                parse_dependency(items.get(2)?)
+            } else if head.is_keyword("version") || head.is_keyword("require") {
+                asdf_name(items.get(1)?)
+            } else {
+                None
+            }
+        },
+        _ => asdf_name(form)
+    }
+}
+
+///
+/// ASDF names are strings as written or downcased symbol names.
+///
+fn asdf_name(form: &Form) -> Option<String> {
+    match &form.kind {
+        FormKind::Str(s) => Some(s.clone()),
+        _ => form.symbol_name().map(|n| n.to_lowercase())
+    }
+}
+
+fn is_true(form: &Form) -> bool {
+    !form.is_symbol("nil")
+}
+
+fn collect_source_files(components: &[AsdfComponent], files: &mut Vec<String>) {
+    let names = components.iter().map(|c| c.name.clone()).collect::<Vec<String>>();
+    let ordered = topological_order(&names, &|name| {
+        components
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| c.depends_on.clone())
+            .unwrap_or_default()
+    });
+    for name in ordered {
+        if let Some(c) = components.iter().find(|c| c.name == name) {
+            match c.kind {
+                AsdfComponentKind::File => files.push(c.path.clone()),
+                AsdfComponentKind::Module => collect_source_files(&c.components, files),
+                _ => ()
+            }
+        }
+    }
+}
+
+///
+/// Orders the given names and their transitive dependencies so that dependencies come first.
+/// Ties are broken by the given order, cycles are ignored.
+///
+fn topological_order(names: &[String], dependencies: &dyn Fn(&str) -> Vec<String>) -> Vec<String> {
+    fn visit(name: &str, dependencies: &dyn Fn(&str) -> Vec<String>, visited: &mut HashSet<String>, ordered: &mut Vec<String>) {
+        if !visited.insert(name.to_string()) {
+            return;
+        }
+        for dep in dependencies(name) {
+            visit(&dep, dependencies, visited, ordered);
+        }
+        ordered.push(name.to_string());
+    }
+
+    let mut visited = HashSet::new();
+    let mut ordered = vec![];
+    for name in names {
+        visit(name, dependencies, &mut visited, &mut ordered);
+    }
+    ordered
+}
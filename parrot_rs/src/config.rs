@@ -1,6 +1,6 @@
 use directories::{BaseDirs, ProjectDirs, UserDirs};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::Path};
+use std::{fs, path::Path, collections::BTreeMap};
 
 use crate::{BackendError, models::CustomHighlight};
 
@@ -8,7 +8,16 @@ use crate::{BackendError, models::CustomHighlight};
 pub struct Config {
     pub path_to_sbcl: Option<String>,
     pub path_to_core: Option<String>,
+    // the address Slynk listens on, the port is chosen by the image and read from slynk_port_file()
     pub slynk_socket: String,
+    // how long to wait for the image to start Slynk, 10s if None
+    pub slynk_startup_timeout_ms: Option<u64>,
+    // start the image again when it exits unexpectedly, e.g. on heap exhaustion
+    #[serde(default)]
+    pub restart_on_crash: bool,
+    // load the files, systems and forms of the previous session after the image is (re)started
+    #[serde(default)]
+    pub replay_session_on_restart: bool,
 
     pub vim_mode: bool,
     pub vim_esc: Option<String>,
@@ -20,7 +29,148 @@ pub struct Config {
     pub shortcut_slurp_forward: Option<String>,
     pub shortcut_slurp_backward: Option<String>,
     pub show_line_numbers: bool,
-    pub custom_highlights: Vec<CustomHighlight>
+    pub custom_highlights: Vec<CustomHighlight>,
+
+    // named ways to start a Lisp image, path_to_sbcl and path_to_core are used if none is active
+    #[serde(default)]
+    pub launch_profiles: Vec<LaunchProfile>,
+    pub active_launch_profile: Option<String>
+}
+impl Config {
+    ///
+    /// The active profile, or an SBCL profile built from path_to_sbcl and path_to_core.
+    ///
+    pub fn active_profile(&self) -> Option<LaunchProfile> {
+        let named = self.active_launch_profile
+            .as_ref()
+            .and_then(|name| self.launch_profiles.iter().find(|p| &p.name == name));
+        match named {
+            Some(p) => Some(p.clone()),
+            None => self.path_to_sbcl.as_ref().map(|sbcl| LaunchProfile {
+                name: "default".to_string(),
+                implementation: Implementation::Sbcl,
+                executable: sbcl.clone(),
+                core: self.path_to_core.clone(),
+                ..LaunchProfile::default()
+            })
+        }
+    }
+    ///
+    /// Adds the profile, replacing the one with the same name.
+    ///
+    pub fn set_launch_profile(&mut self, profile: LaunchProfile) {
+        match self.launch_profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.launch_profiles.push(profile)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub enum Implementation {
+    #[default]
+    Sbcl,
+    Ccl,
+    Ecl
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LaunchProfile {
+    pub name: String,
+    pub implementation: Implementation,
+    pub executable: String,
+    // SBCL and CCL only
+    pub core: Option<String>,
+    // passed before the forms that load slynk
+    #[serde(default)]
+    pub args: Vec<String>,
+    // e.g. SBCL_HOME
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    // in MB
+    pub dynamic_space_size: Option<usize>,
+    pub working_directory: Option<String>,
+    // evaluated after slynk is started, e.g. (ql:quickload :my-app)
+    #[serde(default)]
+    pub init_forms: Vec<String>
+}
+impl LaunchProfile {
+    ///
+    /// The command line to start the image and load the given start-slynk.lisp, following the
+    /// implementation's conventions, e.g. SBCL's runtime options have to come first.
+    ///
+    pub fn command_args(&self, slynk_start_path: &str) -> Vec<String> {
+        let mut args = self.runtime_args();
+        // a fatal error, e.g. heap exhaustion, exits instead of waiting in ldb, so that the crash is noticed
+        if self.implementation == Implementation::Sbcl {
+            args.extend(["--lose-on-corruption".to_string(), "--disable-ldb".to_string()]);
+        }
+        args.extend(self.args.iter().cloned());
+        args.extend(["--load".to_string(), slynk_start_path.to_string()]);
+        for form in &self.init_forms {
+            args.extend(["--eval".to_string(), form.clone()]);
+        }
+        args
+    }
+    ///
+    /// The command line to run the given script without Slynk and without a REPL, after the init forms,
+    /// e.g. to build an image in a separate process. The process quits after loading the script, its
+    /// stdin should be closed so that an error does not wait in the debugger.
+    ///
+    pub fn batch_args(&self, script_path: &str) -> Vec<String> {
+        let mut args = self.runtime_args();
+        args.extend(self.args.iter().cloned());
+        match self.implementation {
+            // no debugger, quits at the end
+            Implementation::Sbcl => args.push("--non-interactive".to_string()),
+            Implementation::Ccl => args.push("--batch".to_string()),
+            Implementation::Ecl => {}
+        }
+        for form in &self.init_forms {
+            args.extend(["--eval".to_string(), form.clone()]);
+        }
+        args.extend(["--load".to_string(), script_path.to_string()]);
+        // CCL and ECL enter the REPL after the load
+        match self.implementation {
+            Implementation::Sbcl => {},
+            Implementation::Ccl => args.extend(["--eval".to_string(), "(ccl:quit)".to_string()]),
+            Implementation::Ecl => args.extend(["--eval".to_string(), "(ext:quit)".to_string()])
+        }
+        args
+    }
+    ///
+    /// A profile starting the given core saved from an image of this one, the init forms are part of the core.
+    ///
+    pub fn with_core(&self, name: &str, core: &str) -> LaunchProfile {
+        LaunchProfile {
+            name: name.to_string(),
+            core: Some(core.to_string()),
+            init_forms: vec![],
+            ..self.clone()
+        }
+    }
+    // core and heap size
+    fn runtime_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        let (core, heap) = match self.implementation {
+            Implementation::Sbcl => ("--core", "--dynamic-space-size"),
+            Implementation::Ccl => ("--image-name", "--heap-reserve"),
+            Implementation::Ecl => ("", "--heap-size")
+        };
+        if let (Some(c), false) = (&self.core, core.is_empty()) {
+            args.extend([core.to_string(), c.clone()]);
+        }
+        if let Some(mb) = self.dynamic_space_size {
+            let size = match self.implementation {
+                Implementation::Sbcl => format!("{}MB", mb),
+                Implementation::Ccl => format!("{}M", mb),
+                // in KB
+                Implementation::Ecl => (mb * 1024).to_string()
+            };
+            args.extend([heap.to_string(), size]);
+        }
+        args
+    }
 }
 
 fn config_path() -> Result<String, BackendError> {
@@ -61,6 +211,9 @@ pub fn create_config_if_not_existing() {
             path_to_sbcl: None,
             path_to_core: None,
             slynk_socket: "127.0.0.1:4005".to_string(),
+            slynk_startup_timeout_ms: Some(10000),
+            restart_on_crash: false,
+            replay_session_on_restart: false,
             vim_mode: false,
             vim_esc: None,
             shortcut_compile_and_load_file: Some(String::from("Shift-Ctrl-l")),
@@ -70,7 +223,9 @@ pub fn create_config_if_not_existing() {
             shortcut_slurp_forward: Some(String::from("Ctrl-ArrowRight")),
             shortcut_slurp_backward: Some(String::from("Ctrl-ArrowLeft")),
             show_line_numbers: false,
-            custom_highlights: vec![]
+            custom_highlights: vec![],
+            launch_profiles: vec![],
+            active_launch_profile: None
         };
 
         write_config(&config).unwrap();
@@ -97,3 +252,15 @@ pub fn get_data_dir() -> Result<String, BackendError> {
     }
     Err(BackendError("Application's data dir not found".to_string()))
 }
+
+///
+/// The file the image writes its Slynk port to, one per parrot process so that several can run side by side.
+///
+pub fn slynk_port_file() -> Result<String, BackendError> {
+    let data_dir = get_data_dir()?;
+    Path::new(&data_dir)
+        .join(format!("slynk-{}.port", std::process::id()))
+        .to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| BackendError("could not build port file path".to_string()))
+}
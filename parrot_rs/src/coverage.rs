@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use sexp::Sexp;
+
+use crate::config::{self, Implementation, LaunchProfile};
+use crate::image::SETUP_QUICKLISP;
+use crate::models::{CoverageRange, CoverageReport, CoverageRequest, CoverageState, FileCoverage, LineCoverage, LineStatus, TestLocation};
+use crate::parsing::{sexp_list_nth, sexp_list_nth_as_string, sexp_list_nth_as_usize};
+use crate::runner::run_script;
+use crate::testing::{test_run_from_script_output, test_script_form};
+use crate::text::escape_form;
+use crate::{BackendError, BackendResult};
+
+// the line the coverage data is printed on, see coverage_script
+const COVERAGE_MARKER: &str = ";; parrot coverage: ";
+// printed instead if sb-cover has no print-report function to take the data from
+const UNSUPPORTED_MARKER: &str = ";; parrot coverage unsupported: ";
+
+///
+/// A script that compiles the system with sb-cover's instrumentation, runs the tests and prints
+/// the coverage of each instrumented file as (file ((line start end state) ...)). The html report
+/// is written to the folder.
+///
+pub fn coverage_script(request: &CoverageRequest, report_dir: &str) -> BackendResult<String> {
+    let system = escape_form(&request.system);
+    // sb-cover does not export the state of each character of a file, it is taken from the
+    // arguments of its internal function printing the html report, looked up at run time as
+    // reading an unknown symbol of the locked package would fail
+    Ok(format!(r#"{}
+(require :sb-cover)
+(defvar cl-user::*parrot-coverage* (list))
+(let ((print-report (find-symbol "PRINT-REPORT" :sb-cover)))
+  (unless (and print-report (fboundp print-report))
+    (format t "~&{}~A~%" (lisp-implementation-version))
+    (uiop:quit 1))
+  (sb-int:encapsulate print-report 'parrot
+    (lambda (print-report stream file counts states source)
+      (push (list (namestring file) states source) cl-user::*parrot-coverage*)
+      (funcall print-report stream file counts states source))))
+(proclaim '(optimize sb-cover:store-coverage-data))
+(asdf:load-system "{}" :force '("{}"))
+(proclaim '(optimize (sb-cover:store-coverage-data 0)))
+(handler-case {}
+  (error (e) (format t "~&~A~%" e)))
+(sb-cover:report "{}")
+(flet ((runs (states source)
+         ;; (line start end state) for each part of a line with the same state, leaving out
+         ;; blanks and what is not instrumented
+         (let ((runs (list)) (line 0) (column 0) (start 0) (state nil) (blank t))
+           (flet ((end-run ()
+                    (when (and state (not blank) (not (member state '(0 15))))
+                      (push (list line start column state) runs))))
+             (loop for char across source
+                   for char-state across states
+                   do (cond ((char= char #\Newline)
+                             (end-run)
+                             (setf state nil blank t column 0)
+                             (incf line))
+                            (t
+                             (unless (eql char-state state)
+                               (end-run)
+                               (setf state char-state start column blank t))
+                             (unless (member char '(#\Space #\Tab))
+                               (setf blank nil))
+                             (incf column))))
+             (end-run))
+           (nreverse runs))))
+  (let ((*print-pretty* nil))
+    (format t "~&{}~S~%"
+            (mapcar (lambda (file) (list (first file) (runs (second file) (third file))))
+                    cl-user::*parrot-coverage*))))
+"#, SETUP_QUICKLISP, UNSUPPORTED_MARKER, system, system, test_script_form(&request.tests)?, escape_form(report_dir), COVERAGE_MARKER))
+}
+
+///
+/// Reads the data printed by coverage_script.
+///
+pub fn parse_coverage(data: &str) -> BackendResult<Vec<FileCoverage>> {
+    let sexp = sexp::parse(data.trim())
+        .map_err(|e| BackendError(format!("Failed to parse sexp: {}.", e.message)))?;
+    let files = match &sexp {
+        Sexp::List(files) => files,
+        // nil, nothing was instrumented
+        _ => return Ok(vec![])
+    };
+    files
+        .iter()
+        .map(|f| {
+            let ranges = match sexp_list_nth(f, 1)? {
+                Sexp::List(runs) => runs
+                    .iter()
+                    .map(|r| Ok(CoverageRange {
+                        line: sexp_list_nth_as_usize(r, 0)?,
+                        start: sexp_list_nth_as_usize(r, 1)?,
+                        end: sexp_list_nth_as_usize(r, 2)?,
+                        state: coverage_state(sexp_list_nth_as_usize(r, 3)?)
+                    }))
+                    .collect::<BackendResult<Vec<CoverageRange>>>()?,
+                _ => vec![]
+            };
+            Ok(FileCoverage { file: sexp_list_nth_as_string(f, 0)?, lines: line_coverage(&ranges), ranges })
+        })
+        .collect()
+}
+
+///
+/// Compiles the system with coverage instrumentation in a new process started from the profile
+/// and runs the tests. The files are keyed by their document path, files outside the project,
+/// e.g. of dependencies compiled for the first time, are left out.
+///
+pub fn run_coverage(
+    profile: &LaunchProfile,
+    request: &CoverageRequest,
+    locations: &[TestLocation],
+    document_path: impl Fn(&str) -> Option<String>
+) -> BackendResult<CoverageReport> {
+    if profile.implementation != Implementation::Sbcl {
+        return Err(BackendError("Coverage is collected with sb-cover, which requires SBCL.".to_string()));
+    }
+    let report_dir = Path::new(&config::get_data_dir()?)
+        .join("coverage")
+        .join(request.system.replace(['/', '\\'], "-"));
+    fs::create_dir_all(&report_dir)
+        .map_err(|e| BackendError(format!("Could not create {}: {}", report_dir.display(), e)))?;
+    // sb-cover:report expects a directory pathname
+    let html_report = format!("{}/", report_dir.to_str().unwrap_or_default().trim_end_matches(['/', '\\']));
+
+    let (_, output) = run_script(profile, &coverage_script(request, &html_report)?, "coverage")?;
+    if let Some(unsupported) = output.find(UNSUPPORTED_MARKER) {
+        let version = output[unsupported + UNSUPPORTED_MARKER.len()..].lines().next().unwrap_or_default();
+        return Err(BackendError(format!(
+            "Coverage is not supported with SBCL {}: sb-cover has no print-report function to read the coverage from.",
+            version.trim()
+        )));
+    }
+    // the script failed before the report if there is no marker
+    let marker = output
+        .rfind(COVERAGE_MARKER)
+        .ok_or_else(|| BackendError(format!("Could not collect coverage:\n{}", last_lines(&output, 20))))?;
+    let data = output[marker + COVERAGE_MARKER.len()..].lines().next().unwrap_or_default();
+    let files = parse_coverage(data)?
+        .into_iter()
+        .filter_map(|f| document_path(&f.file).map(|path| (path, f)))
+        .collect::<BTreeMap<String, FileCoverage>>();
+    Ok(CoverageReport {
+        system: request.system.clone(),
+        files,
+        tests: test_run_from_script_output(&request.tests, &output[..marker], locations),
+        html_report
+    })
+}
+
+///
+/// The report in the LCOV tracefile format. sb-cover does not count executions, lines and branches
+/// that ran are reported as hit once.
+///
+pub fn to_lcov(report: &CoverageReport) -> String {
+    let mut lcov = String::new();
+    for file in report.files.values() {
+        lcov.push_str(&format!("TN:{}\nSF:{}\n", report.system, file.file));
+        let mut branches = (0, 0);
+        for (block, range) in file.ranges.iter().enumerate() {
+            if let CoverageState::Branch { first_taken, second_taken } = range.state {
+                for (branch, taken) in [first_taken, second_taken].iter().enumerate() {
+                    lcov.push_str(&format!("BRDA:{},{},{},{}\n", range.line + 1, block, branch, *taken as u8));
+                    branches.0 += 1;
+                    branches.1 += *taken as usize;
+                }
+            }
+        }
+        lcov.push_str(&format!("BRF:{}\nBRH:{}\n", branches.0, branches.1));
+        for line in &file.lines {
+            lcov.push_str(&format!("DA:{},{}\n", line.line + 1, (line.status != LineStatus::NotCovered) as u8));
+        }
+        let hit = file.lines.iter().filter(|l| l.status != LineStatus::NotCovered).count();
+        lcov.push_str(&format!("LF:{}\nLH:{}\nend_of_record\n", file.lines.len(), hit));
+    }
+    lcov
+}
+
+fn last_lines(output: &str, n: usize) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    lines[lines.len().saturating_sub(n)..].join("\n")
+}
+
+// the state is a bit mask, 1: executed, 2: not executed, for branches shifted by 2 for the second one
+fn coverage_state(state: usize) -> CoverageState {
+    if state & 12 == 0 {
+        CoverageState::Expression { executed: state & 3 == 1 }
+    } else {
+        CoverageState::Branch { first_taken: state & 3 == 1, second_taken: (state >> 2) & 3 == 1 }
+    }
+}
+
+fn line_coverage(ranges: &[CoverageRange]) -> Vec<LineCoverage> {
+    // line -> (something ran, something did not)
+    let mut lines: BTreeMap<usize, (bool, bool)> = BTreeMap::new();
+    for range in ranges {
+        let (ran, missed) = match range.state {
+            CoverageState::Expression { executed } => (executed, !executed),
+            CoverageState::Branch { first_taken, second_taken } => (first_taken || second_taken, !(first_taken && second_taken))
+        };
+        let line = lines.entry(range.line).or_insert((false, false));
+        line.0 |= ran;
+        line.1 |= missed;
+    }
+    lines
+        .into_iter()
+        .map(|(line, ran_missed)| LineCoverage {
+            line,
+            status: match ran_missed {
+                (true, false) => LineStatus::Covered,
+                (true, true) => LineStatus::Partial,
+                _ => LineStatus::NotCovered
+            }
+        })
+        .collect()
+}
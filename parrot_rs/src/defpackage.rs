@@ -0,0 +1,244 @@
+use crate::{
+    reader::{is_potential_number, read_all, Form, FormKind},
+    tree::get_all_lisp_file_contents_in_folder_as_iter,
+    BackendResult,
+};
+
+///
+/// Find the top-level defpackage (or uiop:define-package) form defining the given package,
+/// by its name or one of its nicknames.
+///
+pub fn find_defpackage<'a>(forms: &'a [Form], package: &str) -> Option<&'a Form> {
+    forms
+        .iter()
+        .map(|f| match &f.kind {
+            FormKind::Conditional { positive: true, form, .. } => form.as_ref(),
+            _ => f
+        })
+        .find(|f| defines_package(f, package))
+}
+
+///
+/// Adds the given symbols to the `:export` clause of the package's defpackage form.
+/// New entries are written in the style of the existing ones, symbols that are already exported are skipped.
+/// Returns None if the text contains no defpackage form for the package.
+///
+pub fn add_exports(text: &str, package: &str, symbols: &[String]) -> Option<String> {
+    let forms = read_all(text);
+    let defpackage = find_defpackage(&forms, package)?;
+    let items = defpackage.as_list()?;
+    let clauses = export_clauses(items);
+
+    let mut exported = clauses
+        .iter()
+        .flat_map(|c| c.as_list().unwrap()[1..].iter())
+        .filter_map(|e| e.string_designator())
+        .collect::<Vec<String>>();
+    let mut names = vec![];
+    for s in symbols {
+        let name = symbol_name_of(s);
+        if !exported.contains(&name) {
+            exported.push(name.clone());
+            names.push(name);
+        }
+    }
+    if names.is_empty() {
+        return Some(text.to_string());
+    }
+
+    let mut updated = text.to_string();
+    match clauses.last().and_then(|c| c.as_list()) {
+        Some(clause) => {
+            let style = clause.get(1).map(|e| entry_style(e, text)).unwrap_or_default();
+            let last = clause.last().unwrap();
+            // entries on their own lines are continued on new lines, otherwise appended to the line
+            let separator = if clause.len() > 2 && line_of(text, clause[1].start) != line_of(text, last.start) {
+                format!("\n{}", " ".repeat(column_of(text, last.start)))
+            } else {
+                " ".to_string()
+            };
+            let insert = names
+                .iter()
+                .map(|n| format!("{}{}", separator, style.format(n)))
+                .collect::<String>();
+            updated.insert_str(last.end, &insert);
+        },
+        None => {
+            let last = items.last().unwrap();
+            let indent = if items.len() > 2 {
+                column_of(text, last.start)
+            } else {
+                column_of(text, defpackage.start) + 2
+            };
+            // align the entries after "(:export "
+            let separator = format!("\n{}", " ".repeat(indent + 9));
+            let entries = names
+                .iter()
+                .map(|n| EntryStyle::default().format(n))
+                .collect::<Vec<String>>()
+                .join(&separator);
+            updated.insert_str(last.end, &format!("\n{}(:export {})", " ".repeat(indent), entries));
+        }
+    }
+    Some(updated)
+}
+
+///
+/// Removes the given symbols from all `:export` clauses of the package's defpackage form.
+/// A clause that has no entries left is removed.
+/// Returns None if the text contains no defpackage form for the package.
+///
+pub fn remove_exports(text: &str, package: &str, symbols: &[String]) -> Option<String> {
+    let names = symbols.iter().map(|s| symbol_name_of(s)).collect::<Vec<String>>();
+    let mut updated = text.to_string();
+    find_defpackage(&read_all(&updated), package)?;
+
+    // remove one entry at a time, as removing an entry changes the offsets of the following ones
+    loop {
+        let forms = read_all(&updated);
+        let items = find_defpackage(&forms, package).and_then(|f| f.as_list()).unwrap();
+        let mut range = None;
+        for (clause_ix, clause) in items.iter().enumerate() {
+            if !clause.head_is_keyword("export") {
+                continue;
+            }
+            let entries = clause.as_list().unwrap();
+            if let Some(ix) = (1..entries.len()).find(|ix| entries[*ix].string_designator().map(|n| names.contains(&n)).unwrap_or(false)) {
+                range = Some(if entries.len() == 2 {
+                    removal_range(&updated, items, clause_ix)
+                } else {
+                    removal_range(&updated, entries, ix)
+                });
+                break;
+            }
+        }
+        match range {
+            Some((start, end)) => updated.replace_range(start..end, ""),
+            None => break
+        }
+    }
+    Some(updated)
+}
+
+///
+/// Updates the `:export` clause of the package's defpackage form in the first .lisp file of the folder that defines the package.
+/// Returns the path of the file containing the defpackage form, or None if no file in the folder defines the package.
+///
+pub fn update_exports_in_folder(folder: &str, package: &str, add: &[String], remove: &[String]) -> BackendResult<Option<String>> {
+    for (path, content) in get_all_lisp_file_contents_in_folder_as_iter(folder) {
+        if find_defpackage(&read_all(&content), package).is_none() {
+            continue;
+        }
+        let added = add_exports(&content, package, add).unwrap();
+        let updated = remove_exports(&added, package, remove).unwrap();
+        if updated != content {
+            std::fs::write(&path, updated)?;
+        }
+        return Ok(Some(path.replace('\\', "/")));
+    }
+    Ok(None)
+}
+
+//
+// private functions
+//
+
+fn defines_package(form: &Form, package: &str) -> bool {
+    if !form.head_is("defpackage") && !form.head_is("define-package") {
+        return false;
+    }
+    let items = form.as_list().unwrap();
+    let is_name = |f: &Form| f.string_designator().map(|n| n.eq_ignore_ascii_case(package)).unwrap_or(false);
+    if items.get(1).map(is_name).unwrap_or(false) {
+        return true;
+    }
+    items
+        .iter()
+        .filter(|c| c.head_is_keyword("nicknames"))
+        .any(|c| c.as_list().unwrap()[1..].iter().any(is_name))
+}
+
+fn export_clauses(items: &[Form]) -> Vec<&Form> {
+    items.iter().filter(|c| c.head_is_keyword("export")).collect()
+}
+
+// "my-fun", "#:my-fun", ":my-fun", "pkg::my-fun" -> "MY-FUN", "|Weird|" -> "Weird"
+fn symbol_name_of(symbol: &str) -> String {
+    read_all(symbol)
+        .first()
+        .and_then(|f| f.string_designator())
+        .unwrap_or_else(|| symbol.to_string())
+}
+
+// the byte range to remove so that the item at ix disappears together with the whitespace before it
+fn removal_range(text: &str, items: &[Form], ix: usize) -> (usize, usize) {
+    let item = &items[ix];
+    if ix == 1 && items.len() > 2 {
+        // first entry, the next entry moves up to its place
+        return (item.start, items[2].start);
+    }
+    let prev_end = items[ix - 1].end;
+    let line_start = text[..item.start].rfind('\n').unwrap_or(0);
+    let on_own_line = text[line_start..item.start].trim().is_empty();
+    let start = if on_own_line { line_start.max(prev_end) } else { prev_end };
+    (start, item.end)
+}
+
+fn line_of(text: &str, pos: usize) -> usize {
+    text[..pos].matches('\n').count()
+}
+
+fn column_of(text: &str, pos: usize) -> usize {
+    let line_start = text[..pos].rfind('\n').map(|ix| ix + 1).unwrap_or(0);
+    text[line_start..pos].chars().count()
+}
+
+#[derive(Default)]
+enum DesignatorKind {
+    #[default]
+    Uninterned,
+    Keyword,
+    Symbol,
+    Str
+}
+
+#[derive(Default)]
+struct EntryStyle {
+    kind: DesignatorKind,
+    upcase: bool
+}
+
+impl EntryStyle {
+    fn format(&self, name: &str) -> String {
+        let printed = match self.kind {
+            DesignatorKind::Str => return format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\"")),
+            _ if needs_escape(name) => format!("|{}|", name.replace('\\', "\\\\").replace('|', "\\|")),
+            _ if self.upcase => name.to_string(),
+            _ => name.to_lowercase()
+        };
+        match self.kind {
+            DesignatorKind::Uninterned => format!("#:{}", printed),
+            DesignatorKind::Keyword => format!(":{}", printed),
+            _ => printed
+        }
+    }
+}
+
+fn entry_style(entry: &Form, text: &str) -> EntryStyle {
+    let written = entry.text(text);
+    let kind = match &entry.kind {
+        FormKind::Str(_) => DesignatorKind::Str,
+        _ if written.starts_with("#:") => DesignatorKind::Uninterned,
+        _ if written.starts_with(':') => DesignatorKind::Keyword,
+        _ => DesignatorKind::Symbol
+    };
+    let upcase = !written.contains('|') && written.chars().any(|c| c.is_uppercase()) && !written.chars().any(|c| c.is_lowercase());
+    EntryStyle { kind, upcase }
+}
+
+// names that do not read back as themselves when written in lower case without bars
+fn needs_escape(name: &str) -> bool {
+    name.is_empty()
+        || is_potential_number(name)
+        || name.chars().any(|c| c.is_lowercase() || c.is_whitespace() || "()\"';|:\\`,#".contains(c))
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use crossbeam::{unbounded, Receiver, Sender};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::file::normalize_path;
+use crate::models::{CompilerNotes, Location, LocationBuffer};
+
+///
+/// Severities as reported by Slynk, ordered from most to least severe.
+///
+pub const SEVERITIES: [&str; 6] = ["error", "read-error", "warning", "style-warning", "note", "redefinition"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeverityGroup {
+    pub severity: String,
+    pub notes: Vec<CompilerNotes>
+}
+
+///
+/// Sent to subscribers whenever the notes of a file changed.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsUpdate {
+    pub file: String,
+    pub notes: Vec<CompilerNotes>
+}
+
+///
+/// Collects the compiler notes of all compilations (files, forms, systems), per file.
+/// Notes that could not be associated with a file are stored under their buffer name.
+///
+pub struct Diagnostics {
+    notes: HashMap<String, Vec<CompilerNotes>>,
+    subscribers: Vec<Sender<DiagnosticsUpdate>>
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self {
+            notes: HashMap::new(),
+            subscribers: vec![]
+        }
+    }
+
+    ///
+    /// Replaces all notes of the given files with the given notes, e.g. after a file or system has been recompiled.
+    /// Files the new notes refer to are treated as recompiled as well.
+    ///
+    pub fn replace_notes_for_files(&mut self, files: &[String], notes: Vec<CompilerNotes>) {
+        let grouped = group_by_source(notes);
+        let touched = files
+            .iter()
+            .map(normalize_path)
+            .chain(grouped.keys().cloned())
+            .unique()
+            .collect::<Vec<String>>();
+        for f in &touched {
+            self.notes.remove(f);
+        }
+        for (key, file_notes) in grouped {
+            self.notes.insert(key, file_notes);
+        }
+        for f in &touched {
+            self.notify(f);
+        }
+    }
+
+    ///
+    /// Replaces the notes of a file or unnamed buffer in the given (0-based, inclusive) line range, e.g. after a single
+    /// top-level form has been recompiled.
+    ///
+    pub fn replace_notes_for_lines(&mut self, file: &str, from_line: usize, to_line: usize, notes: Vec<CompilerNotes>) {
+        let key = normalize_path(file);
+        if let Some(file_notes) = self.notes.get_mut(&key) {
+            file_notes.retain(|n| !matches!(n.line, Some(l) if l >= from_line && l <= to_line));
+        }
+        let grouped = group_by_source(notes);
+        let mut touched = vec![key];
+        for (key, mut new_notes) in grouped {
+            self.notes
+                .entry(key.clone())
+                .or_default()
+                .append(&mut new_notes);
+            touched.push(key);
+        }
+        for f in touched.into_iter().unique() {
+            self.notify(&f);
+        }
+    }
+
+    pub fn clear_file(&mut self, file: &str) {
+        let key = normalize_path(file);
+        if self.notes.remove(&key).is_some() {
+            self.notify(&key);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        let keys = self.notes.keys().cloned().collect::<Vec<String>>();
+        self.notes.clear();
+        for k in keys {
+            self.notify(&k);
+        }
+    }
+
+    pub fn get_notes_for_file(&self, file: &str) -> Vec<CompilerNotes> {
+        self.notes
+            .get(&normalize_path(file))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn get_files(&self) -> Vec<String> {
+        self.notes
+            .iter()
+            .filter(|(_, notes)| !notes.is_empty())
+            .map(|(f, _)| f.clone())
+            .sorted()
+            .collect()
+    }
+
+    ///
+    /// Returns all notes, grouped by severity (most severe first) and sorted by file and line.
+    ///
+    pub fn get_grouped_by_severity(&self) -> Vec<SeverityGroup> {
+        let mut groups: Vec<SeverityGroup> = vec![];
+        for note in self.notes.values().flatten() {
+            match groups.iter_mut().find(|g| g.severity == note.severity) {
+                Some(g) => g.notes.push(note.clone()),
+                None => groups.push(SeverityGroup { severity: note.severity.clone(), notes: vec![note.clone()] })
+            }
+        }
+        for g in groups.iter_mut() {
+            g.notes.sort_by(|a, b| (&a.file, a.line, a.col).cmp(&(&b.file, b.line, b.col)));
+        }
+        groups.sort_by_key(|g| severity_rank(&g.severity));
+        groups
+    }
+
+    pub fn count_by_severity(&self) -> HashMap<String, usize> {
+        self.notes
+            .values()
+            .flatten()
+            .counts_by(|n| n.severity.clone())
+    }
+
+    ///
+    /// Get a receiver that gets notified whenever the notes of a file changed.
+    ///
+    pub fn subscribe(&mut self) -> Receiver<DiagnosticsUpdate> {
+        let (sender, receiver) = unbounded::<DiagnosticsUpdate>();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    fn notify(&mut self, file: &str) {
+        let update = DiagnosticsUpdate {
+            file: file.to_string(),
+            notes: self.get_notes_for_file(file)
+        };
+        // drop subscribers whose receiver is gone
+        self.subscribers.retain(|s| s.send(update.clone()).is_ok());
+    }
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///
+/// The file a note belongs to, or the buffer name if there is no file.
+///
+pub fn note_source(note: &CompilerNotes) -> String {
+    if let Some(file) = &note.file {
+        return normalize_path(file);
+    }
+    match &note.location {
+        Location::Location { buffer: LocationBuffer::Buffer(name), .. } => name.clone(),
+        Location::Location { buffer: LocationBuffer::BufferAndFile { file, .. }, .. }
+        | Location::Location { buffer: LocationBuffer::File(file), .. } => normalize_path(file),
+        _ => String::new()
+    }
+}
+
+fn group_by_source(notes: Vec<CompilerNotes>) -> HashMap<String, Vec<CompilerNotes>> {
+    notes.into_iter().into_group_map_by(note_source)
+}
+
+fn severity_rank(severity: &str) -> usize {
+    SEVERITIES
+        .iter()
+        .position(|s| *s == severity)
+        .unwrap_or(SEVERITIES.len())
+}
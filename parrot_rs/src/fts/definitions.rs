@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::models::FlexCompletion;
+use crate::reader::{read_all, included_forms, Form};
+
+///
+/// A top-level definition found in a source file, e.g. (defun make-point (x y) ...).
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Definition {
+    // the symbol's name as the reader would intern it, e.g. MAKE-POINT
+    pub name: String,
+    // classified like slynk-completion does, e.g. fn, generic-fn, macro, var, constant, cla, type or pak, test for unit tests
+    pub kind: String,
+    // the package of the last (in-package ...) before the definition, None if there is none
+    pub package: Option<String>,
+    // 0-based
+    pub line: usize,
+    // e.g. "(make-point x y)" for functions and macros
+    pub arglist: Option<String>
+}
+
+// defining operator -> kind
+const DEFINERS: [(&str, &str); 15] = [
+    ("defun", "fn"),
+    ("defgeneric", "generic-fn"),
+    ("defmethod", "generic-fn"),
+    ("defmacro", "macro"),
+    ("define-compiler-macro", "macro"),
+    ("defvar", "var"),
+    ("defparameter", "var"),
+    ("define-symbol-macro", "var"),
+    ("defconstant", "constant"),
+    ("defclass", "cla"),
+    ("define-condition", "cla"),
+    ("defstruct", "cla"),
+    ("deftype", "type"),
+    ("defpackage", "pak"),
+    ("define-package", "pak")
+];
+
+// unit test definer -> kind, kept apart as e.g. (test ...) is not a definition in other projects
+const TEST_DEFINERS: [(&str, &str); 4] = [
+    // FiveAM, Parachute, Rove and FiveAM's short form
+    ("def-test", "test"),
+    ("define-test", "test"),
+    ("deftest", "test"),
+    ("test", "test")
+];
+
+///
+/// Reads the definitions of a source file. The file is not evaluated, so definitions
+/// expanded from user macros and accessors generated by defclass/defstruct are not found.
+///
+pub fn read_definitions(text: &str) -> Vec<Definition> {
+    read_with_definers(text, &DEFINERS)
+}
+
+///
+/// Reads the unit tests defined in a source file with FiveAM, Parachute or Rove, their kind is "test".
+///
+pub fn read_test_definitions(text: &str) -> Vec<Definition> {
+    read_with_definers(text, &TEST_DEFINERS)
+}
+
+fn read_with_definers(text: &str, definers: &[(&str, &str)]) -> Vec<Definition> {
+    let forms = read_all(text);
+    let mut package = None;
+    let mut definitions = vec![];
+    for form in included_forms(&forms) {
+        if form.head_is("in-package") {
+            package = form.as_list().unwrap().get(1).and_then(|p| p.string_designator());
+            continue;
+        }
+        let items = match form.as_list() {
+            Some(items) if items.len() > 1 => items,
+            _ => continue
+        };
+        let kind = match definers.iter().find(|(definer, _)| items[0].is_symbol(definer)) {
+            Some((_, kind)) => kind,
+            None => continue
+        };
+        // (defstruct (point (:conc-name p-)) x y), (test (addition :suite arithmetic) ...)
+        let name_form = match items[1].as_list() {
+            Some(options) if items[0].is_symbol("defstruct") || *kind == "test" => match options.first() {
+                Some(n) => n,
+                None => continue
+            },
+            _ => &items[1]
+        };
+        let name = match name_form.string_designator() {
+            Some(name) => name,
+            // e.g. (defun (setf point-x) ...)
+            None => continue
+        };
+        let arglist = match *kind {
+            "fn" | "macro" | "generic-fn" => items
+                .iter()
+                .skip(2)
+                // method qualifiers come before the lambda list
+                .find(|f| f.as_list().is_some())
+                .map(|lambda_list| arglist(&name, lambda_list, text)),
+            _ => None
+        };
+        definitions.push(Definition {
+            name,
+            kind: kind.to_string(),
+            package: package.clone(),
+            line: text[..form.start].matches('\n').count(),
+            arglist
+        });
+    }
+    definitions
+}
+
+///
+/// Flex-matches the pattern against the given definitions, like slynk-completion:flex-completions does in the image.
+/// Definitions in the given package (CL-USER if None) are completed unqualified, the ones of other packages qualified.
+/// A qualified pattern, e.g. "my-app::ma", only matches definitions in that package.
+///
+pub fn complete<'a, I: Iterator<Item = &'a Definition>>(definitions: I, pattern: &str, package: Option<&str>, limit: usize) -> Vec<FlexCompletion> {
+    let current = package.unwrap_or("COMMON-LISP-USER");
+    let (qualifier, name_pattern) = match pattern.find(':') {
+        Some(ix) => (Some(&pattern[..ix]), pattern[ix..].trim_start_matches(':')),
+        None => (None, pattern)
+    };
+    if name_pattern.is_empty() {
+        return vec![];
+    }
+    let upcase = pattern.chars().all(|c| !c.is_lowercase());
+
+    // (package, name) -> kinds, arglist
+    let mut found: HashMap<(String, String), (Vec<String>, Option<String>)> = HashMap::new();
+    for d in definitions {
+        let def_package = d.package.clone().unwrap_or_else(|| "COMMON-LISP-USER".to_string());
+        if let Some(q) = qualifier {
+            if !same_package(q, &def_package) {
+                continue;
+            }
+        }
+        let entry = found
+            .entry((def_package, d.name.clone()))
+            .or_insert_with(|| (vec![], None));
+        if !entry.0.contains(&d.kind) {
+            entry.0.push(d.kind.clone());
+        }
+        if entry.1.is_none() {
+            entry.1 = d.arglist.clone();
+        }
+    }
+
+    let mut completions = vec![];
+    // e.g. "my-app::"
+    let written_qualifier = &pattern[..pattern.len() - name_pattern.len()];
+    for ((def_package, name), (kinds, arglist)) in found {
+        let (symbol, offset) = match qualifier {
+            Some(_) => (format!("{}{}", written_qualifier, name), written_qualifier.chars().count()),
+            None if same_package(current, &def_package) => (name.clone(), 0),
+            None => (format!("{}::{}", def_package, name), def_package.chars().count() + 2)
+        };
+        let indexes = match flex_match(name_pattern, &name) {
+            Some(indexes) => indexes,
+            None => continue
+        };
+        let mut score = name_pattern.len() as f64 / name.len() as f64 / chunk_count(&indexes) as f64;
+        if offset > 0 && qualifier.is_none() {
+            score /= 2.0;
+        }
+        let symbol = if upcase { symbol.to_uppercase() } else { symbol.to_lowercase() };
+        let chunks = to_chunks(&symbol, &indexes.iter().map(|i| i + offset).collect::<Vec<usize>>());
+        let symbol_type = kinds.join(",");
+        completions.push(FlexCompletion { symbol, prob: score, chunks, symbol_type, kinds, arglist });
+    }
+    completions
+        .into_iter()
+        .sorted_by(|a, b| b.prob.partial_cmp(&a.prob).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.symbol.cmp(&b.symbol)))
+        .take(limit)
+        .collect()
+}
+
+///
+/// The live completions, followed by the ones of the index that the image does not know (yet),
+/// e.g. definitions in files that have not been loaded.
+///
+pub fn merge_completions(live: Vec<FlexCompletion>, offline: Vec<FlexCompletion>) -> Vec<FlexCompletion> {
+    let mut merged = live;
+    for c in offline {
+        if !merged.iter().any(|m| m.symbol.eq_ignore_ascii_case(&c.symbol)) {
+            merged.push(c);
+        }
+    }
+    merged
+}
+
+fn same_package(a: &str, b: &str) -> bool {
+    let normalized = |p: &str| match p.to_uppercase().as_str() {
+        "CL-USER" => "COMMON-LISP-USER".to_string(),
+        "CL" => "COMMON-LISP".to_string(),
+        p => p.to_string()
+    };
+    normalized(a) == normalized(b)
+}
+
+// "(x y &optional z)" -> "(make-point x y &optional z)"
+fn arglist(name: &str, lambda_list: &Form, text: &str) -> String {
+    let args = lambda_list.text(text);
+    let args = args.strip_prefix('(').unwrap_or(args);
+    let args = args.strip_suffix(')').unwrap_or(args).split_whitespace().join(" ");
+    if args.is_empty() {
+        format!("({})", name.to_lowercase())
+    } else {
+        format!("({} {})", name.to_lowercase(), args)
+    }
+}
+
+// the char indexes of name matching the pattern's chars in order, case-insensitive
+fn flex_match(pattern: &str, name: &str) -> Option<Vec<usize>> {
+    let mut indexes = vec![];
+    let mut chars = name.chars().enumerate();
+    for p in pattern.chars() {
+        let (ix, _) = chars.find(|(_, c)| c.eq_ignore_ascii_case(&p))?;
+        indexes.push(ix);
+    }
+    Some(indexes)
+}
+
+fn chunk_count(indexes: &[usize]) -> usize {
+    1 + indexes.windows(2).filter(|w| w[1] != w[0] + 1).count()
+}
+
+// like slynk-completion::to-chunks, ("farfalhini", [1, 3, 4]) -> [(1, "a"), (3, "fa")]
+fn to_chunks(symbol: &str, indexes: &[usize]) -> Vec<(usize, String)> {
+    let chars: Vec<char> = symbol.chars().collect();
+    let mut chunks: Vec<(usize, String)> = vec![];
+    for &ix in indexes {
+        let c = match chars.get(ix) {
+            Some(c) => *c,
+            None => continue
+        };
+        match chunks.last_mut() {
+            Some((start, text)) if *start + text.chars().count() == ix => text.push(c),
+            _ => chunks.push((ix, c.to_string()))
+        }
+    }
+    chunks
+}
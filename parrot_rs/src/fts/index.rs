@@ -13,6 +13,8 @@ use crate::error::BackendError;
 use crate::tree::{get_all_lisp_files_in_folder_as_iter};
 use crate::{ config, text::*, BackendResult };
 use super::models::*;
+use super::definitions::{Definition, read_definitions, read_test_definitions, complete};
+use crate::models::{FlexCompletion, TestLocation};
 
 pub const NGRAM_SIZE: usize = 3;
 
@@ -28,6 +30,10 @@ pub struct Index {
     pub index_file_names: HashMap<u64, Vec<DocId>>,
     // maps doc ids to document content
     pub index_file_contents: HashMap<DocId, String>,
+    // maps doc ids to the top-level definitions in the document, for completion without a running image
+    pub definitions: HashMap<DocId, Vec<Definition>>,
+    // maps doc ids to the unit tests defined in the document
+    pub tests: HashMap<DocId, Vec<Definition>>,
 
 }
 impl Index {
@@ -38,6 +44,8 @@ impl Index {
             doc_ids: HashMap::new(),
             index_file_names: HashMap::new(),
             index_file_contents: HashMap::new(),
+            definitions: HashMap::new(),
+            tests: HashMap::new(),
         }
     }
 
@@ -48,6 +56,8 @@ impl Index {
 
         if let Some((docs_id, _)) = self.get_document(file_path) {
             self.index_file_contents.retain(|doc_id, _| docs_id.ne(doc_id));
+            self.definitions.remove(&docs_id);
+            self.tests.remove(&docs_id);
             let _ = self.index_file_content(file_path, docs_id);
         } else {
             println!("WARN: Doc for path {} not found.", file_path);
@@ -110,6 +120,8 @@ impl Index {
     pub fn remove_document(&mut self, docs_id: DocId) -> Document {
             // 1. delete the file in content token -> file index
             self.index_file_contents.retain(|doc_id, _| docs_id.ne(doc_id));
+            self.definitions.remove(&docs_id);
+            self.tests.remove(&docs_id);
 
             // 2. delete the file in name token -> file index
             for (_, doc_list) in self.index_file_names.iter_mut() {
@@ -164,6 +176,8 @@ impl Index {
         let root_pth = Path::new(&rc);
         self.index_file_names.clear();
         self.index_file_contents.clear();
+        self.definitions.clear();
+        self.tests.clear();
         self.documents.clear();
         self.doc_ids.clear();
 
@@ -312,6 +326,51 @@ impl Index {
     }
 
 
+    ///
+    /// Completes the pattern from the definitions in the project's source files,
+    /// see definitions::complete.
+    ///
+    pub fn complete_definitions(&self, pattern: &str, package: Option<&str>, limit: usize) -> Vec<FlexCompletion> {
+        complete(self.definitions.values().flatten(), pattern, package, limit)
+    }
+
+    ///
+    /// The path of the file relative to the project folder, None if it is not one of the indexed documents.
+    ///
+    pub fn document_path(&self, file: &str) -> Option<String> {
+        let root_folder = self.root_folder.as_ref()?;
+        let rel_path = Path::new(&normalize_path(file))
+            .strip_prefix(normalize_path(root_folder))
+            .ok()?
+            .to_str()?
+            .replace('\\', "/");
+        self.doc_ids.contains_key(&rel_path).then_some(rel_path)
+    }
+
+    ///
+    /// Where the tests defined in the project's source files are, with deftest, define-test, test ...
+    ///
+    pub fn test_locations(&self) -> Vec<TestLocation> {
+        let root_folder = match &self.root_folder {
+            Some(root_folder) => root_folder,
+            None => return vec![]
+        };
+        self.tests
+            .iter()
+            .filter_map(|(doc_id, tests)| self.documents.get(doc_id).map(|doc| (doc, tests)))
+            .flat_map(|(doc, tests)| tests
+                .iter()
+                .map(|d| TestLocation {
+                    name: d.name.clone(),
+                    package: d.package.clone(),
+                    file: Path::new(root_folder).join(&doc.path).to_str().unwrap_or_default().to_string(),
+                    line: d.line
+                }))
+            .sorted_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)))
+            .collect()
+    }
+
+
     //
     // private functions
     //
@@ -330,6 +389,8 @@ impl Index {
 
     fn index_file_content<P: AsRef<Path>>(&mut self, fpath: P, doc_id: DocId) -> String {
         let content = fs::read_to_string(fpath.as_ref()).unwrap();
+        self.definitions.insert(doc_id, read_definitions(&content));
+        self.tests.insert(doc_id, read_test_definitions(&content));
         self.index_file_contents
             .entry(doc_id)
             .or_insert(content.clone());
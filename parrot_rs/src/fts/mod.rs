@@ -1,3 +1,4 @@
 pub mod index;
+pub mod definitions;
 pub mod models;
 mod tests;
@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, Implementation, LaunchProfile};
+use crate::models::SessionStep;
+use crate::runner::run_script;
+use crate::session::replay_form_without_slynk;
+use crate::text::escape_form;
+use crate::{BackendError, BackendResult};
+
+///
+/// How to save an image, with save-lisp-and-die on SBCL and save-application on CCL.
+///
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ImageOptions {
+    // the absolute path of the core or executable to write
+    pub output: String,
+    // a standalone executable including the runtime instead of a core
+    pub executable: bool,
+    // executables only, the function called on startup, e.g. my-app:main, an integer it returns is the exit code
+    pub toplevel: Option<String>,
+    // SBCL only, the runtime has to be built with :sb-core-compression
+    #[serde(default)]
+    pub compression: bool,
+    // e.g. 9 for zstd, the implementation's default if None
+    pub compression_level: Option<i32>,
+    // SBCL only, keeps e.g. --dynamic-space-size and passes all command line arguments to the toplevel
+    #[serde(default)]
+    pub save_runtime_options: bool,
+    // cores only, adds a launch profile with this name that starts the saved core
+    pub launch_profile: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImageBuildResult {
+    pub success: bool,
+    pub output: String,
+    // what the building process wrote to stdout & stderr
+    pub log: Vec<String>
+}
+
+///
+/// A script that loads the recorded session into a fresh image and saves it. Steps that fail are
+/// reported in the log and do not stop the build.
+///
+pub fn session_image_script(steps: &[SessionStep], options: &ImageOptions, implementation: &Implementation) -> BackendResult<String> {
+    let save = save_form(options, implementation)?;
+    Ok(format!("{}\n(format t \"~A~%\" {})\n{}\n", SETUP_QUICKLISP, replay_form_without_slynk(steps), save))
+}
+
+///
+/// A script that loads the ASDF system, with Quicklisp if it is installed to fetch the dependencies,
+/// and saves the image. Any error aborts the build.
+///
+pub fn system_image_script(system: &str, options: &ImageOptions, implementation: &Implementation) -> BackendResult<String> {
+    let save = save_form(options, implementation)?;
+    let load = format!(
+        r#"(if (find-package "QL") (uiop:symbol-call :ql :quickload "{0}") (asdf:load-system "{0}"))"#,
+        escape_form(system));
+    Ok(format!("{}\n{}\n{}\n", SETUP_QUICKLISP, load, save))
+}
+
+///
+/// Runs the script in a new process started from the profile, the running image is not touched
+/// as saving kills the process.
+///
+pub fn build_image(profile: &LaunchProfile, script: &str, output: &str) -> BackendResult<ImageBuildResult> {
+    let (success, log) = run_script(profile, script, "build-image")?;
+    Ok(ImageBuildResult {
+        success: success && Path::new(output).exists(),
+        output: output.to_string(),
+        log: log.lines().map(|l| l.to_string()).collect()
+    })
+}
+
+///
+/// Adds a launch profile starting the saved core, based on the profile that built it.
+///
+pub fn add_core_launch_profile(profile: &LaunchProfile, name: &str, core: &str) -> BackendResult<()> {
+    let mut config = config::read_config()?;
+    config.set_launch_profile(profile.with_core(name, core));
+    config::write_config(&config)
+}
+
+// loads quicklisp if it is installed and not part of the core
+pub const SETUP_QUICKLISP: &str = r#"(require :asdf)
+(let ((setup (merge-pathnames "quicklisp/setup.lisp" (user-homedir-pathname))))
+  (when (and (not (find-package "QL")) (probe-file setup))
+    (load setup)))"#;
+
+fn save_form(options: &ImageOptions, implementation: &Implementation) -> BackendResult<String> {
+    let output = escape_form(&options.output);
+    // the symbol is read once everything is loaded
+    let toplevel = |prologue: &str, exit: &str| options.toplevel.as_ref().filter(|_| options.executable).map(|t| format!(
+        r#"(let ((main (read-from-string "{}"))) (lambda () {}(let ((code (funcall main))) ({} (if (integerp code) code 0)))))"#,
+        escape_form(t), prologue, exit));
+    match implementation {
+        Implementation::Sbcl => {
+            let mut args = format!(r#""{}" :executable {}"#, output, if options.executable { "t" } else { "nil" });
+            if options.compression {
+                args.push_str(&format!(" :compression {}", options.compression_level.map(|l| l.to_string()).unwrap_or_else(|| "t".to_string())));
+            }
+            if options.save_runtime_options {
+                args.push_str(" :save-runtime-options t");
+            }
+            if let Some(toplevel) = toplevel("(sb-ext:disable-debugger) ", "sb-ext:exit :code") {
+                args.push_str(&format!(" :toplevel {}", toplevel));
+            }
+            Ok(format!("(sb-ext:save-lisp-and-die {})", args))
+        },
+        Implementation::Ccl => {
+            if options.compression {
+                return Err(BackendError("CCL cannot compress images.".to_string()));
+            }
+            let mut args = format!(r#""{}""#, output);
+            if options.executable {
+                args.push_str(" :prepend-kernel t");
+            }
+            if let Some(toplevel) = toplevel("", "ccl:quit") {
+                args.push_str(&format!(" :toplevel-function {}", toplevel));
+            }
+            Ok(format!("(ccl:save-application {})", args))
+        },
+        Implementation::Ecl => Err(BackendError("ECL cannot save images, build executables with asdf:make instead.".to_string()))
+    }
+}
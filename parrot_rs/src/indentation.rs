@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{ArgIndent, IndentSpec};
+
+// columns body forms are indented by, relative to the opening paren
+const BODY_INDENT: usize = 2;
+// columns distinguished arguments and lambda lists are indented by
+const SPECIAL_INDENT: usize = 4;
+
+///
+/// Maps operator names to their indentation.
+/// Starts with the standard Common Lisp operators, the specs Slynk sends with :indentation-update
+/// for macros defined in the image are merged into it.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndentationTable {
+    specs: HashMap<String, IndentSpec>
+}
+
+impl IndentationTable {
+    pub fn new() -> Self {
+        Self {
+            specs: builtin_specs()
+                .into_iter()
+                .map(|(name, spec)| (name.to_string(), spec))
+                .collect()
+        }
+    }
+
+    ///
+    /// Adds or replaces the specs of the given operators.
+    ///
+    pub fn update(&mut self, specs: Vec<(String, IndentSpec)>) {
+        for (name, spec) in specs {
+            self.specs.insert(name.to_lowercase(), spec);
+        }
+    }
+
+    ///
+    /// Get the spec of the given operator, its package prefix is ignored.
+    /// Operators without a spec that start with def, with- or do- are indented like
+    /// defun, with-open-file and dolist respectively, as in cl-indent.el.
+    ///
+    pub fn get(&self, operator: &str) -> Option<IndentSpec> {
+        let name = operator.rsplit(':').next().unwrap_or(operator).to_lowercase();
+        if let Some(spec) = self.specs.get(&name) {
+            return Some(spec.clone());
+        }
+        if name.starts_with("def") {
+            Some(defun_spec())
+        } else if name.starts_with("with-") || name.starts_with("do-") {
+            Some(IndentSpec::Body(1))
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for IndentationTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///
+/// The column a line starting at the given byte offset should be indented to.
+/// Returns None if the offset is inside a string or a block comment, whose lines must not be reindented.
+///
+pub fn indent_column(text: &str, pos: usize, table: &IndentationTable) -> Option<usize> {
+    let mut scanner = Scanner::new();
+    scanner.feed(&text[..pos]);
+    // the line break before the line ends any symbol or comment
+    scanner.feed("\n");
+    scanner.indent_column(table)
+}
+
+///
+/// Reindents all lines of the given text but the first one.
+/// Lines inside strings and block comments are left untouched, blank lines are emptied.
+///
+pub fn reindent(text: &str, table: &IndentationTable) -> String {
+    let mut scanner = Scanner::new();
+    let mut out = String::with_capacity(text.len());
+    for (ix, line) in text.split('\n').enumerate() {
+        let indented = if ix == 0 {
+            line.to_string()
+        } else {
+            scanner.feed("\n");
+            out.push('\n');
+            let trimmed = line.trim_start();
+            match scanner.indent_column(table) {
+                Some(_) if trimmed.is_empty() => String::new(),
+                Some(col) => format!("{}{}", " ".repeat(col), trimmed),
+                None => line.to_string()
+            }
+        };
+        scanner.feed(&indented);
+        out.push_str(&indented);
+    }
+    out
+}
+
+//
+// private functions
+//
+
+fn defun_spec() -> IndentSpec {
+    IndentSpec::Args(vec![ArgIndent::Offset(SPECIAL_INDENT), ArgIndent::Lambda, ArgIndent::Body])
+}
+
+fn builtin_specs() -> Vec<(&'static str, IndentSpec)> {
+    use ArgIndent::{Lambda, Offset, Rest};
+    use IndentSpec::*;
+
+    let mut specs = vec![
+        ("block", Body(1)),
+        ("case", Body(1)),
+        ("ccase", Body(1)),
+        ("ecase", Body(1)),
+        ("typecase", Body(1)),
+        ("ctypecase", Body(1)),
+        ("etypecase", Body(1)),
+        ("catch", Body(1)),
+        ("defclass", Args(vec![Offset(6), Offset(4), Offset(2), Rest(Box::new(Offset(2)))])),
+        ("define-condition", Args(vec![Offset(6), Offset(4), Offset(2), Rest(Box::new(Offset(2)))])),
+        ("defconstant", Args(vec![Offset(4), Offset(2), Offset(2)])),
+        ("defparameter", Args(vec![Offset(4), Offset(2), Offset(2)])),
+        ("defvar", Args(vec![Offset(4), Offset(2), Offset(2)])),
+        ("defgeneric", Args(vec![Offset(4), Offset(4), ArgIndent::Body])),
+        ("defpackage", Args(vec![Offset(4), Rest(Box::new(Offset(2)))])),
+        ("defstruct", Args(vec![Offset(4), Rest(Box::new(Offset(2)))])),
+        ("destructuring-bind", Args(vec![Offset(6), Offset(4), ArgIndent::Body])),
+        ("multiple-value-bind", Args(vec![Offset(6), Offset(4), ArgIndent::Body])),
+        ("do", Body(2)),
+        ("do*", Body(2)),
+        ("dolist", Body(1)),
+        ("dotimes", Body(1)),
+        ("eval-when", Body(1)),
+        ("flet", Body(1)),
+        ("labels", Body(1)),
+        ("macrolet", Body(1)),
+        ("symbol-macrolet", Body(1)),
+        ("handler-bind", Body(1)),
+        ("handler-case", Args(vec![Offset(4), Rest(Box::new(Offset(2)))])),
+        ("restart-case", Args(vec![Offset(4), Rest(Box::new(Offset(2)))])),
+        ("restart-bind", Body(1)),
+        ("ignore-errors", Body(0)),
+        ("lambda", Args(vec![Lambda, ArgIndent::Body])),
+        ("let", Body(1)),
+        ("let*", Body(1)),
+        ("locally", Body(0)),
+        ("multiple-value-prog1", Body(1)),
+        ("multiple-value-call", Body(1)),
+        ("print-unreadable-object", Body(1)),
+        ("prog", Body(1)),
+        ("prog*", Body(1)),
+        ("prog1", Body(1)),
+        ("prog2", Body(2)),
+        ("progn", Body(0)),
+        ("progv", Body(2)),
+        ("return-from", Body(1)),
+        ("tagbody", Body(0)),
+        ("the", Body(1)),
+        ("throw", Body(1)),
+        ("unless", Body(1)),
+        ("unwind-protect", Args(vec![Offset(5), ArgIndent::Body])),
+        ("when", Body(1)),
+        ("with-accessors", Body(2)),
+        ("with-slots", Body(2)),
+        ("with-standard-io-syntax", Body(0)),
+        ("with-compilation-unit", Body(1)),
+    ];
+    for name in ["defun", "defmacro", "define-compiler-macro", "define-setf-expander", "deftype", "defmethod", "defsetf"] {
+        specs.push((name, defun_spec()));
+    }
+    specs
+}
+
+struct Frame {
+    // column of the opening paren
+    column: usize,
+    // quoted lists and vectors are data, e.g. '(a b) or #(1 2)
+    data: bool,
+    // line and column where each element starts
+    elements: Vec<(usize, usize)>,
+    // the first element, if it is a symbol
+    operator: Option<String>
+}
+
+enum State {
+    Code,
+    Str { escaped: bool },
+    LineComment,
+    BlockComment { depth: usize, prev: char },
+    // a # at the start of an element, the next char decides what it starts
+    Hash { line: usize, column: usize },
+    Atom { text: String, head: bool, escaped: bool, in_bars: bool }
+}
+
+// reads just enough Lisp to know the open lists and where their elements start
+struct Scanner {
+    frames: Vec<Frame>,
+    state: State,
+    line: usize,
+    column: usize,
+    // start of the element a prefix like ', ` or #' belongs to, and whether it quotes it
+    prefix: Option<(usize, usize, bool)>
+}
+
+impl Scanner {
+    fn new() -> Self {
+        Self {
+            frames: vec![],
+            state: State::Code,
+            line: 0,
+            column: 0,
+            prefix: None
+        }
+    }
+
+    fn feed(&mut self, text: &str) {
+        for c in text.chars() {
+            self.process(c);
+            if c == '\n' {
+                self.line += 1;
+                self.column = 0;
+            } else {
+                self.column += 1;
+            }
+        }
+    }
+
+    fn process(&mut self, c: char) {
+        match &mut self.state {
+            State::Str { escaped } => {
+                if *escaped {
+                    *escaped = false;
+                } else if c == '\\' {
+                    *escaped = true;
+                } else if c == '"' {
+                    self.state = State::Code;
+                }
+            },
+            State::LineComment => {
+                if c == '\n' {
+                    self.state = State::Code;
+                }
+            },
+            State::BlockComment { depth, prev } => {
+                if *prev == '|' && c == '#' {
+                    *depth -= 1;
+                    *prev = ' ';
+                    if *depth == 0 {
+                        self.state = State::Code;
+                    }
+                } else if *prev == '#' && c == '|' {
+                    *depth += 1;
+                    *prev = ' ';
+                } else {
+                    *prev = c;
+                }
+            },
+            State::Hash { line, column } => {
+                let (line, column) = (*line, *column);
+                self.state = State::Code;
+                match c {
+                    '|' => self.state = State::BlockComment { depth: 1, prev: ' ' },
+                    '\'' => self.prefix = Some(self.prefix.unwrap_or((line, column, false))),
+                    '(' => {
+                        self.prefix = Some(self.prefix.map(|(l, c, _)| (l, c, true)).unwrap_or((line, column, true)));
+                        self.process(c);
+                    },
+                    // feature expressions are read as elements of their own
+                    '+' | '-' => (),
+                    _ => {
+                        let head = self.start_element(line, column);
+                        self.state = State::Atom { text: format!("#{}", c), head, escaped: c == '\\', in_bars: false };
+                    }
+                }
+            },
+            State::Atom { text, escaped, in_bars, head } => {
+                if *escaped {
+                    *escaped = false;
+                    text.push(c);
+                } else if c == '\\' {
+                    *escaped = true;
+                    text.push(c);
+                } else if c == '|' {
+                    *in_bars = !*in_bars;
+                    text.push(c);
+                } else if *in_bars || !is_delimiter(c) {
+                    text.push(c);
+                } else {
+                    if *head {
+                        let operator = text.clone();
+                        if let Some(f) = self.frames.last_mut() {
+                            f.operator = Some(operator);
+                        }
+                    }
+                    self.state = State::Code;
+                    self.process(c);
+                }
+            },
+            State::Code => match c {
+                ';' => self.state = State::LineComment,
+                '"' => {
+                    self.start_element(self.line, self.column);
+                    self.state = State::Str { escaped: false };
+                },
+                '#' => self.state = State::Hash { line: self.line, column: self.column },
+                '\'' | '`' | ',' => {
+                    if self.prefix.is_none() {
+                        self.prefix = Some((self.line, self.column, c != ','));
+                    }
+                },
+                // ,@
+                '@' if self.prefix.is_some() => (),
+                '(' => {
+                    let quoted = matches!(self.prefix, Some((_, _, true)));
+                    self.start_element(self.line, self.column);
+                    let data = quoted || self.frames.last().map(|f| f.data).unwrap_or(false);
+                    self.frames.push(Frame { column: self.column, data, elements: vec![], operator: None });
+                },
+                ')' => {
+                    self.frames.pop();
+                    self.prefix = None;
+                },
+                _ if c.is_whitespace() => (),
+                _ => {
+                    let head = self.start_element(self.line, self.column);
+                    self.state = State::Atom { text: c.to_string(), head, escaped: c == '\\', in_bars: c == '|' };
+                }
+            }
+        }
+    }
+
+    // records the start of an element in the innermost list, returns whether it is the list's first element
+    fn start_element(&mut self, line: usize, column: usize) -> bool {
+        let prefix = self.prefix.take();
+        match self.frames.last_mut() {
+            Some(f) => {
+                f.elements.push(prefix.map(|(l, c, _)| (l, c)).unwrap_or((line, column)));
+                f.elements.len() == 1 && prefix.is_none()
+            },
+            None => false
+        }
+    }
+
+    fn indent_column(&self, table: &IndentationTable) -> Option<usize> {
+        if matches!(self.state, State::Str { .. } | State::BlockComment { .. }) {
+            return None;
+        }
+        let frame = match self.frames.last() {
+            Some(f) => f,
+            None => return Some(0)
+        };
+        let first = match frame.elements.first() {
+            Some(e) => e,
+            None => return Some(frame.column + 1)
+        };
+        let operator = match &frame.operator {
+            Some(op) if !frame.data && !op.starts_with(':') => op,
+            // data and lists starting with a list, string or keyword are aligned with their first element
+            _ => return Some(first.1)
+        };
+        // like a function call, aligned with the first argument if it is on the operator's line
+        let normal = match frame.elements.get(1) {
+            Some((line, column)) if *line == first.0 => *column,
+            _ => first.1
+        };
+        let arg_ix = frame.elements.len() - 1;
+        let indent = match table.get(operator) {
+            Some(IndentSpec::Body(n)) if arg_ix < n => ArgIndent::Offset(SPECIAL_INDENT),
+            Some(IndentSpec::Body(_)) => ArgIndent::Body,
+            Some(IndentSpec::Args(args)) => arg_indent(&args, arg_ix),
+            None => ArgIndent::Normal
+        };
+        Some(column_for(&indent, frame.column, normal))
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || "()\"';`,".contains(c)
+}
+
+// the indentation of the argument at ix
+fn arg_indent(args: &[ArgIndent], ix: usize) -> ArgIndent {
+    for (k, arg) in args.iter().enumerate() {
+        match arg {
+            ArgIndent::Rest(_) | ArgIndent::Body => return arg.clone(),
+            _ if k == ix => return arg.clone(),
+            _ => ()
+        }
+    }
+    ArgIndent::Normal
+}
+
+fn column_for(indent: &ArgIndent, paren_column: usize, normal: usize) -> usize {
+    match indent {
+        ArgIndent::Normal => normal,
+        ArgIndent::Offset(n) => paren_column + n,
+        ArgIndent::Lambda => paren_column + SPECIAL_INDENT,
+        ArgIndent::Body => paren_column + BODY_INDENT,
+        ArgIndent::Rest(inner) => column_for(inner, paren_column, normal)
+    }
+}
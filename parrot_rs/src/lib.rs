@@ -19,6 +19,17 @@ pub mod text;
 pub mod models;
 pub mod state;
 pub mod parsing;
+pub mod diagnostics;
+pub mod reader;
+pub mod asdf;
+pub mod defpackage;
+pub mod indentation;
+pub mod session;
+pub mod image;
+pub mod runner;
+pub mod testing;
+pub mod coverage;
+pub mod watch;
 
 #[derive(Serialize, Debug, PartialOrd, PartialEq)]
 pub enum ConfigValueStatus {
@@ -38,6 +49,10 @@ pub struct ConfigDiagnostics {
 
 pub fn get_config_diagnostics() -> ConfigDiagnostics {
     let c = config::read_config().unwrap();
+    // a named profile replaces the SBCL paths, its core is optional
+    if let Some(profile) = c.active_profile().filter(|p| c.active_launch_profile.as_ref() == Some(&p.name)) {
+        return profile_diagnostics(&profile, &c.slynk_socket);
+    }
     let path_to_sbcl = match c.path_to_sbcl {
         Some(p) => { 
             if Path::new(&p).exists() {
@@ -73,6 +88,31 @@ pub fn get_config_diagnostics() -> ConfigDiagnostics {
         slynk_socket
     }
 }
+fn profile_diagnostics(profile: &config::LaunchProfile, socket: &str) -> ConfigDiagnostics {
+    let path_status = |p: &str| if Path::new(p).exists() {
+        ConfigValueStatus::Ok(p.to_string())
+    } else {
+        ConfigValueStatus::ValueInvalid(p.to_string())
+    };
+    let path_to_sbcl = path_status(&profile.executable);
+    let path_to_core = match &profile.core {
+        Some(core) => path_status(core),
+        None => ConfigValueStatus::Ok(String::new())
+    };
+    let slynk_socket = if socket.parse::<SocketAddr>().is_ok() {
+        ConfigValueStatus::Ok(socket.to_string())
+    } else {
+        ConfigValueStatus::ValueInvalid(socket.to_string())
+    };
+    ConfigDiagnostics {
+        ok: matches!(path_to_core, ConfigValueStatus::Ok(_))
+            && matches!(path_to_sbcl, ConfigValueStatus::Ok(_))
+            && matches!(slynk_socket, ConfigValueStatus::Ok(_)),
+        path_to_sbcl,
+        path_to_core,
+        slynk_socket
+    }
+}
 pub fn set_sbcl_path(path: &str) -> BackendResult<()> {
     config::create_config_if_not_existing();
     let mut config = config::read_config()?;
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::clone::Clone;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct IdNamePair {
@@ -45,16 +46,177 @@ pub type SymbolName = String;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum SymbolType {
-    Macro,
     Function,
+    GenericFunction,
+    Macro,
+    SpecialVar,
+    Constant,
     Class,
-    Var
+    Struct,
+    Condition,
+    Type
+}
+
+///
+/// What the image knows about a symbol, one section for each kind of definition it names.
+///
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SymbolDescription {
+    pub name: String,
+    // None for uninterned symbols
+    pub package: Option<String>,
+    // the text of slynk:documentation-symbol
+    pub summary: String,
+    pub function: Option<FunctionDescription>,
+    pub variable: Option<VariableDescription>,
+    pub class: Option<ClassDescription>,
+    // types defined with deftype
+    pub type_info: Option<TypeDescription>
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FunctionDescription {
+    // function, generic-function, macro or special-operator
+    pub kind: String,
+    pub lambda_list: Option<String>,
+    // e.g. (function (list) (values t &optional))
+    pub derived_type: Option<String>,
+    pub documentation: Option<String>,
+    pub source_file: Option<String>,
+    // the output of describe for the function
+    pub description: Option<String>
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VariableDescription {
+    // e.g. special, constant or global
+    pub kind: String,
+    // the printed value, None if unbound
+    pub value: Option<String>,
+    pub declared_type: Option<String>,
+    pub documentation: Option<String>,
+    pub description: Option<String>
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClassDescription {
+    pub superclasses: Vec<String>,
+    pub subclasses: Vec<String>,
+    pub slots: Vec<String>,
+    pub documentation: Option<String>,
+    pub description: Option<String>
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TypeDescription {
+    pub expansion: Option<String>,
+    pub documentation: Option<String>
+}
+
+///
+/// A class as seen through the MOP, the names (printed in the REPL's package)
+/// can be passed back to get e.g. a superclass or a reader's generic function.
+///
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClassInfo {
+    pub name: String,
+    // e.g. standard-class, structure-class or condition-class
+    pub metaclass: String,
+    // the precedence list and effective slots are empty if the class cannot be finalized yet
+    pub finalized: bool,
+    pub precedence_list: Vec<String>,
+    pub direct_superclasses: Vec<String>,
+    pub direct_subclasses: Vec<String>,
+    pub direct_slots: Vec<SlotInfo>,
+    pub effective_slots: Vec<SlotInfo>,
+    // generic functions with a method specialized on the class
+    pub generic_functions: Vec<String>,
+    pub documentation: Option<String>
+}
+impl ClassInfo {
+    // the effective slot if the class is finalized, the direct one otherwise
+    pub fn slot(&self, name: &str) -> Option<&SlotInfo> {
+        self.effective_slots
+            .iter()
+            .chain(self.direct_slots.iter())
+            .find(|s| s.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SlotInfo {
+    pub name: String,
+    // instance or class
+    pub allocation: String,
+    pub initargs: Vec<String>,
+    pub initform: Option<String>,
+    pub slot_type: String,
+    // for effective slots, the accessors defined in any class of the precedence list
+    pub readers: Vec<String>,
+    pub writers: Vec<String>,
+    pub documentation: Option<String>
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GenericFunctionInfo {
+    pub name: String,
+    pub lambda_list: String,
+    pub documentation: Option<String>,
+    pub methods: Vec<MethodInfo>
+}
+impl GenericFunctionInfo {
+    // methods with the class as one of their specializers
+    pub fn methods_specialized_on(&self, class: &str) -> Vec<&MethodInfo> {
+        self.methods
+            .iter()
+            .filter(|m| m.specializers.iter().any(|s| matches!(s, Specializer::Class(c) if c.eq_ignore_ascii_case(class))))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MethodInfo {
+    // e.g. [":around"], empty for primary methods
+    pub qualifiers: Vec<String>,
+    // one per required parameter
+    pub specializers: Vec<Specializer>,
+    pub lambda_list: String,
+    pub documentation: Option<String>,
+    pub source_file: Option<String>
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum Specializer {
+    // the class name, t for unspecialized parameters
+    Class(String),
+    // the printed object of (eql object)
+    Eql(String)
+}
+
+///
+/// A symbol found by apropos, for each kind of definition it names,
+/// e.g. a symbol naming a function and a variable is found twice.
+///
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AproposHit {
+    // e.g. ALEXANDRIA:FLATTEN or MY-APP::HELPER
+    pub designator: String,
+    // e.g. function, generic-function, macro, variable, class or type
+    pub kind: String,
+    // first line of the docstring
+    pub documentation: Option<String>
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Symbol {
+    // the symbol's name as returned by symbol-name, i.e. without |escapes|
     pub name: String,
-    pub stype: SymbolType 
+    // a symbol can name e.g. both a function and a class
+    pub kinds: Vec<SymbolType>,
+    pub exported: bool,
+    // the first line of the docstring
+    pub doc: Option<String>,
+    pub arglist: Option<String>
 }
 
 ///
@@ -113,8 +275,40 @@ pub enum SlynkAnswer {
         prompt: String,
         initial_value: Option<String>
     },
-    IndentationUpdate,
+    // (symbol name, indentation) of macros defined in the image
+    IndentationUpdate {
+        specs: Vec<(String, IndentSpec)>
+    },
     NewFeatures,
+    // (ed ...) called in the image, None for (ed)
+    Ed {
+        target: Option<EdTarget>
+    },
+    // (slynk:inspect-in-emacs ...), thread and tag are set if the image waits for the inspector to be closed
+    Inspect {
+        content: InspectorContent,
+        thread: Option<usize>,
+        tag: Option<usize>
+    },
+    YOrNP {
+        thread: usize,
+        tag: usize,
+        question: String
+    },
+    BackgroundMessage {
+        message: String
+    },
+    // answered with :emacs-pong by the REPL itself
+    Ping {
+        thread: usize,
+        tag: usize
+    },
+    InvalidRpc {
+        continuation: usize,
+        message: String
+    },
+    // events that are not handled yet
+    Unknown(String),
     // added, not from Slynk
     Notify {
         text: String,
@@ -123,8 +317,102 @@ pub enum SlynkAnswer {
     ResolvePending {
         continuation: usize,
         data: String
+    },
+    SystemOperation {
+        system: String,
+        operation: AsdfOperation,
+        stage: SystemOperationStage
+    },
+    // a line of output written while (ql:quickload ...) is running
+    QuickloadProgress {
+        systems: Vec<String>,
+        line: String
+    },
+    // the Lisp process ended without being asked to, code is None if it was killed by a signal
+    ProcessExited {
+        code: Option<i32>,
+        heap_exhausted: bool,
+        // the last lines the process wrote to stdout & stderr
+        output: Vec<String>
+    },
+    // the watch list after the watches were evaluated
+    WatchesUpdated(Vec<Watch>)
+
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum EdTarget {
+    File {
+        filename: String,
+        line: Option<usize>,
+        column: Option<usize>,
+        position: Option<usize>
+    },
+    FunctionName(String)
+}
+
+///
+/// How the arguments of an operator are indented, as in cl-indent.el.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum IndentSpec {
+    // the number of distinguished arguments before the body, e.g. 1 for when
+    Body(usize),
+    // the indentation of each argument, e.g. (4 &lambda &body) for defun
+    Args(Vec<ArgIndent>)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ArgIndent {
+    // nil, indented like a function argument
+    Normal,
+    // indented by the given number of columns relative to the opening paren
+    Offset(usize),
+    // &lambda, a lambda list
+    Lambda,
+    // &body, this and all following arguments are body forms
+    Body,
+    // &rest, this and all following arguments are indented the same way
+    Rest(Box<ArgIndent>)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InspectorContent {
+    pub title: String,
+    pub id: usize,
+    pub parts: Vec<InspectorPart>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum InspectorPart {
+    Text(String),
+    Label(String),
+    // index of the part in the inspector, to inspect it further
+    Value {
+        text: String,
+        index: usize
+    },
+    Action {
+        label: String,
+        index: usize
     }
+}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AsdfOperation {
+    Load,
+    Compile,
+    Test
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SystemOperationStage {
+    Started,
+    Finished {
+        success: bool,
+        duration: f64,
+        notes: usize
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -143,8 +431,71 @@ pub struct DebugFrame {
 pub struct CompilerNotes {
     pub message: String,
     pub severity: String,
+    pub location: Location,
+    pub references: Vec<CompilerNoteReference>,
+    pub source_context: Option<String>,
+
+    // resolved from the location, if possible
     pub file: Option<String>,
-    pub position: Option<usize>
+    pub position: Option<usize>,
+    pub line: Option<usize>,
+    pub col: Option<usize>
+}
+
+///
+/// A reference to a manual, e.g. (:ansi-cl :section (3 2 2 3)) or (:sbcl :node "Handling of Types")
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompilerNoteReference {
+    // e.g. "ansi-cl", "sbcl", "amop"
+    pub manual: String,
+    // e.g. "section", "node", "glossary", "function"
+    pub kind: String,
+    // e.g. "3.2.2.3", "Handling of Types"
+    pub name: String
+}
+
+///
+/// Slynk's location format: (:location <buffer> <position> <hints>) or (:error <message>)
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Location {
+    Location {
+        buffer: LocationBuffer,
+        position: LocationPosition,
+        snippet: Option<String>
+    },
+    Error(String)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LocationBuffer {
+    File(String),
+    Buffer(String),
+    BufferAndFile {
+        buffer: String,
+        file: String
+    },
+    SourceForm(String)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LocationPosition {
+    // 1-based character position
+    Position(usize),
+    // start of the compiled string in the buffer + 0-based offset inside the string
+    Offset {
+        start: usize,
+        offset: usize
+    },
+    // 1-based line
+    Line {
+        line: usize,
+        col: Option<usize>
+    },
+    FunctionName(String),
+    Eof,
+    Unknown
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -189,20 +540,28 @@ pub struct DebugCondition {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SlynkMessage {
     Eval(String),
-    InteractiveEval(String),
+    // form, package (None: the REPL's package)
+    InteractiveEval(String, Option<String>),
     InvokeNthRestart(usize, usize, usize),
     FrameLocals(usize, usize, usize),
     CompileAndLoadFile(String),
     LoadFile(String),
-    FindDefinitions(String),
+    // symbol, package (None: the REPL's package)
+    FindDefinitions(String, Option<String>),
     Stop,
     EmacsReturn(String, usize, usize),
+    // like EmacsReturn, but with t or nil instead of a string
+    EmacsReturnBool(bool, usize, usize),
+    // thread, tag
+    EmacsPong(usize, usize),
     CompileStringForEmacs{
         string: String,
         buffer: String,
         position: EditorPosition,
         filename: Option<String>,
-        policy: Option<String> // ?
+        policy: Option<String>, // ?
+        // the package of the buffer, None: the REPL's package
+        package: Option<String>
     },
     ListAllPackages(usize),
     ListSymbolsInPackage{
@@ -217,13 +576,111 @@ pub enum SlynkMessage {
         symbol: String,
         cont: usize
     },
-    AproposForSymbolInfo {
-        symbol: String,
+    // a class name, e.g. "standard-object" or "my-app::point"
+    ClassInfo {
+        class: String,
+        cont: usize
+    },
+    GenericFunctionInfo {
+        name: String,
+        cont: usize
+    },
+    // slynk:value-for-editing, the form is usually a special variable, e.g. "*config*"
+    ValueForEditing {
+        form: String,
+        package: Option<String>,
+        cont: usize
+    },
+    // slynk:commit-edited-value, (setf form value)
+    CommitEditedValue {
+        form: String,
+        value: String,
+        package: Option<String>,
+        cont: usize
+    },
+    // slynk:re-evaluate-defvar, the text of a (defvar ...) form
+    ReEvaluateDefvar {
+        form: String,
+        package: Option<String>,
+        cont: usize
+    },
+    Apropos {
+        pattern: String,
+        external_only: bool,
+        case_sensitive: bool,
+        // use cl-ppcre (if loaded in the image) to match the pattern
+        regex: bool,
+        // only symbols whose home package is the given one
+        package: Option<String>,
+        cont: usize
+    },
+    // loads the steps of a recorded session in order, see session::SessionRecord
+    ReplaySession(Vec<SessionStep>),
+    // evaluates the watches' forms, see watch::WatchList
+    EvaluateWatches(Vec<Watch>),
+    RunTests {
+        request: TestRequest,
+        // where the project's tests are defined, to add to the results
+        locations: Vec<TestLocation>,
         cont: usize
     },
     FlexCompletions {
         text: String,
+        // the buffer's package, None: the REPL's package
+        package: Option<String>,
+        // the number of top results to add the arglist to
+        arglists: usize,
+        // completions from the project's definitions, added to the ones of the image
+        offline: Vec<FlexCompletion>,
+        cont: usize
+    },
+    OperateOnSystem {
+        system: String,
+        operation: AsdfOperation,
+        // the system's source files, to replace their compiler notes
+        files: Vec<String>
+    },
+    Quickload {
+        systems: Vec<String>,
+        cont: usize
+    },
+    QuicklispSystemApropos {
+        term: String,
+        cont: usize
+    },
+    QuicklispInstalledSystems(usize),
+    QuicklispDists(usize),
+    ExportSymbol {
+        symbol: String,
+        package: String,
         cont: usize
+    },
+    UnexportSymbol {
+        symbol: String,
+        package: String,
+        cont: usize
+    },
+    ImportSymbol {
+        symbol: String,
+        destination: String,
+        origin: String,
+        cont: usize
+    },
+    ExportStructure {
+        name: String,
+        package: String,
+        cont: usize
+    },
+    // all packages if None
+    PackageInfos {
+        package: Option<String>,
+        cont: usize
+    },
+    // evaluated in the mrepl's thread, changing its package (and directory)
+    GuessAndSetPackage(String),
+    SyncPackageAndDefaultDirectory {
+        package: Option<String>,
+        directory: Option<String>
     }
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -237,14 +694,51 @@ pub struct EditorPosition {
 pub enum ContinuationCallback {
     PrintReturnValue(PrintKind),
     Print(String, PrintKind),
-    LoadFile,
+    LoadFile(String),
+    CompileForm {
+        form: String,
+        position: EditorPosition,
+        filename: Option<String>,
+        // the notes of forms without a file are kept under the buffer's name
+        buffer: String
+    },
+    OperateOnSystem {
+        system: String,
+        operation: AsdfOperation,
+        files: Vec<String>
+    },
     JumpToDef,
     DisplayPackages(usize),
     DisplaySymbolsInPackage(usize),
     DisplayDescribe(usize),
     DisplayApropos(usize),
+    DisplayClassInfo(usize),
+    DisplayGenericFunctionInfo(usize),
     DisplayFrameLocals(usize),
-    DisplayCompletions(usize)
+    DisplayCompletions {
+        cont: usize,
+        offline: Vec<FlexCompletion>
+    },
+    DisplayTestRun {
+        request: TestRequest,
+        locations: Vec<TestLocation>,
+        cont: usize
+    },
+    UpdateWatches,
+    DisplayValueForEditing(usize),
+    CommitEditedValue(usize),
+    ReEvaluateDefvar(usize),
+    Quickload {
+        systems: Vec<String>,
+        cont: usize
+    },
+    DisplayQuicklispSystems(usize),
+    DisplayQuicklispDists(usize),
+    PackageFuResult(usize),
+    DisplayExportedSymbols(usize),
+    DisplayPackageInfos(usize),
+    CreateMrepl,
+    SetMreplPrompt
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -260,9 +754,251 @@ pub struct FrameLocal {
     pub value: String
 
 }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TestFramework {
+    FiveAm,
+    Parachute,
+    Rove
+}
+
+///
+/// What to test, see testing::test_form. The system's test-op is run if neither suite nor tests are given.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestRequest {
+    pub framework: TestFramework,
+    // loaded before the suite or the tests are run
+    pub system: Option<String>,
+    // a FiveAM suite, a Parachute test or a Rove package
+    pub suite: Option<String>,
+    // run one after the other, e.g. the failed tests of a previous run
+    #[serde(default)]
+    pub tests: Vec<String>,
+    // the package the suite and test names are read in, CL-USER if None
+    pub package: Option<String>,
+    // in a new process instead of the image Slynk is connected to
+    #[serde(default)]
+    pub isolated: bool
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped
+}
+
+///
+/// Where a test is defined, e.g. (fiveam:test add-test ...) or (parachute:define-test add-test ...).
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestLocation {
+    // as the reader interns it, e.g. ADD-TEST
+    pub name: String,
+    pub package: Option<String>,
+    pub file: String,
+    // 0-based
+    pub line: usize
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestResult {
+    // as printed by the framework, e.g. ADD-TEST or MY-APP/TESTS::ADD-TEST
+    pub name: String,
+    pub suite: Option<String>,
+    pub status: TestStatus,
+    // in seconds, if the framework reports it
+    pub duration: Option<f64>,
+    // why the test failed
+    pub message: Option<String>,
+    pub location: Option<TestLocation>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestRun {
+    pub request: TestRequest,
+    pub suites: Vec<String>,
+    pub tests: Vec<TestResult>,
+    // in seconds
+    pub duration: f64,
+    // what the tests printed
+    pub output: String
+}
+impl TestRun {
+    pub fn count(&self, status: TestStatus) -> usize {
+        self.tests.iter().filter(|t| t.status == status).count()
+    }
+}
+
+///
+/// Which system to instrument with sb-cover and the tests to run it with, see coverage::coverage_script.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageRequest {
+    // recompiled with coverage data, its dependencies are not
+    pub system: String,
+    pub tests: TestRequest
+}
+
+///
+/// What sb-cover recorded for an expression or a conditional's two branches.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CoverageState {
+    Expression {
+        executed: bool
+    },
+    Branch {
+        first_taken: bool,
+        second_taken: bool
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoverageRange {
+    // 0-based
+    pub line: usize,
+    // columns on the line, the end is exclusive, tabs count as the spaces sb-cover expands them to
+    pub start: usize,
+    pub end: usize,
+    pub state: CoverageState
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LineStatus {
+    Covered,
+    // some of the line's code ran, e.g. one branch of an if
+    Partial,
+    NotCovered
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineCoverage {
+    // 0-based
+    pub line: usize,
+    pub status: LineStatus
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileCoverage {
+    // absolute
+    pub file: String,
+    pub ranges: Vec<CoverageRange>,
+    // the lines with instrumented code, in order
+    pub lines: Vec<LineCoverage>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub system: String,
+    // by the file's path relative to the project folder, like the documents of fts::index::Index
+    pub files: BTreeMap<String, FileCoverage>,
+    pub tests: TestRun,
+    // the folder with sb-cover's html report
+    pub html_report: String
+}
+
+///
+/// Something loaded into the image that can be loaded again into a new one, see session::SessionRecord.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SessionStep {
+    // compiled and loaded file
+    LoadFile(String),
+    // (asdf:load-system ...)
+    LoadSystem(String),
+    // (ql:quickload ...)
+    Quickload(String),
+    // compiled top-level form, the package is the buffer's
+    CompileForm {
+        form: String,
+        filename: Option<String>,
+        package: Option<String>
+    }
+}
+
+///
+/// A form evaluated again and again to see how its value changes, see watch::WatchList.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watch {
+    pub id: usize,
+    pub name: String,
+    pub form: String,
+    // the package the form is read and evaluated in, CL-USER if None
+    pub package: Option<String>,
+    // None until it has been evaluated
+    pub value: Option<WatchValue>,
+    // the previous values, the oldest first
+    pub history: Vec<WatchValue>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchValue {
+    // the printed value, or the error's message
+    pub value: String,
+    pub error: bool,
+    // seconds since the epoch
+    pub time: u64
+}
+
+///
+/// When the watches are evaluated, besides on demand.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WatchRefresh {
+    Manual,
+    // after each REPL evaluation, file load and return from the debugger
+    AfterEvaluation,
+    Interval {
+        seconds: u64
+    }
+}
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlexCompletion {
     pub symbol: String,
     pub prob: f64,
-    pub symbol_type: String
-}
\ No newline at end of file
+    // the matched parts of symbol as (index, text), for highlighting
+    pub chunks: Vec<(usize, String)>,
+    // classification flags as returned by slynk, e.g. "fn,cla"
+    pub symbol_type: String,
+    // the flags split, e.g. ["fn", "cla"], empty for symbols naming nothing
+    pub kinds: Vec<String>,
+    // e.g. "(mapcar function list &rest more-lists)", only for the top results
+    pub arglist: Option<String>
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickloadResult {
+    pub system: String,
+    pub success: bool,
+    pub error: Option<String>
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuicklispSystem {
+    pub name: String,
+    pub release: String,
+    pub dist: String
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuicklispDist {
+    pub name: String,
+    pub version: String,
+    pub url: Option<String>,
+    pub enabled: bool
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageInfo {
+    pub name: String,
+    pub nicknames: Vec<String>,
+    pub use_list: Vec<String>,
+    pub used_by: Vec<String>,
+    pub local_nicknames: Vec<PackageLocalNickname>,
+    pub shadowing_symbols: Vec<String>,
+    pub external_count: usize,
+    pub internal_count: usize
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageLocalNickname {
+    pub nickname: String,
+    pub package: String
+}
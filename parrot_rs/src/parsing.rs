@@ -6,6 +6,7 @@ use lazy_static::lazy_static;
 use crate::{BackendError, BackendResult };
 use crate::text::*;
 use crate::models::*;
+use crate::reader::read_all;
 
 lazy_static! {
     // parsing swank :return messages
@@ -16,8 +17,6 @@ lazy_static! {
     static ref EVALUATION_ABORTED: Regex = Regex::new(" ?\\(:evaluation-aborted \"(?P<message>.+)\"\\)$").unwrap();
     static ref PROMPT: Regex = Regex::new("\\(:prompt \"(.+)\" \"(.+)\" (?P<elevel>[0-9]+) (?P<len_history>[0-9]+)( \"(?P<condition>.+)\")?\\)$").unwrap();
     static ref CHANNEL_SEND: Regex = RegexBuilder::new("\\(:channel-send ([0-9]+) (\\((?:.|\n)+\\))\\)$").multi_line(true).build().unwrap();
-    static ref COMPILATION_RESULT: Regex = RegexBuilder::new(" ?\\(:compilation-result (?P<notes>nil|\".+\"|\\((\\((?:.|\n)+\\)\\s*)+\\)) (?P<success>nil|t) (?P<duration>[0-9]+\\.[0-9]+) (?P<loadp>nil|t) (?P<faslfile>nil|\".+\")\\)$").dot_matches_new_line(true).build().unwrap();
-    static ref COMPILER_NOTES: Regex = RegexBuilder::new("\\(:message \"(?P<message>(?:.|\n)+?)\" :severity :(?P<severity>[^ ]+) :location \\(:location \\(:file \"(?P<file>[^\"]+?)\"\\) \\(:position (?P<pos>[0-9]+)\\) nil\\) :references (?P<refs>nil|\\((\\(.+?\\) ?)+\\))+?( :source-context \"(?:.|\n)+?\")?\\)").build().unwrap();
 }
 
 
@@ -43,15 +42,15 @@ pub fn sexp_children(sexp: Sexp) -> BackendResult<Vec<Sexp>> {
 }
 pub fn sexp_list_nth(sexp: &Sexp, n: usize) -> BackendResult<&Sexp> {
     if let Sexp::List(children) = sexp {
-        Ok(&children[n])
+        children.get(n).ok_or_else(|| BackendError("Failed to parse sexp.".to_string()))
     } else {
         Err(BackendError("Failed to parse sexp.".to_string()))
     }
 }
 pub fn sexp_list_nth_as_string(sexp: &Sexp, n: usize) -> BackendResult<String> {
     if let Sexp::List(children) = sexp {
-        match &children[n] {
-            Sexp::Atom(Atom::S(value)) => Ok(value.clone()),
+        match children.get(n) {
+            Some(Sexp::Atom(Atom::S(value))) => Ok(value.clone()),
             _ => Err(BackendError("Failed to parse sexp.".to_string()))
         }
     } else {
@@ -60,8 +59,8 @@ pub fn sexp_list_nth_as_string(sexp: &Sexp, n: usize) -> BackendResult<String> {
 }
 pub fn sexp_list_nth_as_usize(sexp: &Sexp, n: usize) -> BackendResult<usize> {
     if let Sexp::List(children) = sexp {
-        match &children[n] {
-            Sexp::Atom(Atom::I(value)) => Ok(*value as usize),
+        match children.get(n) {
+            Some(Sexp::Atom(Atom::I(value))) => Ok(*value as usize),
             _ => Err(BackendError("Failed to parse sexp.".to_string()))
         }
     } else {
@@ -70,8 +69,8 @@ pub fn sexp_list_nth_as_usize(sexp: &Sexp, n: usize) -> BackendResult<usize> {
 }
 pub fn sexp_list_nth_as_f64(sexp: &Sexp, n: usize) -> BackendResult<f64> {
     if let Sexp::List(children) = sexp {
-        match &children[n] {
-            Sexp::Atom(Atom::F(value)) => Ok(*value),
+        match children.get(n) {
+            Some(Sexp::Atom(Atom::F(value))) => Ok(*value),
             _ => Err(BackendError("Failed to parse sexp.".to_string()))
         }
     } else {
@@ -80,8 +79,8 @@ pub fn sexp_list_nth_as_f64(sexp: &Sexp, n: usize) -> BackendResult<f64> {
 }
 pub fn sexp_list_nth_as_bool(sexp: &Sexp, n: usize) -> BackendResult<bool> {
     if let Sexp::List(children) = sexp {
-        match &children[n] {
-            Sexp::Atom(Atom::S(value)) => Ok(value.eq_ignore_ascii_case("t")),
+        match children.get(n) {
+            Some(Sexp::Atom(Atom::S(value))) => Ok(value.eq_ignore_ascii_case("t")),
             _ => Err(BackendError("Failed to parse sexp.".to_string()))
         }
     } else {
@@ -90,8 +89,8 @@ pub fn sexp_list_nth_as_bool(sexp: &Sexp, n: usize) -> BackendResult<bool> {
 }
 pub fn sexp_list_nth_as_list(sexp: &Sexp, n: usize) -> BackendResult<&Vec<Sexp>> {
     if let Sexp::List(children) = sexp {
-        match &children[n] {
-            Sexp::List(subchildren) => Ok(subchildren),
+        match children.get(n) {
+            Some(Sexp::List(subchildren)) => Ok(subchildren),
             _ => Err(BackendError("Failed to parse sexp.".to_string()))
         }
     } else {
@@ -105,7 +104,7 @@ pub fn sexp_list_nth_as_list(sexp: &Sexp, n: usize) -> BackendResult<&Vec<Sexp>>
 //
 pub fn sexp_list_nth_or_nil(sexp: &Sexp, n: usize) -> BackendResult<Option<&Sexp>> {
     if let Sexp::List(children) = sexp {
-        if n > children.len() - 1 {
+        if n >= children.len() {
            return Err(BackendError("Failed to parse sexp.".to_string()));
         }
         match &children[n] {
@@ -123,7 +122,7 @@ pub fn sexp_list_nth_or_nil(sexp: &Sexp, n: usize) -> BackendResult<Option<&Sexp
 //
 pub fn sexp_list_nth_or_none(sexp: &Sexp, n: usize) -> BackendResult<Option<&Sexp>> {
     if let Sexp::List(children) = sexp {
-        if n > children.len() - 1 {
+        if n >= children.len() {
            return Ok(None);
         }
         return Ok(Some(&children[n]));
@@ -153,6 +152,37 @@ pub fn sexp_usize_atom(sexp: &Sexp) -> BackendResult<usize> {
         Err(BackendError("Failed to parse sexp.".to_string()))
     }
 }
+//
+// sexp=(:a 1 :b 2), key=":b" -> Some(2)
+// sexp=(:a 1), key=":b" -> None
+//
+pub fn sexp_plist_get<'a>(sexp: &'a Sexp, key: &str) -> Option<&'a Sexp> {
+    if let Sexp::List(children) = sexp {
+        children
+            .chunks(2)
+            .find(|kv| matches!(&kv[0], Sexp::Atom(Atom::S(k)) if k.eq_ignore_ascii_case(key)))
+            .and_then(|kv| kv.get(1))
+    } else {
+        None
+    }
+}
+// :warning -> warning
+pub fn sexp_keyword_name(sexp: &Sexp) -> BackendResult<String> {
+    let name = sexp_string_atom(sexp)?;
+    Ok(name.trim_start_matches(':').to_lowercase())
+}
+pub fn sexp_as_f64(sexp: &Sexp) -> BackendResult<f64> {
+    match sexp {
+        Sexp::Atom(Atom::F(value)) => Ok(*value),
+        Sexp::Atom(Atom::I(value)) => Ok(*value as f64),
+        // e.g. 1.0d-3
+        Sexp::Atom(Atom::S(value)) => value
+            .replace(['d', 'D'], "e")
+            .parse::<f64>()
+            .map_err(|_| BackendError("Failed to parse sexp.".to_string())),
+        _ => Err(BackendError("Failed to parse sexp.".to_string()))
+    }
+}
 
 ///
 /// Check if the given form is a return statement with a continuation
@@ -181,33 +211,216 @@ pub fn parse_package_list(list: &str) -> BackendResult<Vec<String>> {
     Ok(pnames)
 }
 ///
-/// Parse the list given by (do-all-symbols)
-/// 
-pub fn parse_symbol_list(return_value: String) -> BackendResult<Vec<String>> {
-    let sexp = clean_and_parse_sexp(&return_value).unwrap();
-    let list = sexp_list_nth_as_string(&sexp, 0).unwrap();
-    if list.len() == 0 || list == "()" || list.eq_ignore_ascii_case("nil") {
-        Ok(vec![])
-    } else {
-        let re = Regex::new("(?:\n| +|\t)+").unwrap();
-        let list_cleaned = Regex::new("(^[(\"]+|[\")]+$)").unwrap()
-            .replace_all(&list, "").to_string();
-        Ok(re.split(&list_cleaned)
-            .map(|s| s.to_string())
-            .sorted()
-            .collect())
+/// Parse the written list of (name kinds exported doc arglist) tuples returned by slynk:eval-and-grab-output,
+/// e.g. (("MAKE-POINT" (:FUNCTION) T "Creates a point." "(&key x y)") ("Weird Symbol" (:SPECIAL-VAR) NIL NIL NIL))
+///
+pub fn parse_symbols(return_value: &str) -> BackendResult<Vec<Symbol>> {
+    let sexp = parse_grabbed_output(return_value)?;
+    let mut symbols = vec![];
+    if let Sexp::List(items) = &sexp {
+        for s in items {
+            let kinds = match sexp_list_nth(s, 1)? {
+                Sexp::List(kinds) => kinds.iter().filter_map(|k| sexp_keyword_name(k).ok()).filter_map(|k| parse_symbol_type(&k)).collect(),
+                _ => vec![]
+            };
+            symbols.push(Symbol {
+                name: sexp_list_nth_as_string(s, 0)?,
+                kinds,
+                exported: sexp_list_nth_as_bool(s, 2)?,
+                doc: sexp_string_or_nil(sexp_list_nth(s, 3)?),
+                arglist: sexp_string_or_nil(sexp_list_nth(s, 4)?)
+            });
+        }
+    }
+    symbols.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(symbols)
+}
+fn parse_symbol_type(keyword: &str) -> Option<SymbolType> {
+    match keyword {
+        "function" => Some(SymbolType::Function),
+        "generic-function" => Some(SymbolType::GenericFunction),
+        "macro" => Some(SymbolType::Macro),
+        "special-var" => Some(SymbolType::SpecialVar),
+        "constant" => Some(SymbolType::Constant),
+        "class" => Some(SymbolType::Class),
+        "struct" => Some(SymbolType::Struct),
+        "condition" => Some(SymbolType::Condition),
+        "type" => Some(SymbolType::Type),
+        _ => None
     }
 }
 
-pub fn parse_describe(return_value: String) -> BackendResult<String> {
-    let sexp = sexp::parse(&return_value).unwrap();
-    let desc = sexp_list_nth_as_string(&sexp, 0)?;
-    Ok(desc)
+// ("(:name \"FLATTEN\" :package \"ALEXANDRIA\" :summary \"...\" :function (:function \"(tree)\" ...) :variable nil ...)" "nil"), 
+// None if the symbol does not exist
+pub fn parse_describe(return_value: &str) -> BackendResult<Option<SymbolDescription>> {
+    // not cleaned, as the descriptions span multiple lines
+    let sexp = parse_grabbed_multiline_output(return_value)?;
+    if sexp_is_any_case_nil(&sexp) {
+        return Ok(None);
+    }
+    let section = |key: &str| sexp_plist_get(&sexp, key).filter(|s| !sexp_is_any_case_nil(s));
+    let string_at = |s: &Sexp, n: usize| sexp_list_nth(s, n).ok().and_then(sexp_string_or_nil);
+    let list_at = |s: &Sexp, n: usize| sexp_list_nth(s, n).map(sexp_string_list).unwrap_or_default();
+
+    let function = match section(":function") {
+        Some(f) => Some(FunctionDescription {
+            kind: sexp_keyword_name(sexp_list_nth(f, 0)?)?,
+            lambda_list: string_at(f, 1),
+            derived_type: string_at(f, 2),
+            documentation: string_at(f, 3),
+            source_file: string_at(f, 4),
+            description: string_at(f, 5)
+        }),
+        None => None
+    };
+    let variable = match section(":variable") {
+        Some(v) => Some(VariableDescription {
+            kind: sexp_keyword_name(sexp_list_nth(v, 0)?)?,
+            // a printed value of "nil" cannot be told from nil, hence the boundp flag
+            value: if sexp_list_nth_as_bool(v, 1)? { Some(sexp_list_nth_as_string(v, 2)?) } else { None },
+            declared_type: string_at(v, 3),
+            documentation: string_at(v, 4),
+            description: string_at(v, 5)
+        }),
+        None => None
+    };
+    let class = section(":class").map(|c| ClassDescription {
+        superclasses: list_at(c, 0),
+        subclasses: list_at(c, 1),
+        slots: list_at(c, 2),
+        documentation: string_at(c, 3),
+        description: string_at(c, 4)
+    });
+    let type_info = section(":type").map(|t| TypeDescription {
+        expansion: string_at(t, 0),
+        documentation: string_at(t, 1)
+    });
+    Ok(Some(SymbolDescription {
+        name: sexp_plist_get(&sexp, ":name").map(sexp_string_atom).transpose()?.unwrap_or_default(),
+        package: sexp_plist_get(&sexp, ":package").and_then(sexp_string_or_nil),
+        summary: sexp_plist_get(&sexp, ":summary").and_then(sexp_string_or_nil).unwrap_or_default(),
+        function,
+        variable,
+        class,
+        type_info
+    }))
 }
-pub fn parse_apropos(return_value: String) -> BackendResult<String> {
-    let sexp = sexp::parse(&return_value).unwrap();
-    let lst = sexp_list_nth_as_string(&sexp, 0)?;
-    Ok(lst)
+// (:name "point" :metaclass "standard-class" :finalized T :precedence-list ("point" "standard-object" "t") ... 
+//  :direct-slots (("x" ":instance" (":x") T "0" "t" ("point-x") ("(setf point-x)") nil)) ...), or NIL for unknown classes
+pub fn parse_class_info(return_value: &str) -> BackendResult<Option<ClassInfo>> {
+    let sexp = parse_grabbed_multiline_output(return_value)?;
+    if sexp_is_any_case_nil(&sexp) {
+        return Ok(None);
+    }
+    let string = |key: &str| sexp_plist_get(&sexp, key).and_then(sexp_string_or_nil);
+    let list = |key: &str| sexp_plist_get(&sexp, key).map(sexp_string_list).unwrap_or_default();
+    let slots = |key: &str| -> BackendResult<Vec<SlotInfo>> {
+        match sexp_plist_get(&sexp, key) {
+            Some(Sexp::List(slots)) => slots.iter().map(parse_slot_info).collect(),
+            _ => Ok(vec![])
+        }
+    };
+    Ok(Some(ClassInfo {
+        name: string(":name").unwrap_or_default(),
+        metaclass: string(":metaclass").unwrap_or_default(),
+        finalized: sexp_plist_get(&sexp, ":finalized").map(|f| !sexp_is_any_case_nil(f)).unwrap_or(false),
+        precedence_list: list(":precedence-list"),
+        direct_superclasses: list(":direct-superclasses"),
+        direct_subclasses: list(":direct-subclasses"),
+        direct_slots: slots(":direct-slots")?,
+        effective_slots: slots(":effective-slots")?,
+        generic_functions: list(":generic-functions"),
+        documentation: string(":documentation")
+    }))
+}
+// (name allocation (initargs) initform-p initform type (readers) (writers) documentation)
+fn parse_slot_info(sexp: &Sexp) -> BackendResult<SlotInfo> {
+    let string_at = |n: usize| sexp_list_nth(sexp, n).ok().and_then(sexp_string_or_nil);
+    let list_at = |n: usize| sexp_list_nth(sexp, n).map(sexp_string_list).unwrap_or_default();
+    Ok(SlotInfo {
+        name: sexp_list_nth_as_string(sexp, 0)?,
+        allocation: string_at(1).map(|a| a.trim_start_matches(':').to_string()).unwrap_or_default(),
+        initargs: list_at(2),
+        // :initform nil prints as "nil", hence the initform-p flag
+        initform: if sexp_list_nth_as_bool(sexp, 3)? { Some(sexp_list_nth_as_string(sexp, 4)?) } else { None },
+        slot_type: string_at(5).unwrap_or_else(|| "t".to_string()),
+        readers: list_at(6),
+        writers: list_at(7),
+        documentation: string_at(8)
+    })
+}
+// (:name "area" :lambda-list "(shape)" :documentation NIL 
+//  :methods (((":around") ((:class "circle") (:eql "0")) "(c n)" NIL "/home/user/shapes.lisp") ...)), or NIL
+pub fn parse_generic_function_info(return_value: &str) -> BackendResult<Option<GenericFunctionInfo>> {
+    let sexp = parse_grabbed_multiline_output(return_value)?;
+    if sexp_is_any_case_nil(&sexp) {
+        return Ok(None);
+    }
+    let string = |key: &str| sexp_plist_get(&sexp, key).and_then(sexp_string_or_nil);
+    let mut methods = vec![];
+    if let Some(Sexp::List(items)) = sexp_plist_get(&sexp, ":methods") {
+        for item in items {
+            let mut specializers = vec![];
+            if let Sexp::List(specs) = sexp_list_nth(item, 1)? {
+                for spec in specs {
+                    let object = sexp_list_nth_as_string(spec, 1)?;
+                    specializers.push(match sexp_keyword_name(sexp_list_nth(spec, 0)?)?.as_str() {
+                        "eql" => Specializer::Eql(object),
+                        _ => Specializer::Class(object)
+                    });
+                }
+            }
+            methods.push(MethodInfo {
+                qualifiers: sexp_string_list(sexp_list_nth(item, 0)?),
+                specializers,
+                lambda_list: sexp_list_nth(item, 2).ok().and_then(sexp_string_or_nil).unwrap_or_default(),
+                documentation: sexp_list_nth(item, 3).ok().and_then(sexp_string_or_nil),
+                source_file: sexp_list_nth(item, 4).ok().and_then(sexp_string_or_nil)
+            });
+        }
+    }
+    Ok(Some(GenericFunctionInfo {
+        name: string(":name").unwrap_or_default(),
+        lambda_list: string(":lambda-list").unwrap_or_default(),
+        documentation: string(":documentation"),
+        methods
+    }))
+}
+// ((:designator ("FLATTEN" "ALEXANDRIA" t) :function "Traverses the tree..." :arglist "(TREE)" :bounds ((0 7))) ...)
+pub fn parse_apropos(return_value: &str) -> BackendResult<Vec<AproposHit>> {
+    let sexp = clean_and_parse_sexp(return_value)?;
+    let mut hits = vec![];
+    if let Sexp::List(items) = &sexp {
+        for item in items {
+            let designator = match sexp_plist_get(item, ":designator") {
+                Some(d) => {
+                    let name = sexp_list_nth_as_string(d, 0)?;
+                    match sexp_string_or_nil(sexp_list_nth(d, 1)?) {
+                        Some(package) if sexp_list_nth_as_bool(d, 2)? => format!("{}:{}", package, name),
+                        Some(package) => format!("{}::{}", package, name),
+                        None => format!("#:{}", name)
+                    }
+                },
+                None => continue
+            };
+            let plist = match item {
+                Sexp::List(plist) => plist,
+                _ => continue
+            };
+            for pair in plist.chunks(2) {
+                let kind = sexp_keyword_name(&pair[0])?;
+                if ["designator", "arglist", "bounds", "flex-score"].contains(&kind.as_str()) {
+                    continue;
+                }
+                let documentation = pair
+                    .get(1)
+                    .and_then(sexp_string_or_nil)
+                    .filter(|d| d != ":not-documented");
+                hits.push(AproposHit { designator: designator.clone(), kind, documentation });
+            }
+        }
+    }
+    Ok(hits)
 }
 pub fn parse_frame_locals(return_value: &str) -> BackendResult<Vec<FrameLocal>> {
     let sexp = clean_and_parse_sexp(return_value)?;
@@ -224,17 +437,414 @@ pub fn parse_frame_locals(return_value: &str) -> BackendResult<Vec<FrameLocal>>
     }
     Ok(lst)
 }
+///
+/// Parse the return value of slynk-completion:flex-completions: ((("mapcar" 0.9 ((0 "map")) "fn" "(mapcar ...)") ...) nil),
+/// the arglist is only there for the completions it was added to
+///
 pub fn parse_flex_completions(return_value: &str) -> BackendResult<Vec<FlexCompletion>> {
     let sexp = clean_and_parse_sexp(return_value)?;
-    let suggestions = sexp_list_nth_as_list(&sexp, 0)?;
+    // nil for an empty pattern
+    if sexp_is_any_case_nil(&sexp) {
+        return Ok(vec![]);
+    }
+    let suggestions = match sexp_list_nth(&sexp, 0)? {
+        Sexp::List(suggestions) => suggestions,
+        _ => return Ok(vec![])
+    };
     let mut lst = vec![];
     for s in suggestions {
-        let symbol = sexp_list_nth_as_string(&s, 0)?;
-        let prob = sexp_list_nth_as_f64(&s, 1)?;
-        let symbol_type = sexp_list_nth_as_string(&s, 3)?;
-        lst.push(FlexCompletion { symbol: symbol, prob: prob, symbol_type: symbol_type });
+        let symbol = sexp_list_nth_as_string(s, 0)?;
+        let prob = sexp_list_nth_as_f64(s, 1)?;
+        let mut chunks = vec![];
+        if let Sexp::List(items) = sexp_list_nth(s, 2)? {
+            for chunk in items {
+                chunks.push((sexp_list_nth_as_usize(chunk, 0)?, sexp_list_nth_as_string(chunk, 1)?));
+            }
+        }
+        let symbol_type = sexp_list_nth_as_string(s, 3)?;
+        let kinds = symbol_type
+            .split(',')
+            .filter(|k| !k.is_empty())
+            .map(|k| k.to_string())
+            .collect();
+        let arglist = sexp_list_nth_or_none(s, 4)?.and_then(sexp_string_or_nil);
+        lst.push(FlexCompletion { symbol, prob, chunks, symbol_type, kinds, arglist });
+    }
+    Ok(lst)
+}
+
+///
+/// Parse the return value of the quickload form: (("name" t nil) ("name" nil "error message"))
+///
+pub fn parse_quickload_results(return_value: &str) -> BackendResult<Vec<QuickloadResult>> {
+    let sexp = clean_and_parse_sexp(return_value)?;
+    let mut results = vec![];
+    if let Sexp::List(items) = &sexp {
+        for r in items {
+            let system = sexp_list_nth_as_string(r, 0)?;
+            let success = sexp_list_nth_as_bool(r, 1)?;
+            let error = sexp_string_or_nil(sexp_list_nth(r, 2)?);
+            results.push(QuickloadResult { system, success, error });
+        }
+    }
+    Ok(results)
+}
+///
+/// Parse the written list of (name release dist) tuples returned by slynk:eval-and-grab-output
+///
+pub fn parse_quicklisp_systems(return_value: &str) -> BackendResult<Vec<QuicklispSystem>> {
+    let sexp = parse_grabbed_output(return_value)?;
+    let mut systems = vec![];
+    if let Sexp::List(items) = &sexp {
+        for s in items {
+            systems.push(QuicklispSystem {
+                name: sexp_list_nth_as_string(s, 0)?,
+                release: sexp_list_nth_as_string(s, 1)?,
+                dist: sexp_list_nth_as_string(s, 2)?
+            });
+        }
+    }
+    Ok(systems)
+}
+///
+/// Parse the written list of (name version url enabled) tuples returned by slynk:eval-and-grab-output
+///
+pub fn parse_quicklisp_dists(return_value: &str) -> BackendResult<Vec<QuicklispDist>> {
+    let sexp = parse_grabbed_output(return_value)?;
+    let mut dists = vec![];
+    if let Sexp::List(items) = &sexp {
+        for d in items {
+            dists.push(QuicklispDist {
+                name: sexp_list_nth_as_string(d, 0)?,
+                version: sexp_list_nth_as_string(d, 1)?,
+                url: sexp_string_or_nil(sexp_list_nth(d, 2)?),
+                enabled: sexp_list_nth_as_bool(d, 3)?
+            });
+        }
+    }
+    Ok(dists)
+}
+///
+/// Parse the return value of slynk:export-symbol-for-emacs and friends, nil if the package could not be found.
+///
+pub fn parse_package_fu_result(return_value: &str) -> bool {
+    let value = return_value.trim();
+    !(value.is_empty() || value.eq_ignore_ascii_case("nil"))
+}
+///
+/// Parse the symbols returned by slynk:export-structure, e.g. (common-lisp-user::point common-lisp-user::make-point),
+/// into their names without package prefix.
+///
+pub fn parse_exported_symbols(return_value: &str) -> Vec<String> {
+    read_all(return_value)
+        .first()
+        .and_then(|f| f.as_list())
+        .map(|symbols| symbols.iter().filter_map(|s| s.symbol_name()).collect())
+        .unwrap_or_default()
+}
+///
+/// Parse a string returned by a slynk function, e.g. slynk:value-for-editing, keeping newlines
+///
+pub fn parse_returned_string(return_value: &str) -> BackendResult<String> {
+    let sexp = sexp::parse(return_value.trim())
+        .map_err(|e| BackendError(format!("Failed to parse sexp: {}.", e.message)))?;
+    sexp_string_atom(&sexp)
+}
+///
+/// Parse the written list of (id ok "printed value") returned by slynk:eval-and-grab-output for
+/// watch::watch_form into (id, printed value or error message, error)
+///
+pub fn parse_watch_values(return_value: &str) -> BackendResult<Vec<(usize, String, bool)>> {
+    match parse_grabbed_multiline_output(return_value)? {
+        Sexp::List(values) => values
+            .iter()
+            .map(|v| Ok((sexp_list_nth_as_usize(v, 0)?, sexp_list_nth_as_string(v, 2)?, !sexp_list_nth_as_bool(v, 1)?)))
+            .collect(),
+        _ => Ok(vec![])
+    }
+}
+///
+/// Parse the written list of (name nicknames use-list used-by local-nicknames shadowing-symbols external-count internal-count)
+/// tuples returned by slynk:eval-and-grab-output
+///
+pub fn parse_package_infos(return_value: &str) -> BackendResult<Vec<PackageInfo>> {
+    let sexp = parse_grabbed_output(return_value)?;
+    let mut packages = vec![];
+    if let Sexp::List(items) = &sexp {
+        for p in items {
+            let local_nicknames = sexp_list_nth_as_list(p, 4)
+                .map(|nicks| nicks
+                    .iter()
+                    .filter_map(|n| Some(PackageLocalNickname {
+                        nickname: sexp_list_nth_as_string(n, 0).ok()?,
+                        package: sexp_list_nth_as_string(n, 1).ok()?
+                    }))
+                    .collect())
+                .unwrap_or_default();
+            packages.push(PackageInfo {
+                name: sexp_list_nth_as_string(p, 0)?,
+                nicknames: sexp_string_list(sexp_list_nth(p, 1)?),
+                use_list: sexp_string_list(sexp_list_nth(p, 2)?),
+                used_by: sexp_string_list(sexp_list_nth(p, 3)?),
+                local_nicknames,
+                shadowing_symbols: sexp_string_list(sexp_list_nth(p, 5)?),
+                external_count: sexp_list_nth_as_usize(p, 6)?,
+                internal_count: sexp_list_nth_as_usize(p, 7)?
+            });
+        }
+    }
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(packages)
+}
+///
+/// Parse the return value of slynk-mrepl:create-mrepl: (channel-id thread-id)
+///
+pub fn parse_mrepl_ids(return_value: &str) -> BackendResult<(usize, usize)> {
+    let sexp = clean_and_parse_sexp(return_value)?;
+    Ok((sexp_list_nth_as_usize(&sexp, 0)?, sexp_list_nth_as_usize(&sexp, 1)?))
+}
+///
+/// Parse the prompt arguments returned by slynk-mrepl:eval-for-mrepl:
+/// ((package prompt elevel len-history &optional condition) (values ...))
+///
+pub fn parse_mrepl_prompt(return_value: &str) -> BackendResult<ChannelMethod> {
+    let sexp = clean_and_parse_sexp(return_value)?;
+    let args = sexp_list_nth(&sexp, 0)?;
+    Ok(ChannelMethod::Prompt {
+        package: sexp_list_nth_as_string(args, 0)?,
+        prompt: sexp_list_nth_as_string(args, 1)?,
+        elevel: sexp_list_nth_as_usize(args, 2)?,
+        len_history: sexp_list_nth_as_usize(args, 3)?,
+        condition: sexp_list_nth_or_none(args, 4)?.and_then(sexp_string_or_nil)
+    })
+}
+///
+/// Parse the return value of slynk:eval-and-grab-output: ("output" "value") -> (output, value)
+///
+pub fn parse_grabbed_output_and_value(return_value: &str) -> BackendResult<(String, String)> {
+    let grabbed = sexp::parse(return_value.trim())
+        .map_err(|e| BackendError(format!("Failed to parse sexp: {}.", e.message)))?;
+    Ok((sexp_list_nth_as_string(&grabbed, 0)?, sexp_list_nth_as_string(&grabbed, 1)?))
+}
+// ("output" "value") -> output parsed as sexp
+fn parse_grabbed_output(return_value: &str) -> BackendResult<Sexp> {
+    let sexp = clean_and_parse_sexp(return_value)?;
+    let output = sexp_list_nth_as_string(&sexp, 0)?;
+    clean_and_parse_sexp(&output)
+}
+// ("output" "value") -> output parsed as sexp, keeping newlines in strings
+fn parse_grabbed_multiline_output(return_value: &str) -> BackendResult<Sexp> {
+    let grabbed = sexp::parse(return_value.trim())
+        .map_err(|e| BackendError(format!("Failed to parse sexp: {}.", e.message)))?;
+    let output = sexp_list_nth_as_string(&grabbed, 0)?;
+    sexp::parse(output.trim())
+        .map_err(|e| BackendError(format!("Failed to parse sexp: {}.", e.message)))
+}
+// written with the image's print case, i.e. NIL
+fn sexp_is_any_case_nil(sexp: &Sexp) -> bool {
+    matches!(sexp, Sexp::Atom(Atom::S(s)) if s.eq_ignore_ascii_case("nil"))
+}
+// ("a" "b") -> [a, b], nil/NIL -> []
+fn sexp_string_list(sexp: &Sexp) -> Vec<String> {
+    match sexp {
+        Sexp::List(items) => items.iter().filter_map(sexp_string_or_nil).collect(),
+        _ => vec![]
+    }
+}
+// "text" -> Some(text), nil/NIL -> None
+fn sexp_string_or_nil(sexp: &Sexp) -> Option<String> {
+    match sexp {
+        Sexp::Atom(Atom::S(s)) if !s.eq_ignore_ascii_case("nil") => Some(s.clone()),
+        _ => None
+    }
+}
+
+///
+/// Parse the return value of slynk:compile-file-for-emacs and slynk:compile-string-for-emacs:
+/// (:compilation-result <notes> <success> <duration> <loadp> <faslfile>)
+///
+pub fn parse_compilation_result(value: &str, continuation: usize, ccb: Option<&ContinuationCallback>) -> BackendResult<SlynkAnswer> {
+    let sexp = sexp::parse(value.trim())
+        .map_err(|e| BackendError(format!("Failed to parse sexp: {}.", e.message)))?;
+    let mut notes = None;
+    if let Some(Sexp::List(lnotes)) = sexp_list_nth_or_nil(&sexp, 1)? {
+        let mut parsed = vec![];
+        // a note that cannot be parsed is left out instead of failing the whole result
+        for n in lnotes {
+            if let Ok(mut note) = parse_compiler_note(n) {
+                resolve_compiler_note_position(&mut note, ccb);
+                parsed.push(note);
+            }
+        }
+        notes = Some(parsed);
+    }
+    let success = sexp_list_nth_as_bool(&sexp, 2)?;
+    let duration = sexp_as_f64(sexp_list_nth(&sexp, 3)?)?;
+    let loadp = sexp_list_nth_as_bool(&sexp, 4)?;
+    let fasl_file = match sexp_list_nth_or_nil(&sexp, 5)? {
+        Some(f) => Some(sexp_string_atom(f)?),
+        None => None
+    };
+    Ok(SlynkAnswer::ReturnCompilationResult { continuation, notes, success, duration, loadp, fasl_file })
+}
+
+///
+/// Parse a single compiler note:
+/// (:message "..." :severity :warning :location (...) :references (...) :source-context "...")
+///
+pub fn parse_compiler_note(sexp: &Sexp) -> BackendResult<CompilerNotes> {
+    let message = sexp_plist_get(sexp, ":message")
+        .map(sexp_string_atom)
+        .unwrap_or_else(|| Ok(String::new()))?;
+    let severity = sexp_plist_get(sexp, ":severity")
+        .map(sexp_keyword_name)
+        .unwrap_or_else(|| Ok(String::from("note")))?;
+    // the note is kept without a location if the location is nil or of an unknown kind
+    let location = match sexp_plist_get(sexp, ":location") {
+        Some(l) => parse_location(l).unwrap_or_else(|e| Location::Error(e.0)),
+        None => Location::Error("No location given.".to_string())
+    };
+    let references = match sexp_plist_get(sexp, ":references") {
+        Some(Sexp::List(refs)) => refs.iter().filter_map(|r| parse_compiler_note_reference(r).ok()).collect(),
+        _ => vec![]
+    };
+    let source_context = match sexp_plist_get(sexp, ":source-context") {
+        Some(ctx) if !sexp_is_nil(ctx) => sexp_string_atom(ctx).ok(),
+        _ => None
+    };
+    Ok(CompilerNotes {
+        message,
+        severity,
+        location,
+        references,
+        source_context,
+        file: None,
+        position: None,
+        line: None,
+        col: None
+    })
+}
+
+///
+/// (:ansi-cl :section (3 2 2 3)) -> { manual: "ansi-cl", kind: "section", name: "3.2.2.3" }
+///
+pub fn parse_compiler_note_reference(sexp: &Sexp) -> BackendResult<CompilerNoteReference> {
+    let manual = sexp_keyword_name(sexp_list_nth(sexp, 0)?)?;
+    let kind = sexp_keyword_name(sexp_list_nth(sexp, 1)?)?;
+    let name = match sexp_list_nth(sexp, 2)? {
+        Sexp::List(parts) => parts.iter().map(|p| p.to_string()).join("."),
+        Sexp::Atom(Atom::S(name)) => name.clone(),
+        other => other.to_string()
+    };
+    Ok(CompilerNoteReference { manual, kind, name })
+}
+
+///
+/// Parse a Slynk location, e.g.
+/// (:location (:file "path/to/file.lisp") (:position 42) nil)
+/// (:location (:buffer "name") (:offset 120 7) (:snippet "..."))
+/// (:error "No error location available")
+///
+pub fn parse_location(sexp: &Sexp) -> BackendResult<Location> {
+    let ltype = sexp_list_nth_as_string(sexp, 0)?;
+    if ltype.eq_ignore_ascii_case(":error") {
+        return Ok(Location::Error(sexp_list_nth_as_string(sexp, 1)?));
+    }
+    let lbuffer = sexp_list_nth(sexp, 1)?;
+    let buffer = match sexp_list_nth_as_string(lbuffer, 0)?.to_lowercase().as_str() {
+        ":file" => LocationBuffer::File(sexp_list_nth_as_string(lbuffer, 1)?),
+        ":buffer" => LocationBuffer::Buffer(sexp_list_nth_as_string(lbuffer, 1)?),
+        ":buffer-and-file" => LocationBuffer::BufferAndFile {
+            buffer: sexp_list_nth_as_string(lbuffer, 1)?,
+            file: sexp_list_nth_as_string(lbuffer, 2)?
+        },
+        ":source-form" => LocationBuffer::SourceForm(sexp_list_nth_as_string(lbuffer, 1)?),
+        other => return Err(BackendError(format!("Unknown location buffer: {}", other)))
+    };
+    let position = match sexp_list_nth_or_none(sexp, 2)? {
+        Some(lpos) => parse_location_position(lpos),
+        None => LocationPosition::Unknown
+    };
+    let snippet = match sexp_list_nth_or_none(sexp, 3)? {
+        Some(hints) => match sexp_plist_get(hints, ":snippet") {
+            Some(s) if !sexp_is_nil(s) => Some(sexp_string_atom(s)?),
+            _ => None
+        },
+        None => None
+    };
+    Ok(Location::Location { buffer, position, snippet })
+}
+
+fn parse_location_position(sexp: &Sexp) -> LocationPosition {
+    let ptype = match sexp_list_nth_as_string(sexp, 0) {
+        Ok(ptype) => ptype.to_lowercase(),
+        Err(_) => return LocationPosition::Unknown
+    };
+    match ptype.as_str() {
+        ":position" => match sexp_list_nth_as_usize(sexp, 1) {
+            Ok(pos) => LocationPosition::Position(pos),
+            Err(_) => LocationPosition::Unknown
+        },
+        ":offset" => match (sexp_list_nth_as_usize(sexp, 1), sexp_list_nth_as_usize(sexp, 2)) {
+            (Ok(start), Ok(offset)) => LocationPosition::Offset { start, offset },
+            _ => LocationPosition::Unknown
+        },
+        ":line" => match sexp_list_nth_as_usize(sexp, 1) {
+            Ok(line) => LocationPosition::Line {
+                line,
+                col: sexp_list_nth_or_none(sexp, 2).ok().flatten().and_then(|c| sexp_usize_atom(c).ok())
+            },
+            Err(_) => LocationPosition::Unknown
+        },
+        ":function-name" => match sexp_list_nth_as_string(sexp, 1) {
+            Ok(name) => LocationPosition::FunctionName(name),
+            Err(_) => LocationPosition::Unknown
+        },
+        ":eof" => LocationPosition::Eof,
+        _ => LocationPosition::Unknown
+    }
+}
+
+///
+/// Fills in file, position, line and col of the note from its location.
+/// Buffer locations can only be resolved if we know which form was compiled (ContinuationCallback::CompileForm).
+///
+pub fn resolve_compiler_note_position(note: &mut CompilerNotes, ccb: Option<&ContinuationCallback>) {
+    if let Location::Location { buffer, position, .. } = &note.location {
+        match (buffer, position) {
+            (LocationBuffer::File(file), LocationPosition::Position(pos))
+            | (LocationBuffer::BufferAndFile { file, .. }, LocationPosition::Position(pos)) => {
+                note.file = Some(file.clone());
+                note.position = Some(*pos);
+                if let Ok(content) = std::fs::read_to_string(file) {
+                    if let Some((line, col)) = get_line_and_col_from_char_pos(&content, pos.saturating_sub(1)) {
+                        note.line = Some(line);
+                        note.col = Some(col);
+                    }
+                }
+            },
+            (LocationBuffer::Buffer(_), LocationPosition::Offset { start, offset }) => {
+                note.position = Some(start + offset + 1);
+                if let Some(ContinuationCallback::CompileForm { form, position, filename, .. }) = ccb {
+                    note.file = filename.clone();
+                    if let Some((line, col)) = get_line_and_col_from_char_pos(form, *offset) {
+                        note.line = Some(position.line + line);
+                        note.col = Some(if line == 0 { position.col + col } else { col });
+                    }
+                }
+            },
+            (LocationBuffer::File(file), LocationPosition::Line { line, col })
+            | (LocationBuffer::BufferAndFile { file, .. }, LocationPosition::Line { line, col }) => {
+                note.file = Some(file.clone());
+                note.line = Some(line.saturating_sub(1));
+                note.col = Some(col.unwrap_or(0));
+            },
+            (LocationBuffer::File(file), _) | (LocationBuffer::BufferAndFile { file, .. }, _) => {
+                note.file = Some(file.clone());
+            },
+            _ => ()
+        }
     }
-    return Ok(lst);
 }
 
 
@@ -243,7 +853,7 @@ pub fn parse_slynk_answer(m: &str, ccb: Option<&ContinuationCallback>) -> SlynkA
         println!("Parsing: {}", m);
     }
     if m.starts_with("(:indentation-update ") {
-        SlynkAnswer::IndentationUpdate
+        parse_event(m).unwrap_or_else(|_| SlynkAnswer::Unknown(m.to_string()))
     }  else if m.starts_with("(:channel-send ") {
         let cap = CHANNEL_SEND.captures(m).unwrap();
         let channel = cap.get(1).unwrap().as_str().parse::<usize>().unwrap();
@@ -268,7 +878,7 @@ pub fn parse_slynk_answer(m: &str, ccb: Option<&ContinuationCallback>) -> SlynkA
             .parse::<usize>()
             .unwrap();
 
-        let mut captures = RETURN_VALUE.captures(m).unwrap();
+        let captures = RETURN_VALUE.captures(m).unwrap();
         let value = captures
                 .name("value")
                 .or(captures.name("nil"))
@@ -316,33 +926,13 @@ pub fn parse_slynk_answer(m: &str, ccb: Option<&ContinuationCallback>) -> SlynkA
             }
             SlynkAnswer::ReturnFindDefinitionResult { continuation, definitions } 
 
-        } else if COMPILATION_RESULT.is_match(&value) {
-            captures = COMPILATION_RESULT.captures(&value).unwrap();
-            let cnotes = captures.name("notes").unwrap().as_str();
-            let notes = if COMPILER_NOTES.is_match(&cnotes) {
-                let mut lnotes = vec![];
-                for c in COMPILER_NOTES.captures_iter(&cnotes) {
-                    let message = c.name("message").unwrap().as_str().to_string();
-                    let severity = c.name("severity").unwrap().as_str().to_string();
-                    let file = c.name("file").map(|m| m.as_str().to_string());
-                    let position = c.name("pos").map(|m| m.as_str().parse::<usize>().unwrap());
-                    lnotes.push(CompilerNotes {
-                        message, 
-                        severity,
-                        file,
-                        position
-                    });
-                }
-                Some(lnotes)
-            } else {
-                None
-            };
-            let success = captures.name("success").unwrap().as_str().eq_ignore_ascii_case("t");
-            let duration = captures.name("duration").unwrap().as_str().parse::<f64>().unwrap();
-            let loadp = captures.name("loadp").unwrap().as_str().eq_ignore_ascii_case("t");
-            let fasl_file = option_str(&trim_quotes(captures.name("faslfile").unwrap().as_str().to_string()));
-
-            SlynkAnswer::ReturnCompilationResult { continuation, notes, success, duration, loadp, fasl_file }
+        } else if value.starts_with("(:compilation-result ") {
+            // an abort so that the pending compilation is not left waiting
+            parse_compilation_result(&value, continuation, ccb).unwrap_or_else(|e| SlynkAnswer::Return {
+                continuation,
+                value: format!("Could not read the compilation result: {}", e.0),
+                status: ReturnStatus::Abort
+            })
         } else {
             SlynkAnswer::Return {
                 continuation,
@@ -487,9 +1077,174 @@ pub fn parse_slynk_answer(m: &str, ccb: Option<&ContinuationCallback>) -> SlynkA
             },
             _ => panic!("Should be list")
         }
+    } else if m.starts_with("(:new-package ") 
+        || m.starts_with("(:ed ") 
+        || m.starts_with("(:inspect ") 
+        || m.starts_with("(:y-or-n-p ") 
+        || m.starts_with("(:background-message ") 
+        || m.starts_with("(:ping ") 
+        || m.starts_with("(:invalid-rpc ") {
+        parse_event(m).unwrap_or_else(|_| SlynkAnswer::Unknown(m.to_string()))
     } else {
-        SlynkAnswer::IndentationUpdate
+        SlynkAnswer::Unknown(m.to_string())
+    }
+}
+
+///
+/// Parse the events sent by Slynk without a request, e.g. (:ed (:filename "/x.lisp" :line 2)) or (:ping 3 17)
+///
+pub fn parse_event(m: &str) -> BackendResult<SlynkAnswer> {
+    let sexp = sexp::parse(m.trim())
+        .map_err(|e| BackendError(format!("Failed to parse sexp: {}.", e.message)))?;
+    let event = sexp_keyword_name(sexp_list_nth(&sexp, 0)?)?;
+    let answer = match event.as_str() {
+        "new-package" => SlynkAnswer::NewPackage { 
+            package_name: sexp_list_nth_as_string(&sexp, 1)?, 
+            prompt: sexp_list_nth_as_string(&sexp, 2)? 
+        },
+        "ed" => SlynkAnswer::Ed { 
+            target: sexp_list_nth_or_nil(&sexp, 1)?.map(parse_ed_target).transpose()? 
+        },
+        "inspect" => SlynkAnswer::Inspect {
+            content: parse_inspector_content(sexp_list_nth(&sexp, 1)?)?,
+            thread: sexp_list_nth_or_none(&sexp, 2)?.and_then(|t| sexp_usize_atom(t).ok()),
+            tag: sexp_list_nth_or_none(&sexp, 3)?.and_then(|t| sexp_usize_atom(t).ok())
+        },
+        "y-or-n-p" => SlynkAnswer::YOrNP {
+            thread: sexp_list_nth_as_usize(&sexp, 1)?,
+            tag: sexp_list_nth_as_usize(&sexp, 2)?,
+            question: sexp_list_nth_as_string(&sexp, 3)?
+        },
+        "background-message" => SlynkAnswer::BackgroundMessage { message: sexp_list_nth_as_string(&sexp, 1)? },
+        "ping" => SlynkAnswer::Ping {
+            thread: sexp_list_nth_as_usize(&sexp, 1)?,
+            tag: sexp_list_nth_as_usize(&sexp, 2)?
+        },
+        "indentation-update" => SlynkAnswer::IndentationUpdate {
+            specs: parse_indentation_specs(sexp_list_nth(&sexp, 1)?)
+        },
+        "invalid-rpc" => SlynkAnswer::InvalidRpc {
+            continuation: sexp_list_nth_as_usize(&sexp, 1)?,
+            message: sexp_list_nth_as_string(&sexp, 2)?
+        },
+        _ => SlynkAnswer::Unknown(m.to_string())
+    };
+    Ok(answer)
+}
+
+// (("define-foo" 1 ("MY-APP")) ("with-bar" (4 "&body") ("MY-APP")) ...), entries with unknown specs are skipped
+fn parse_indentation_specs(sexp: &Sexp) -> Vec<(String, IndentSpec)> {
+    let entries = match sexp {
+        Sexp::List(entries) => entries,
+        _ => return vec![]
+    };
+    entries
+        .iter()
+        .filter_map(|e| {
+            let name = sexp_list_nth_as_string(e, 0).ok()?;
+            let spec = match sexp_list_nth(e, 1).ok()? {
+                Sexp::Atom(Atom::I(n)) => IndentSpec::Body(*n as usize),
+                Sexp::List(items) => IndentSpec::Args(parse_arg_indents(items)?),
+                _ => return None
+            };
+            Some((name.to_lowercase(), spec))
+        })
+        .collect()
+}
+
+fn parse_arg_indents(items: &[Sexp]) -> Option<Vec<ArgIndent>> {
+    let mut args = vec![];
+    let mut ix = 0;
+    while ix < items.len() {
+        let arg = match &items[ix] {
+            Sexp::Atom(Atom::S(s)) if s.eq_ignore_ascii_case("&rest") => {
+                ix += 1;
+                let rest = items.get(ix).map(parse_arg_indent).unwrap_or(Some(ArgIndent::Normal))?;
+                ArgIndent::Rest(Box::new(rest))
+            },
+            item => parse_arg_indent(item)?
+        };
+        args.push(arg);
+        ix += 1;
+    }
+    Some(args)
+}
+
+// nil, 4, &lambda, &body or (&whole 4 ...)
+fn parse_arg_indent(sexp: &Sexp) -> Option<ArgIndent> {
+    match sexp {
+        Sexp::Atom(Atom::I(n)) => Some(ArgIndent::Offset(*n as usize)),
+        Sexp::Atom(Atom::S(s)) => match s.to_lowercase().as_str() {
+            "nil" => Some(ArgIndent::Normal),
+            "&lambda" => Some(ArgIndent::Lambda),
+            "&body" => Some(ArgIndent::Body),
+            _ => None
+        },
+        Sexp::List(items) if items.is_empty() => Some(ArgIndent::Normal),
+        Sexp::List(items) => match (&items[0], items.get(1)) {
+            (Sexp::Atom(Atom::S(s)), Some(Sexp::Atom(Atom::I(n)))) if s.eq_ignore_ascii_case("&whole") => Some(ArgIndent::Offset(*n as usize)),
+            (Sexp::Atom(Atom::S(s)), _) if s.eq_ignore_ascii_case("&whole") => Some(ArgIndent::Normal),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+// (:filename "/x.lisp" :line 2 :column 4) or (:function-name "FOO")
+fn parse_ed_target(sexp: &Sexp) -> BackendResult<EdTarget> {
+    let kind = sexp_keyword_name(sexp_list_nth(sexp, 0)?)?;
+    let rest = match sexp {
+        Sexp::List(items) => Sexp::List(items[2..].to_vec()),
+        _ => return Err(BackendError("Failed to parse sexp.".to_string()))
+    };
+    let usize_value = |key: &str| sexp_plist_get(&rest, key).and_then(|v| sexp_usize_atom(v).ok());
+    match kind.as_str() {
+        "filename" => {
+            let filename = sexp_list_nth_as_string(sexp, 1)?;
+            // with :bytep t the (1-based) position is a byte offset, the ui expects characters
+            let bytep = sexp_plist_get(&rest, ":bytep").is_some_and(|v| !sexp_is_nil(v));
+            let position = usize_value(":position").map(|pos| match std::fs::read_to_string(&filename) {
+                Ok(content) if bytep => char_pos_from_byte_offset(&content, pos.saturating_sub(1)) + 1,
+                _ => pos
+            });
+            Ok(EdTarget::File {
+                filename,
+                line: usize_value(":line"),
+                column: usize_value(":column"),
+                position
+            })
+        },
+        "function-name" => Ok(EdTarget::FunctionName(sexp_list_nth_as_string(sexp, 1)?)),
+        _ => Err(BackendError(format!("Unknown ed target: {}", kind)))
+    }
+}
+
+///
+/// Parse the inspector content as returned by slynk::inspect-object:
+/// (:title "..." :id 0 :content (("text" (:value "1" 0) (:label "l") (:action "a" 0)) length start end))
+///
+pub fn parse_inspector_content(sexp: &Sexp) -> BackendResult<InspectorContent> {
+    let title = sexp_plist_get(sexp, ":title").map(sexp_string_atom).transpose()?.unwrap_or_default();
+    let id = sexp_plist_get(sexp, ":id").map(sexp_usize_atom).transpose()?.unwrap_or_default();
+    let mut parts = vec![];
+    if let Some(content) = sexp_plist_get(sexp, ":content") {
+        if let Some(Sexp::List(items)) = sexp_list_nth_or_nil(content, 0)? {
+            for item in items {
+                let part = match item {
+                    Sexp::Atom(Atom::S(text)) => InspectorPart::Text(text.clone()),
+                    Sexp::List(_) => match sexp_keyword_name(sexp_list_nth(item, 0)?)?.as_str() {
+                        "value" => InspectorPart::Value { text: sexp_list_nth_as_string(item, 1)?, index: sexp_list_nth_as_usize(item, 2)? },
+                        "label" => InspectorPart::Label(sexp_list_nth_as_string(item, 1)?),
+                        "action" => InspectorPart::Action { label: sexp_list_nth_as_string(item, 1)?, index: sexp_list_nth_as_usize(item, 2)? },
+                        _ => continue
+                    },
+                    _ => continue
+                };
+                parts.push(part);
+            }
+        }
     }
+    Ok(InspectorContent { title, id, parts })
 }
 
 pub fn parse_channel_method(answer: &str) -> ChannelMethod {
@@ -544,6 +1299,17 @@ pub fn parse_channel_method(answer: &str) -> ChannelMethod {
     ChannelMethod::Unknown(answer.to_string())
 }
 
+///
+/// Whether the output of an ended Lisp process tells that it ran out of heap, e.g.
+/// "fatal error encountered in SBCL pid 4711: Heap exhausted, game over."
+///
+pub fn is_heap_exhaustion(output: &[String]) -> bool {
+    output.iter().any(|line| {
+        let line = line.to_lowercase();
+        line.contains("heap exhausted") || line.contains("heap exhaustion") || line.contains("memory allocation request failed")
+    })
+}
+
 pub fn nil_or_string<T: Into<String>>(form: Option<T>) -> String {
     match form {
         Some(is) => format!("\"{}\"", is.into()),
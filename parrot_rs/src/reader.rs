@@ -0,0 +1,425 @@
+use serde::{Deserialize, Serialize};
+
+///
+/// A form read from Lisp source text.
+/// `start` and `end` are byte offsets into the text the form was read from.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Form {
+    pub kind: FormKind,
+    pub start: usize,
+    pub end: usize
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FormKind {
+    List(Vec<Form>),
+    // symbols, numbers, characters and everything else that is not a list or a string, as written
+    Atom(String),
+    // the unescaped content of a string
+    Str(String),
+    // e.g. 'x, `x, ,x, ,@x, #'x, #.x, #p"x", #(x)
+    Prefixed {
+        prefix: String,
+        form: Box<Form>
+    },
+    // #+feature form, #-feature form
+    Conditional {
+        positive: bool,
+        feature: Box<Form>,
+        form: Box<Form>
+    }
+}
+
+///
+/// Reads all top-level forms of the given text.
+/// The reader is tolerant: unbalanced closing parens are skipped, unterminated lists and strings end at the end of the text.
+///
+pub fn read_all(text: &str) -> Vec<Form> {
+    let mut reader = Reader { text, bytes: text.as_bytes(), pos: 0 };
+    let mut forms = vec![];
+    while let Some(f) = reader.read_form() {
+        forms.push(f);
+    }
+    forms
+}
+
+///
+/// Returns the forms that would be read on an SBCL image, assuming `#+feature` forms are included
+/// and `#-feature` forms are excluded, as the features of the image are not known statically.
+///
+pub fn included_forms(forms: &[Form]) -> Vec<&Form> {
+    let mut included = vec![];
+    for f in forms {
+        match &f.kind {
+            FormKind::Conditional { positive: true, form, .. } => included.push(form.as_ref()),
+            FormKind::Conditional { positive: false, .. } => (),
+            _ => included.push(f)
+        }
+    }
+    included
+}
+
+///
+/// Get the value following the given keyword in a property list, e.g. `:serial` in (:serial t :components (...)).
+///
+pub fn plist_get<'a>(items: &[&'a Form], key: &str) -> Option<&'a Form> {
+    items
+        .iter()
+        .position(|f| f.is_keyword(key))
+        .and_then(|ix| items.get(ix + 1))
+        .copied()
+}
+
+///
+/// Whether the token is a potential number (CLHS 2.3.1.1) with *read-base* 10, i.e. it does not
+/// read as a symbol: digits, signs, ratio markers, decimal points, ^ and _ and single letters
+/// as number markers, at least one digit, not starting with a letter and not ending with a sign.
+/// E.g. 42, -1.5e3 and 1/2 but not 1+, inf or nan.
+///
+pub fn is_potential_number(token: &str) -> bool {
+    let chars = token.chars().collect::<Vec<char>>();
+    chars.iter().any(|c| c.is_ascii_digit())
+        && chars.iter().all(|c| c.is_ascii_alphanumeric() || "+-/.^_".contains(*c))
+        && matches!(chars.first(), Some(c) if c.is_ascii_digit() || "+-.^_".contains(*c))
+        && !matches!(chars.last(), Some('+' | '-'))
+        && !chars.windows(2).any(|w| w[0].is_ascii_alphabetic() && w[1].is_ascii_alphabetic())
+}
+
+///
+/// Get the package governing the given byte position, i.e. the one named by the last top-level
+/// (in-package ...) form before it. Returns None if there is none, in which case the REPL's package applies.
+///
+pub fn in_package_at(text: &str, pos: usize) -> Option<String> {
+    read_all(text)
+        .iter()
+        .take_while(|f| f.end <= pos)
+        .filter(|f| f.head_is("in-package"))
+        .filter_map(|f| f.as_list().unwrap().get(1).and_then(|p| p.string_designator()))
+        .last()
+}
+
+///
+/// Get the top-level (defvar ...) form the given byte position is in, e.g. to evaluate it again.
+///
+pub fn defvar_at(text: &str, pos: usize) -> Option<&str> {
+    read_all(text)
+        .iter()
+        .find(|f| f.start <= pos && pos <= f.end)
+        .filter(|f| f.head_is("defvar"))
+        .map(|f| f.text(text))
+}
+
+impl Form {
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+
+    pub fn as_list(&self) -> Option<&Vec<Form>> {
+        match &self.kind {
+            FormKind::List(items) => Some(items),
+            _ => None
+        }
+    }
+
+    pub fn as_atom(&self) -> Option<&str> {
+        match &self.kind {
+            FormKind::Atom(a) => Some(a),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.kind {
+            FormKind::Str(s) => Some(s),
+            _ => None
+        }
+    }
+
+    ///
+    /// The name of the symbol as the Lisp reader would intern it, i.e. without package prefix,
+    /// unescaped parts upcased and |escaped| parts kept as written.
+    /// Returns None for non-symbols (lists, strings, numbers, characters).
+    ///
+    pub fn symbol_name(&self) -> Option<String> {
+        let token = self.as_atom()?;
+        if token.starts_with("#\\") || is_potential_number(token) {
+            return None;
+        }
+        let token = token.strip_prefix('#').unwrap_or(token);
+        let mut name = String::new();
+        let mut chars = token.chars();
+        let mut in_pipe = false;
+        while let Some(c) = chars.next() {
+            match c {
+                '|' => in_pipe = !in_pipe,
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        name.push(escaped);
+                    }
+                },
+                ':' if !in_pipe => name.clear(),
+                c if in_pipe => name.push(c),
+                c => name.extend(c.to_uppercase())
+            }
+        }
+        Some(name)
+    }
+
+    ///
+    /// Strings as written, symbols by their name, as e.g. defpackage treats its arguments.
+    ///
+    pub fn string_designator(&self) -> Option<String> {
+        match &self.kind {
+            FormKind::Str(s) => Some(s.clone()),
+            _ => self.symbol_name()
+        }
+    }
+
+    pub fn is_keyword(&self, name: &str) -> bool {
+        match self.as_atom() {
+            Some(a) => a.starts_with(':') && a[1..].eq_ignore_ascii_case(name),
+            None => false
+        }
+    }
+
+    ///
+    /// True if the form is a symbol with the given name, in any package, e.g. `defsystem` for asdf:defsystem.
+    ///
+    pub fn is_symbol(&self, name: &str) -> bool {
+        self.symbol_name()
+            .map(|n| n.eq_ignore_ascii_case(name))
+            .unwrap_or(false)
+    }
+
+    ///
+    /// True if the form is a list whose first element is a symbol with the given name.
+    ///
+    pub fn head_is(&self, name: &str) -> bool {
+        self.as_list()
+            .and_then(|items| items.first())
+            .map(|h| h.is_symbol(name))
+            .unwrap_or(false)
+    }
+
+    ///
+    /// True if the form is a list whose first element is the given keyword, e.g. (:export ...) for `export`.
+    ///
+    pub fn head_is_keyword(&self, name: &str) -> bool {
+        self.as_list()
+            .and_then(|items| items.first())
+            .map(|h| h.is_keyword(name))
+            .unwrap_or(false)
+    }
+}
+
+struct Reader<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Reader<'a> {
+    fn read_form(&mut self) -> Option<Form> {
+        loop {
+            self.skip_whitespace_and_comments();
+            if self.pos >= self.bytes.len() {
+                return None;
+            }
+            let start = self.pos;
+            match self.bytes[self.pos] {
+                b')' => {
+                    // unbalanced, skip
+                    self.pos += 1;
+                },
+                b'(' => {
+                    self.pos += 1;
+                    let items = self.read_list_items();
+                    return Some(Form { kind: FormKind::List(items), start, end: self.pos });
+                },
+                b'"' => {
+                    let s = self.read_string();
+                    return Some(Form { kind: FormKind::Str(s), start, end: self.pos });
+                },
+                b'\'' | b'`' => {
+                    self.pos += 1;
+                    return Some(self.read_prefixed(start));
+                },
+                b',' => {
+                    self.pos += 1;
+                    if self.peek() == Some(b'@') {
+                        self.pos += 1;
+                    }
+                    return Some(self.read_prefixed(start));
+                },
+                b'#' => return Some(self.read_dispatch(start)),
+                _ => {
+                    let token = self.read_token();
+                    return Some(Form { kind: FormKind::Atom(token), start, end: self.pos });
+                }
+            }
+        }
+    }
+
+    fn read_list_items(&mut self) -> Vec<Form> {
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace_and_comments();
+            match self.peek() {
+                None => break,
+                Some(b')') => {
+                    self.pos += 1;
+                    break;
+                },
+                _ => match self.read_form() {
+                    Some(f) => items.push(f),
+                    None => break
+                }
+            }
+        }
+        items
+    }
+
+    fn read_prefixed(&mut self, start: usize) -> Form {
+        let prefix = self.text[start..self.pos].to_string();
+        match self.read_form() {
+            Some(f) => Form { kind: FormKind::Prefixed { prefix, form: Box::new(f) }, start, end: self.pos },
+            None => Form { kind: FormKind::Atom(prefix), start, end: self.pos }
+        }
+    }
+
+    fn read_dispatch(&mut self, start: usize) -> Form {
+        match self.bytes.get(self.pos + 1).copied() {
+            Some(b'\'') | Some(b'.') => {
+                self.pos += 2;
+                self.read_prefixed(start)
+            },
+            Some(b'p') | Some(b'P') | Some(b'(') => {
+                self.pos += if self.bytes[self.pos + 1] == b'(' { 1 } else { 2 };
+                self.read_prefixed(start)
+            },
+            Some(sign @ (b'+' | b'-')) => {
+                self.pos += 2;
+                let feature = self.read_form();
+                let form = self.read_form();
+                match (feature, form) {
+                    (Some(feature), Some(form)) => Form {
+                        kind: FormKind::Conditional { positive: sign == b'+', feature: Box::new(feature), form: Box::new(form) },
+                        start,
+                        end: self.pos
+                    },
+                    _ => Form { kind: FormKind::Atom(self.text[start..self.pos].to_string()), start, end: self.pos }
+                }
+            },
+            Some(b'\\') => {
+                // character, the first char after #\ is always part of it, e.g. #\( or #\Space
+                self.pos += 2;
+                if let Some(c) = self.text[self.pos..].chars().next() {
+                    self.pos += c.len_utf8();
+                }
+                self.read_token_rest();
+                Form { kind: FormKind::Atom(self.text[start..self.pos].to_string()), start, end: self.pos }
+            },
+            _ => {
+                self.pos += 1;
+                self.read_token_rest();
+                Form { kind: FormKind::Atom(self.text[start..self.pos].to_string()), start, end: self.pos }
+            }
+        }
+    }
+
+    fn read_string(&mut self) -> String {
+        // skip opening quote
+        self.pos += 1;
+        let mut value = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        let mut end = self.bytes.len();
+        while let Some((ix, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        value.push(escaped);
+                    }
+                },
+                '"' => {
+                    end = self.pos + ix + 1;
+                    break;
+                },
+                c => value.push(c)
+            }
+        }
+        self.pos = end;
+        value
+    }
+
+    fn read_token(&mut self) -> String {
+        let start = self.pos;
+        self.read_token_rest();
+        self.text[start..self.pos].to_string()
+    }
+
+    fn read_token_rest(&mut self) {
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'|' => {
+                    self.pos += 1;
+                    while self.pos < self.bytes.len() && self.bytes[self.pos] != b'|' {
+                        if self.bytes[self.pos] == b'\\' {
+                            self.pos += 1;
+                        }
+                        self.pos += 1;
+                    }
+                    self.pos += 1;
+                },
+                b'\\' => self.pos += 2,
+                c if is_delimiter(c) => break,
+                _ => self.pos += 1
+            }
+        }
+        self.pos = self.pos.min(self.bytes.len());
+        // an escape may have skipped into a multi-byte char
+        while !self.text.is_char_boundary(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                b';' => {
+                    while self.pos < self.bytes.len() && self.bytes[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                },
+                b'#' if self.bytes.get(self.pos + 1) == Some(&b'|') => {
+                    // block comments nest
+                    let mut depth = 0;
+                    while self.pos < self.bytes.len() {
+                        if self.bytes[self.pos..].starts_with(b"#|") {
+                            depth += 1;
+                            self.pos += 2;
+                        } else if self.bytes[self.pos..].starts_with(b"|#") {
+                            depth -= 1;
+                            self.pos += 2;
+                            if depth == 0 {
+                                break;
+                            }
+                        } else {
+                            self.pos += 1;
+                        }
+                    }
+                },
+                _ => break
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+}
+
+fn is_delimiter(c: u8) -> bool {
+    c.is_ascii_whitespace() || matches!(c, b'(' | b')' | b'"' | b';' | b'\'' | b'`' | b',')
+}
@@ -5,22 +5,49 @@ use std::thread;
 use std::io::{BufRead, BufReader, Write, Read, ErrorKind};
 use std::process::{Command, Stdio, Child, ChildStdin};
 use std::sync::{Mutex, Arc, RwLock};
-use crossbeam::{unbounded, bounded, Sender, Receiver};
+use std::sync::atomic::{AtomicBool, Ordering};
+use crossbeam::{unbounded, bounded, Sender, Receiver, TrySendError};
 use regex::{Regex, RegexBuilder};
 use serde::{Serialize, Deserialize};
 
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use std::fs;
 use os_pipe::{self, PipeWriter};
 use utf8_chars::BufReadCharsExt;
 use crate::models::*;
-use crate::text::{trim_quotes, unescape_quotes, escape_quotes, quote, escape_form};
+use crate::text::{trim_quotes, unescape_quotes, escape_quotes, quote, escape_form, trim_quote_op};
 use crate::{BackendResult, BackendError};
 use lazy_static::lazy_static;
 use sexp::{self, Atom, Sexp};
 use crate::parsing::*;
+use crate::diagnostics::Diagnostics;
+use crate::session::{SessionRecord, replay_form};
+use crate::watch::{WatchList, watch_form};
+use crate::indentation::IndentationTable;
+use crate::fts::definitions::merge_completions;
+use crate::config::LaunchProfile;
+use crate::testing::{test_form, test_run};
 
 pub const STOP_SIG: &str = "REPL~QUIT"; 
+// read by start-slynk.lisp, the file the image writes its Slynk port to
+pub const PORT_FILE_ENV: &str = "PARROT_SLYNK_PORT_FILE";
+// restarts after crashes in a row wait 1s, 2s, 4s, ... and are given up after this many
+pub const MAX_CRASH_RESTARTS: u32 = 5;
+// an image that ran this long before crashing starts the backoff anew
+const CRASH_RESTART_RESET: Duration = Duration::from_secs(300);
+// how many lines of the process output are sent with ProcessExited
+const EXIT_OUTPUT_LINES: usize = 50;
+// how long a completion request waits for the writer thread, it is busy if the image does not read
+const COMPLETION_SEND_TIMEOUT: Duration = Duration::from_millis(500);
+// how long the image has to answer a completion request before the offline completions are used
+pub const COMPLETION_TIMEOUT: Duration = Duration::from_secs(2);
+
+// turns a ql-dist system into (name release dist)
+const QL_SYSTEM_TO_LIST: &str = r#"(lambda (system)
+    (list (uiop:symbol-call :ql-dist :name system)
+          (uiop:symbol-call :ql-dist :name (uiop:symbol-call :ql-dist :release system))
+          (uiop:symbol-call :ql-dist :name (uiop:symbol-call :ql-dist :dist system))))"#;
 
 
 pub struct REPL {
@@ -29,18 +56,27 @@ pub struct REPL {
     // parameters
     //
 
-    // the slynk server to talk to, e.g. 127.0.0.1:4005
+    // the slynk server to talk to, e.g. 127.0.0.1:4005, the port is replaced by the one in port_file
     socket: String,
+    // written by the image once Slynk listens
+    port_file: String,
+    // how long to wait for the port file
+    startup_timeout: Duration,
 
-    // sbcl path
-    path: String,
-    // sbcl args
-    args: Vec<String>,
+    // how to start the image
+    profile: LaunchProfile,
+    // slynk/start-slynk.lisp next to the executable
+    slynk_start_path: String,
 
     //
     // process & commmunication
     //
-    child: Child,
+    child: Arc<Mutex<Child>>,
+    // set when the process is killed on purpose, so that the supervisor does not report it
+    stopping: Arc<AtomicBool>,
+    started: Instant,
+    // restarts after crashes in a row, see restart_after_crash
+    crash_restarts: u32,
     slynk_repl_sender: Sender<SlynkMessage>,
     // receives Answers from Slynk server
     rec_tcp: Receiver<SlynkAnswer>,
@@ -70,6 +106,18 @@ pub struct REPL {
     // current prompt
     prompt: Arc<Mutex<String>>,
 
+    // compiler notes of all compilations, kept across restarts
+    diagnostics: Arc<Mutex<Diagnostics>>,
+
+    // what has been loaded, to load it again after a restart, kept across restarts
+    session: Arc<Mutex<SessionRecord>>,
+
+    // indentation of the standard operators and the macros defined in the image
+    indentation: Arc<Mutex<IndentationTable>>,
+
+    // forms whose values are shown while developing, the forms are kept across restarts in state.json
+    watches: Arc<Mutex<WatchList>>,
+
     //
     // meta
     //
@@ -79,24 +127,36 @@ pub struct REPL {
 
 
 impl REPL {
-    pub fn new<T: AsRef<str>>(socket: String, path: &str, args: &[T]) -> Self {
-        println!("REPL::new(socket={}, path={}, args={:?})", &socket, path, args.iter().map(|a| a.as_ref()).collect::<Vec<&str>>());
+    pub fn new(socket: String, port_file: String, startup_timeout: Duration, profile: LaunchProfile, slynk_start_path: String) -> Self {
+        let session = SessionRecord::load().unwrap_or_default();
+        Self::start(socket, port_file, startup_timeout, profile, slynk_start_path, Arc::new(Mutex::new(Diagnostics::new())), Arc::new(Mutex::new(session)))
+    }
 
-        let mut c = Command::new(path);
-        for a in args {
-            c.arg(a.as_ref());
+    fn start(socket: String, port_file: String, startup_timeout: Duration, profile: LaunchProfile, slynk_start_path: String, diagnostics: Arc<Mutex<Diagnostics>>, session: Arc<Mutex<SessionRecord>>) -> Self {
+        let args = profile.command_args(&slynk_start_path);
+        println!("REPL::new(socket={}, port_file={}, profile={}, path={}, args={:?})", &socket, &port_file, &profile.name, &profile.executable, &args);
+
+        // a stale file from a previous start would announce the wrong port
+        let _ = fs::remove_file(&port_file);
+        let mut c = Command::new(&profile.executable);
+        c.args(&args);
+        c.envs(&profile.env);
+        if let Some(dir) = &profile.working_directory {
+            c.current_dir(dir);
         }
+        c.env(PORT_FILE_ENV, &port_file);
         let (reader, writer_out) = os_pipe::pipe().unwrap();
         let writer_err = writer_out.try_clone().unwrap();
         // let writer_cancel = writer_out.try_clone().unwrap();
 
-        let child = 
+        let mut child = 
             c
-            // .stdin(Stdio::piped())
+            // the REPL goes through Slynk, nothing may wait for input from the app's terminal
+            .stdin(Stdio::null())
             .stdout(writer_out)
             .stderr(writer_err)
             .spawn()
-            .expect("SBCL command failed to start");
+            .expect("Lisp command failed to start");
         
         
         let (sender_tcp, rec_tcp) = bounded::<SlynkAnswer>(0);
@@ -107,6 +167,10 @@ impl REPL {
         let arc_cont = Arc::new(Mutex::new(0 as usize));
         let arc_package = Arc::new(Mutex::new(String::from("COMMON-LISP-USER")));
         let arc_prompt = Arc::new(Mutex::new(String::from("CL-USER")));
+        // (channel id, thread id) of the mrepl, as returned by slynk-mrepl:create-mrepl
+        let arc_mrepl = Arc::new(Mutex::new(None::<(usize, usize)>));
+        let arc_indentation = Arc::new(Mutex::new(IndentationTable::new()));
+        let watches = Arc::new(Mutex::new(WatchList::load().unwrap_or_default()));
 
         let mut init_err: Option<String> = None;
         let out_buf = Arc::new(Mutex::new(String::from("")));
@@ -145,27 +209,99 @@ impl REPL {
 
         let pending = Arc::new(Mutex::new(HashMap::<usize, ContinuationCallback>::new()));
 
-        let mut tcp_write_try = TcpStream::connect(&socket);
-        let mut connect_try = 1;
-        while tcp_write_try.is_err() && connect_try < 6 {
-            println!("Could not connect to Slynk server over socket {}. Retry {} of 5 in 500ms...", &socket, &connect_try);
-            thread::sleep(Duration::from_millis(500));
-            connect_try += 1;
-            tcp_write_try = TcpStream::connect(&socket);
-        }
+        let host = socket.rsplit_once(':').map(|(host, _)| host).unwrap_or(&socket);
+        let tcp_write_try = wait_for_port_file(&port_file, startup_timeout, &mut child)
+            .and_then(|port| TcpStream::connect(format!("{}:{}", host, port))
+                .map_err(|e| BackendError(format!("Could not connect to {}:{}: {}", host, port, e))));
+        let child = Arc::new(Mutex::new(child));
+        let stopping = Arc::new(AtomicBool::new(false));
 
         if let Err(e) = tcp_write_try {
-            init_err = Some(format!("Failed to connect to Slynk server.\n{}", &e.to_string()));
+            init_err = Some(format!("Failed to connect to Slynk server.\n{}", &e.0));
         } else {
-            let mut tcp_write = tcp_write_try.expect(&format!("ABORT: Could not connect to Slynk server over socket {}.", &socket));
+            //
+            // thread that watches the process, e.g. SBCL exits on heap exhaustion
+            //
+            let child_handle = child.clone();
+            let stopping_handle = stopping.clone();
+            let sbcl_process_out_handle = sbcl_process_out.clone();
+            let exit_sender = sender_tcp.clone();
+            thread::spawn(move || {
+                let status = loop {
+                    // not wait(), quit() has to be able to kill the process meanwhile
+                    match child_handle.lock().unwrap().try_wait() {
+                        Ok(Some(status)) => break Some(status),
+                        Ok(None) => {},
+                        Err(_) => break None
+                    }
+                    thread::sleep(Duration::from_millis(250));
+                };
+                if stopping_handle.load(Ordering::SeqCst) {
+                    return;
+                }
+                // let the listener thread collect the last output
+                thread::sleep(Duration::from_millis(200));
+                let output: Vec<String> = {
+                    let out = sbcl_process_out_handle.lock().unwrap();
+                    out.iter().skip(out.len().saturating_sub(EXIT_OUTPUT_LINES)).cloned().collect()
+                };
+                println!("Lisp process exited: {:?}", &status);
+                let _ = exit_sender.send(SlynkAnswer::ProcessExited {
+                    code: status.and_then(|s| s.code()),
+                    heap_exhausted: is_heap_exhaustion(&output),
+                    output
+                });
+            });
+
+            //
+            // thread that evaluates the watches on their interval
+            //
+            // set by the reader thread if the writer thread was busy when the watches were stale
+            let watches_requested = Arc::new(AtomicBool::new(false));
+            let watches_handle = watches.clone();
+            let stopping_handle = stopping.clone();
+            let watches_sender = slynk_repl_sender.clone();
+            let watches_requested_handle = watches_requested.clone();
+            thread::spawn(move || {
+                let mut last = Instant::now();
+                while !stopping_handle.load(Ordering::SeqCst) {
+                    thread::sleep(Duration::from_millis(500));
+                    let requested = watches_requested_handle.swap(false, Ordering::SeqCst);
+                    let watches = {
+                        let watches = watches_handle.lock().unwrap();
+                        match watches.refresh() {
+                            WatchRefresh::Interval { seconds } if last.elapsed() >= Duration::from_secs(*seconds) && !watches.watches().is_empty() =>
+                                Some(watches.watches().to_vec()),
+                            _ if requested => Some(watches.watches().to_vec()),
+                            _ => None
+                        }
+                    };
+                    if let Some(watches) = watches {
+                        last = Instant::now();
+                        // the writer thread is gone after a crash
+                        if watches_sender.send(SlynkMessage::EvaluateWatches(watches)).is_err() {
+                            break;
+                        }
+                    }
+                }
+            });
+
+            let mut tcp_write = tcp_write_try.expect("checked above");
             let mut tcp_read = tcp_write.try_clone().expect("Could not clone stream");
 
             // // thread that reads incoming messages from swank/slynk server
             let package_handle = arc_package.clone();
             let prompt_handle = arc_prompt.clone();
+            let mrepl_handle_out = arc_mrepl.clone();
             let pending_handle_out = pending.clone();
             let slynk_repl_sender_handle = slynk_repl_sender.clone();
+            let diagnostics_handle = diagnostics.clone();
+            let indentation_handle = arc_indentation.clone();
+            let progress_sender = sender_tcp.clone();
+            let watches_handle = watches.clone();
             thread::spawn(move || {
+                // threads in the debugger entered while evaluating the watches
+                let mut watch_debug_threads: Vec<usize> = vec![];
 
                 loop {
                     let mut buf = [0 as u8;6];
@@ -183,7 +319,7 @@ impl REPL {
                     let mut bbuf = vec![0 as u8; message_len as usize];
                     tcp_read.read_exact(&mut bbuf).expect("Could not read message body.");
                     let body = String::from_utf8(bbuf).unwrap();
-                    let rets = pending_handle_out
+                    let mut rets = pending_handle_out
                         .lock()
                         .unwrap();
                     let ccb = match get_continuation(&body) {
@@ -193,6 +329,31 @@ impl REPL {
                     let sw = parse_slynk_answer(&body, ccb);
 
                     let mut handled = false;
+                    let mut pong = None;
+                    // the watches show the state after the evaluation
+                    let watches_stale = match &sw {
+                        SlynkAnswer::ChannelSend { method: ChannelMethod::Prompt { .. }, .. } => true,
+                        SlynkAnswer::Debug { thread, continuations, .. } => {
+                            let from_watches = continuations
+                                .iter()
+                                .filter_map(|c| c.parse::<usize>().ok())
+                                .any(|c| matches!(rets.get(&c), Some(ContinuationCallback::UpdateWatches)));
+                            if from_watches && !watch_debug_threads.contains(thread) {
+                                watch_debug_threads.push(*thread);
+                            }
+                            false
+                        },
+                        // refreshing after leaving a debugger the watches entered would loop
+                        SlynkAnswer::DebugReturn { thread, level } => {
+                            let from_watches = watch_debug_threads.contains(thread);
+                            if *level <= 1 {
+                                watch_debug_threads.retain(|t| t != thread);
+                            }
+                            !from_watches
+                        },
+                        SlynkAnswer::ReturnCompilationResult { continuation, .. } => matches!(rets.get(continuation), Some(ContinuationCallback::LoadFile(_))),
+                        _ => false
+                    };
 
                     // handle answer
                     println!("Got SlynkAnswer: {:?}", &sw);
@@ -203,8 +364,31 @@ impl REPL {
                                 let mut pa = package_handle.lock().unwrap();
                                 *pr = prompt.clone();
                                 *pa = package.clone();
+                            } else if let ChannelMethod::WriteString(text) = method {
+                                send_quickload_progress(&rets, text, &sender_tcp);
                             }
                         },
+                        SlynkAnswer::WriteString { value, .. } => {
+                            send_quickload_progress(&rets, value, &sender_tcp);
+                        },
+                        SlynkAnswer::Ping { thread, tag } => {
+                            // the image blocks until it gets the pong, sent below
+                            pong = Some((*thread, *tag));
+                            handled = true;
+                        },
+                        SlynkAnswer::NewPackage { package_name, prompt } => {
+                            *prompt_handle.lock().unwrap() = prompt.clone();
+                            *package_handle.lock().unwrap() = package_name.clone();
+                        },
+                        SlynkAnswer::IndentationUpdate { specs } => {
+                            indentation_handle.lock().unwrap().update(specs.clone());
+                            handled = true;
+                        },
+                        SlynkAnswer::InvalidRpc { continuation, message } => {
+                            rets.remove(continuation);
+                            sender_tcp.send(SlynkAnswer::Notify { text: message.clone(), error: true }).expect("Could not send");
+                            handled = true;
+                        },
                         SlynkAnswer::Return { continuation, status, value } => {
                             if rets.contains_key(continuation) {
                                 match &rets[continuation] {
@@ -222,24 +406,122 @@ impl REPL {
                                     ContinuationCallback::DisplayPackages(cont) => 
                                         sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_package_list(&value).unwrap()).unwrap() }).expect("Could not send"),
                                     ContinuationCallback::DisplaySymbolsInPackage(cont) => 
-                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_symbol_list(value.clone()).unwrap()).unwrap() }).expect("Could not send"),
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_symbols(value).unwrap_or_default()).unwrap() }).expect("Could not send"),
                                     ContinuationCallback::DisplayDescribe(cont) => 
-                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_describe(value.clone()).unwrap()).unwrap() }).expect("Could not send"),
-                                    ContinuationCallback::DisplayApropos(cont) => 
-                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_apropos(value.clone()).unwrap()).unwrap() }).expect("Could not send"),
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_describe(value).unwrap_or(None)).unwrap() }).expect("Could not send"),
+                                    ContinuationCallback::DisplayApropos(cont) => {
+                                        // null if the hits could not be read, which is not the same as no hits
+                                        let hits = match parse_apropos(value) {
+                                            Ok(hits) => Some(hits),
+                                            Err(e) => {
+                                                sender_tcp.send(SlynkAnswer::Notify { text: format!("Could not read the apropos results: {}", e.0), error: true }).expect("Could not send");
+                                                None
+                                            }
+                                        };
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&hits).unwrap() }).expect("Could not send")
+                                    },
+                                    ContinuationCallback::DisplayClassInfo(cont) => 
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_class_info(value).unwrap_or(None)).unwrap() }).expect("Could not send"),
+                                    ContinuationCallback::DisplayGenericFunctionInfo(cont) => 
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_generic_function_info(value).unwrap_or(None)).unwrap() }).expect("Could not send"),
                                     ContinuationCallback::DisplayFrameLocals(cont) => 
                                         sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_frame_locals(value).unwrap()).unwrap() }).expect("Could not send"),
-                                    ContinuationCallback::DisplayCompletions(cont) => 
-                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_flex_completions(value).unwrap()).unwrap() }).expect("Could not send"),
-                                     _ => ()
+                                    ContinuationCallback::DisplayCompletions { cont, offline } => {
+                                        let live = parse_flex_completions(value).unwrap_or_default();
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&merge_completions(live, offline.clone())).unwrap() }).expect("Could not send")
+                                    },
+                                    ContinuationCallback::LoadFile(_) | ContinuationCallback::CompileForm { .. } => 
+                                        sender_tcp.send(SlynkAnswer::Notify { text: value.clone(), error: true }).expect("Could not send"),
+                                    ContinuationCallback::OperateOnSystem { system, operation, .. } => 
+                                        sender_tcp.send(SlynkAnswer::SystemOperation { system: system.clone(), operation: operation.clone(), stage: SystemOperationStage::Finished { success: false, duration: 0.0, notes: 0 } }).expect("Could not send"),
+                                    ContinuationCallback::DisplayQuicklispSystems(cont) => 
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_quicklisp_systems(value).unwrap_or_default()).unwrap() }).expect("Could not send"),
+                                    ContinuationCallback::DisplayQuicklispDists(cont) => 
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_quicklisp_dists(value).unwrap_or_default()).unwrap() }).expect("Could not send"),
+                                    ContinuationCallback::PackageFuResult(cont) => 
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&(matches!(status, ReturnStatus::Ok) && parse_package_fu_result(value))).unwrap() }).expect("Could not send"),
+                                    ContinuationCallback::DisplayExportedSymbols(cont) => 
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_exported_symbols(value)).unwrap() }).expect("Could not send"),
+                                    ContinuationCallback::CreateMrepl => {
+                                        if let Ok(ids) = parse_mrepl_ids(value) {
+                                            *mrepl_handle_out.lock().unwrap() = Some(ids);
+                                        }
+                                    },
+                                    ContinuationCallback::SetMreplPrompt => {
+                                        match parse_mrepl_prompt(value) {
+                                            Ok(ChannelMethod::Prompt { package, prompt, elevel, len_history, condition }) if matches!(status, ReturnStatus::Ok) => {
+                                                *prompt_handle.lock().unwrap() = prompt.clone();
+                                                *package_handle.lock().unwrap() = package.clone();
+                                                sender_tcp.send(SlynkAnswer::ChannelSend { channel: 1, method: ChannelMethod::Prompt { package, prompt, elevel, len_history, condition } }).expect("Could not send");
+                                            },
+                                            _ => sender_tcp.send(SlynkAnswer::Notify { text: "Could not set the REPL's package.".to_string(), error: true }).expect("Could not send")
+                                        }
+                                    },
+                                    ContinuationCallback::DisplayPackageInfos(cont) => 
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&parse_package_infos(value).unwrap_or_default()).unwrap() }).expect("Could not send"),
+                                    ContinuationCallback::DisplayTestRun { request, locations, cont } => {
+                                        // the tests aborted, e.g. from the debugger
+                                        let run = parse_grabbed_output_and_value(value)
+                                            .ok()
+                                            .filter(|_| matches!(status, ReturnStatus::Ok))
+                                            .map(|(output, duration)| test_run(request, &output, duration.parse().unwrap_or_default(), locations));
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&run).unwrap() }).expect("Could not send");
+                                    },
+                                    ContinuationCallback::DisplayValueForEditing(cont) => {
+                                        // null if the form could not be evaluated
+                                        let text = parse_returned_string(value).ok().filter(|_| matches!(status, ReturnStatus::Ok));
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&text).unwrap() }).expect("Could not send")
+                                    },
+                                    ContinuationCallback::CommitEditedValue(cont) =>
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&matches!(status, ReturnStatus::Ok)).unwrap() }).expect("Could not send"),
+                                    ContinuationCallback::ReEvaluateDefvar(cont) => {
+                                        // the variable's name, null if the form could not be evaluated
+                                        let name = parse_returned_string(value).ok().filter(|_| matches!(status, ReturnStatus::Ok));
+                                        if let Some(name) = &name {
+                                            sender_tcp.send(SlynkAnswer::Notify { text: format!("Re-evaluated {}.", name), error: false }).expect("Could not send");
+                                        }
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&name).unwrap() }).expect("Could not send")
+                                    },
+                                    ContinuationCallback::UpdateWatches => {
+                                        if let (ReturnStatus::Ok, Ok(values)) = (status, parse_watch_values(value)) {
+                                            let mut watches = watches_handle.lock().unwrap();
+                                            for (id, value, error) in values {
+                                                watches.update(id, value, error);
+                                            }
+                                            sender_tcp.send(SlynkAnswer::WatchesUpdated(watches.watches().to_vec())).expect("Could not send");
+                                        }
+                                    },
+                                    ContinuationCallback::Quickload { systems, cont } => {
+                                        let results = match status {
+                                            ReturnStatus::Ok => parse_quickload_results(value).unwrap_or_default(),
+                                            ReturnStatus::Abort => systems
+                                                .iter()
+                                                .map(|s| QuickloadResult { system: s.clone(), success: false, error: Some("Aborted.".to_string()) })
+                                                .collect()
+                                        };
+                                        let failed = results.iter().filter(|r| !r.success).map(|r| r.system.as_str()).collect::<Vec<&str>>();
+                                        let text = if failed.is_empty() {
+                                            format!("Loaded {}.", systems.join(", "))
+                                        } else {
+                                            format!("Failed to load {}.", failed.join(", "))
+                                        };
+                                        sender_tcp.send(SlynkAnswer::Notify { text, error: !failed.is_empty() }).expect("Could not send");
+                                        sender_tcp.send(SlynkAnswer::ResolvePending { continuation: *cont, data: serde_json::to_string(&results).unwrap() }).expect("Could not send");
+                                    }
                                 }
                                 handled = true;
+                                // output written after a quickload finished is no progress anymore
+                                if matches!(rets.get(continuation), Some(ContinuationCallback::Quickload { .. })) {
+                                    rets.remove(continuation);
+                                }
                             }
                          },
-                         SlynkAnswer::ReturnCompilationResult { continuation, success, fasl_file, .. } => {
+                         SlynkAnswer::ReturnCompilationResult { continuation, success, fasl_file, notes, duration, .. } => {
                             if rets.contains_key(continuation) {
-                                match rets[continuation] {
-                                    ContinuationCallback::LoadFile => {
+                                let notes = notes.clone().unwrap_or_default();
+                                match &rets[continuation] {
+                                    ContinuationCallback::LoadFile(path) => {
+                                        diagnostics_handle.lock().unwrap().replace_notes_for_files(std::slice::from_ref(path), notes);
                                         if !success {
                                             sender_tcp.send(SlynkAnswer::Notify { text: "Failed to compile file.".to_string(), error: true }).expect("Could not send");
                                         } 
@@ -248,6 +530,21 @@ impl REPL {
                                                 .expect("Could not send");
                                         }
                                     },
+                                    ContinuationCallback::CompileForm { form, position, filename, buffer } => {
+                                        // the last line of the form is position.line + lines - 1
+                                        let to_line = position.line + form.lines().count().saturating_sub(1);
+                                        diagnostics_handle.lock().unwrap().replace_notes_for_lines(filename.as_ref().unwrap_or(buffer), position.line, to_line, notes)
+                                    },
+                                    ContinuationCallback::OperateOnSystem { system, operation, files } => {
+                                        let count = notes.len();
+                                        diagnostics_handle.lock().unwrap().replace_notes_for_files(files, notes);
+                                        sender_tcp.send(SlynkAnswer::SystemOperation { 
+                                            system: system.clone(), 
+                                            operation: operation.clone(), 
+                                            stage: SystemOperationStage::Finished { success: *success, duration: *duration, notes: count } 
+                                        }).expect("Could not send");
+                                        handled = true;
+                                    },
                                     _ => ()
                                 }
                             }
@@ -259,6 +556,24 @@ impl REPL {
                     if !handled {
                         sender_tcp.send(sw).expect("Failed to send.");
                     }
+                    // the writer thread needs the pending continuations to send the evaluation
+                    drop(rets);
+                    if let Some((thread, tag)) = pong {
+                        slynk_repl_sender_handle.send(SlynkMessage::EmacsPong(thread, tag)).expect("Could not send");
+                    }
+                    if watches_stale {
+                        let watches = {
+                            let watches = watches_handle.lock().unwrap();
+                            watches.refreshes_after_evaluation().then(|| watches.watches().to_vec())
+                        };
+                        // the writer thread may be waiting for this thread, e.g. on the pending
+                        // continuations, so the interval thread sends them if it is busy
+                        if let Some(watches) = watches {
+                            if let Err(TrySendError::Full(_)) = slynk_repl_sender_handle.try_send(SlynkMessage::EvaluateWatches(watches)) {
+                                watches_requested.store(true, Ordering::SeqCst);
+                            }
+                        }
+                    }
 
                 };
                 println!("Thread reading incoming messages from Slynk stopped.");
@@ -271,9 +586,11 @@ impl REPL {
             let cont_handle = arc_cont.clone();
             let package_handle = arc_package.clone();
             let pending_handle_in = pending.clone();
+            let mrepl_handle_in = arc_mrepl.clone();
             thread::spawn(move || {
 
                 // create mrepl
+                pending_handle_in.lock().unwrap().insert(*cont_handle.lock().unwrap(), ContinuationCallback::CreateMrepl);
                 let mreq = emacs_rex("(slynk-mrepl:create-mrepl 1)", &package_handle.lock().unwrap(), &cont_handle.lock().unwrap());
                 let hex_prefix = format!("{:#08x}", mreq.chars().count())[2..].to_string();
                 let message_full = format!("{}{}", hex_prefix, mreq);
@@ -291,23 +608,23 @@ impl REPL {
                             let escaped = escape_form(form);
                             emacs_channel_send(&format!("(:process \"{}\")", escaped), 1)
                         },
-                        SlynkMessage::InteractiveEval(form) => {
+                        SlynkMessage::InteractiveEval(form, package) => {
                             let escaped = escape_form(form);
                             pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::PrintReturnValue(PrintKind::Notification));
-                            emacs_rex(&format!("(slynk:interactive-eval \"{}\")", escaped), &package_handle.lock().unwrap(), &continuation)
+                            emacs_rex(&format!("(slynk:interactive-eval \"{}\")", escaped), &package_or_current(package, &package_handle), &continuation)
                         },
                         SlynkMessage::CompileAndLoadFile(path) => {
-                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::LoadFile);
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::LoadFile(path.clone()));
                             emacs_rex(&format!("(slynk:compile-file-for-emacs \"{}\" t)", path), &package_handle.lock().unwrap(), &continuation)
                         },
                         SlynkMessage::LoadFile(path) => {
                             pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::Print("File loaded.".to_string(), PrintKind::Repl));
                             emacs_rex(&format!("(slynk:load-file \"{}\")", trim_quotes(path.clone())), &package_handle.lock().unwrap(), &continuation)
                         },
-                        SlynkMessage::FindDefinitions(symbol) => {
+                        SlynkMessage::FindDefinitions(symbol, package) => {
                             let escaped = escape_quotes(symbol);
                             pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::JumpToDef);
-                            emacs_rex(&format!("(slynk:find-definitions-for-emacs \"{}\")", escaped), &package_handle.lock().unwrap(), &continuation)
+                            emacs_rex(&format!("(slynk:find-definitions-for-emacs \"{}\")", escaped), &package_or_current(package, &package_handle), &continuation)
                         },
                         SlynkMessage::Stop => { 
                             emacs_rex("(slynk:quit-lisp)", &package_handle.lock().unwrap(), &continuation)
@@ -322,11 +639,18 @@ impl REPL {
                         SlynkMessage::EmacsReturn(form, thread, tag) => {
                             emacs_return(form, *thread, tag)
                         },
-                        SlynkMessage::CompileStringForEmacs{ string, buffer, position, filename, policy} => {
+                        SlynkMessage::EmacsReturnBool(value, thread, tag) => {
+                            format!("(:emacs-return {} {} {})\n", thread, tag, bool_to_nil_or_t(value))
+                        },
+                        SlynkMessage::EmacsPong(thread, tag) => {
+                            format!("(:emacs-pong {} {})\n", thread, tag)
+                        },
+                        SlynkMessage::CompileStringForEmacs{ string, buffer, position, filename, policy, package } => {
                             // ignoring policy for now
                             let fname = nil_or_string(filename.to_owned());
                             let escaped = escape_quotes(string);
-                            emacs_rex_thread(&format!("(slynk:compile-string-for-emacs \"{}\" \"{}\" '((:position {}) (:line {} {})) {} 'nil)", escaped, buffer, position.pos, position.line, position.col, fname),  &package_handle.lock().unwrap(), 1, &continuation)
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::CompileForm { form: string.clone(), position: position.clone(), filename: filename.clone(), buffer: buffer.clone() });
+                            emacs_rex_thread(&format!("(slynk:compile-string-for-emacs \"{}\" \"{}\" '((:position {}) (:line {} {})) {} 'nil)", escaped, buffer, position.pos, position.line, position.col, fname),  &package_or_current(package, &package_handle), 1, &continuation)
                         },
                         SlynkMessage::ListAllPackages(cont) => {
                             pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayPackages(*cont));
@@ -334,40 +658,355 @@ impl REPL {
                         },
                         SlynkMessage::DescribeForSymbolInfo{ symbol, cont } => {
                             pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayDescribe(*cont));
-                            // todo: handle ' or #' in front of symbol
-                            emacs_rex(&format!("(slynk:eval-and-grab-output \"(describe {})\")", quote(symbol)), &package_handle.lock().unwrap(), &continuation)
+                            // 'my-var or #'my-fun describe the symbol
+                            let name = trim_quote_op(symbol.trim().to_string());
+                            let describe = format!(r#"
+                            (let ((*print-pretty* nil))
+                              (write ((lambda (name)
+                                (multiple-value-bind (sym found) (slynk::parse-symbol name)
+                                  (when found
+                                    (flet ((printed (x) 
+                                             (let* ((*print-case* :downcase) (*print-length* 20) (*print-level* 4) (s (prin1-to-string x)))
+                                               (if (> (length s) 500) (concatenate 'string (subseq s 0 500) "...") s)))
+                                           (described (kind) (ignore-errors (slynk:describe-definition-for-emacs name kind))))
+                                      (list :name (symbol-name sym)
+                                            :package (when (symbol-package sym) (package-name (symbol-package sym)))
+                                            :summary (slynk:documentation-symbol name)
+                                            :function (when (fboundp sym)
+                                                        (list (cond ((special-operator-p sym) :special-operator)
+                                                                    ((macro-function sym) :macro)
+                                                                    ((typep (fdefinition sym) 'generic-function) :generic-function)
+                                                                    (t :function))
+                                                              (let ((arglist (slynk-backend:arglist sym))) (when (listp arglist) (printed arglist)))
+                                                              #+sbcl (ignore-errors (printed (sb-introspect:function-type sym))) #-sbcl nil
+                                                              (documentation sym 'function)
+                                                              (let ((loc (ignore-errors (slynk-backend:find-source-location (or (macro-function sym) (fdefinition sym))))))
+                                                                (when (eq (car loc) :location) (second (assoc :file (cdr loc)))))
+                                                              (unless (special-operator-p sym) (described :function))))
+                                            :variable (when (or (boundp sym) #+sbcl (nth-value 1 (sb-int:info :variable :kind sym)))
+                                                        (list #+sbcl (sb-int:info :variable :kind sym) #-sbcl (if (constantp sym) :constant :special)
+                                                              ;; the printed value may be "nil", whether it is bound is sent separately
+                                                              (boundp sym)
+                                                              (when (boundp sym) (printed (symbol-value sym)))
+                                                              #+sbcl (ignore-errors (printed (sb-kernel:type-specifier (sb-int:info :variable :type sym)))) #-sbcl nil
+                                                              (documentation sym 'variable)
+                                                              (described :variable)))
+                                            :class (let ((class (find-class sym nil)))
+                                                     (when class
+                                                       (ignore-errors (unless (slynk-mop:class-finalized-p class) (slynk-mop:finalize-inheritance class)))
+                                                       (list (mapcar (lambda (c) (printed (class-name c))) (slynk-mop:class-direct-superclasses class))
+                                                             (mapcar (lambda (c) (printed (class-name c))) (slynk-mop:class-direct-subclasses class))
+                                                             (mapcar (lambda (s) (printed (slynk-mop:slot-definition-name s)))
+                                                                     (if (slynk-mop:class-finalized-p class) (slynk-mop:class-slots class) (slynk-mop:class-direct-slots class)))
+                                                             (documentation sym 'type)
+                                                             (described :class))))
+                                            :type #+sbcl (when (and (not (find-class sym nil)) (eq (sb-int:info :type :kind sym) :defined))
+                                                           (list (printed (sb-ext:typexpand-1 sym)) (documentation sym 'type)))
+                                                  #-sbcl nil)))))
+                                "{}")))
+                            "#, escape_form(&name));
+                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", escape_form(&describe)), &package_handle.lock().unwrap(), &continuation)
                         },
-                        SlynkMessage::FlexCompletions { text, cont } => {
-                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayCompletions(*cont));
-                            emacs_rex(&format!("(slynk-completion:flex-completions \"{}\" 'nil)", trim_quotes(text.to_string())), &package_handle.lock().unwrap(), &continuation)
+                        SlynkMessage::ClassInfo { class, cont } => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayClassInfo(*cont));
+                            // effective slots have no readers/writers, they are taken from the direct slots of the precedence list
+                            let class_info = format!(r#"
+                            (let ((*print-pretty* nil))
+                              (write ((lambda (name)
+                                (multiple-value-bind (sym found) (slynk::parse-symbol name)
+                                  (let ((class (and found (find-class sym nil))))
+                                    (when class
+                                      (ignore-errors (unless (slynk-mop:class-finalized-p class) (slynk-mop:finalize-inheritance class)))
+                                      (let ((cpl (when (slynk-mop:class-finalized-p class) (slynk-mop:class-precedence-list class))))
+                                        (labels ((printed (x) (let ((*print-case* :downcase)) (prin1-to-string x)))
+                                                 (class-names (classes) (mapcar (lambda (c) (printed (class-name c))) classes))
+                                                 (accessors (slot-name fn)
+                                                   (mapcar #'printed
+                                                           (remove-duplicates
+                                                             (loop for c in cpl
+                                                                   append (loop for d in (slynk-mop:class-direct-slots c)
+                                                                                when (eq (slynk-mop:slot-definition-name d) slot-name)
+                                                                                append (funcall fn d)))
+                                                             :test #'equal)))
+                                                 (slot (s direct)
+                                                   (let ((slot-name (slynk-mop:slot-definition-name s)))
+                                                     (list (printed slot-name)
+                                                           (printed (slynk-mop:slot-definition-allocation s))
+                                                           (mapcar #'printed (slynk-mop:slot-definition-initargs s))
+                                                           ;; an initform of nil prints as "nil", whether there is one is sent separately
+                                                           (and (slynk-mop:slot-definition-initfunction s) t)
+                                                           (when (slynk-mop:slot-definition-initfunction s) (printed (slynk-mop:slot-definition-initform s)))
+                                                           (printed (slynk-mop:slot-definition-type s))
+                                                           (if direct
+                                                               (mapcar #'printed (slynk-mop:slot-definition-readers s))
+                                                               (accessors slot-name #'slynk-mop:slot-definition-readers))
+                                                           (if direct
+                                                               (mapcar #'printed (slynk-mop:slot-definition-writers s))
+                                                               (accessors slot-name #'slynk-mop:slot-definition-writers))
+                                                           (ignore-errors (slynk-mop:slot-definition-documentation s))))))
+                                          (list :name (printed (class-name class))
+                                                :metaclass (printed (class-name (class-of class)))
+                                                :finalized (slynk-mop:class-finalized-p class)
+                                                :precedence-list (class-names cpl)
+                                                :direct-superclasses (class-names (slynk-mop:class-direct-superclasses class))
+                                                :direct-subclasses (class-names (slynk-mop:class-direct-subclasses class))
+                                                :direct-slots (mapcar (lambda (s) (slot s t)) (slynk-mop:class-direct-slots class))
+                                                :effective-slots (when cpl (mapcar (lambda (s) (slot s nil)) (slynk-mop:class-slots class)))
+                                                :generic-functions (mapcar #'printed
+                                                                           (remove-duplicates
+                                                                             (mapcar (lambda (m) (slynk-mop:generic-function-name (slynk-mop:method-generic-function m)))
+                                                                                     (slynk-mop:specializer-direct-methods class))
+                                                                             :test #'equal))
+                                                :documentation (documentation class t))))))))
+                                "{}")))
+                            "#, escape_form(&trim_quote_op(class.trim().to_string())));
+                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", escape_form(&class_info)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::GenericFunctionInfo { name, cont } => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayGenericFunctionInfo(*cont));
+                            let gf_info = format!(r#"
+                            (let ((*print-pretty* nil))
+                              (write ((lambda (name)
+                                (multiple-value-bind (sym found) (slynk::parse-symbol name)
+                                  (let ((gf (and found (fboundp sym) (fdefinition sym))))
+                                    (when (typep gf 'generic-function)
+                                      (flet ((printed (x) (let ((*print-case* :downcase)) (prin1-to-string x))))
+                                        (list :name (printed (slynk-mop:generic-function-name gf))
+                                              :lambda-list (printed (slynk-mop:generic-function-lambda-list gf))
+                                              :documentation (documentation gf t)
+                                              :methods (mapcar (lambda (m)
+                                                                 (list (mapcar #'printed (slynk-mop:method-qualifiers m))
+                                                                       (mapcar (lambda (s)
+                                                                                 (if (typep s 'slynk-mop:eql-specializer)
+                                                                                     (list :eql (printed (slynk-mop:eql-specializer-object s)))
+                                                                                     (list :class (printed (class-name s)))))
+                                                                               (slynk-mop:method-specializers m))
+                                                                       (printed (slynk-mop:method-lambda-list m))
+                                                                       (documentation m t)
+                                                                       (let ((loc (ignore-errors (slynk-backend:find-source-location m))))
+                                                                         (when (eq (car loc) :location) (second (assoc :file (cdr loc)))))))
+                                                               (slynk-mop:generic-function-methods gf))))))))
+                                "{}")))
+                            "#, escape_form(&trim_quote_op(name.trim().to_string())));
+                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", escape_form(&gf_info)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::FlexCompletions { text, package, arglists, offline, cont } => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayCompletions { cont: *cont, offline: offline.clone() });
+                            // symbols are completed relative to the buffer's package, so its internal symbols are found
+                            let package = package_or_current(package, &package_handle);
+                            let completions = format!(r#"
+                            (cl:let ((completions (slynk-completion:flex-completions "{}" "{}")))
+                              (cl:when completions
+                                (cl:list (cl:loop for c in (cl:first completions)
+                                                  for i from 0
+                                                  collect (cl:if (cl:< i {}) 
+                                                                 (cl:append c (cl:list (slynk:operator-arglist (cl:first c) "{}"))) 
+                                                                 c))
+                                         (cl:second completions))))
+                            "#, escape_form(&trim_quotes(text.to_string())), escape_form(&package), arglists, escape_form(&package));
+                            emacs_rex(&completions, &package, &continuation)
                         }
-                        SlynkMessage::AproposForSymbolInfo{ symbol, cont } => {
+                        SlynkMessage::ReplaySession(steps) => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::PrintReturnValue(PrintKind::Repl));
+                            // not using eval-and-grab-output, so that the output is written while loading
+                            emacs_rex(&format!("(cl:eval (cl:read-from-string \"{}\"))", escape_form(&replay_form(steps))), "COMMON-LISP-USER", &continuation)
+                        },
+                        SlynkMessage::ValueForEditing { form, package, cont } => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayValueForEditing(*cont));
+                            emacs_rex(&format!("(slynk:value-for-editing \"{}\")", escape_form(form)), &package_or_current(package, &package_handle), &continuation)
+                        },
+                        SlynkMessage::CommitEditedValue { form, value, package, cont } => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::CommitEditedValue(*cont));
+                            emacs_rex(&format!("(slynk:commit-edited-value \"{}\" \"{}\")", escape_form(form), escape_form(value)), &package_or_current(package, &package_handle), &continuation)
+                        },
+                        SlynkMessage::ReEvaluateDefvar { form, package, cont } => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::ReEvaluateDefvar(*cont));
+                            emacs_rex(&format!("(slynk:re-evaluate-defvar \"{}\")", escape_form(form)), &package_or_current(package, &package_handle), &continuation)
+                        },
+                        SlynkMessage::EvaluateWatches(watches) => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::UpdateWatches);
+                            // grabbing the output keeps it out of the REPL
+                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", escape_form(&watch_form(watches))), "COMMON-LISP-USER", &continuation)
+                        },
+                        SlynkMessage::RunTests { request, locations, cont } => {
+                            let form = match test_form(request) {
+                                Ok(form) => form,
+                                Err(e) => {
+                                    progress_sender.send(SlynkAnswer::Notify { text: e.0, error: true }).expect("Could not send");
+                                    progress_sender.send(SlynkAnswer::ResolvePending { continuation: *cont, data: "null".to_string() }).expect("Could not send");
+                                    continue;
+                                }
+                            };
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayTestRun { request: request.clone(), locations: locations.clone(), cont: *cont });
+                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", escape_form(&form)), &package_or_current(&request.package, &package_handle), &continuation)
+                        },
+                        SlynkMessage::OperateOnSystem { system, operation, files } => {
+                            progress_sender.send(SlynkAnswer::SystemOperation { system: system.clone(), operation: operation.clone(), stage: SystemOperationStage::Started }).expect("Could not send");
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::OperateOnSystem { system: system.clone(), operation: operation.clone(), files: files.clone() });
+                            // collect the notes like slynk:compile-file-for-emacs does,
+                            // asdf symbols are read at eval time as asdf may not be loaded yet
+                            let operate = format!(r#"
+                            (slynk::collect-notes
+                                (cl:lambda ()
+                                    (slynk-backend:with-compilation-hooks ()
+                                        (cl:require :asdf)
+                                        (cl:funcall (cl:read-from-string "asdf:operate") (cl:read-from-string "asdf:{}") "{}")
+                                        t)))
+                            "#, asdf_operation_name(operation), escape_quotes(system));
+                            emacs_rex(&operate, &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::Quickload { systems, cont } => {
+                            let systems_list = systems
+                                .iter()
+                                .map(|s| format!("\"{}\"", escape_form(s)))
+                                .collect::<Vec<String>>()
+                                .join(" ");
+                            // load each system on its own to report success or failure per system
+                            let quickload = format!(r#"
+                            (let ((results (list)))
+                                (dolist (system '({}) (nreverse results))
+                                    (push (handler-case (progn (uiop:symbol-call :ql :quickload system :prompt nil) 
+                                                               (list system t nil))
+                                            (error (e) (list system nil (princ-to-string e))))
+                                          results)))
+                            "#, systems_list);
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::Quickload { systems: systems.clone(), cont: *cont });
+                            // not using eval-and-grab-output, so that the output is written while loading
+                            emacs_rex(&format!("(cl:eval (cl:read-from-string \"{}\"))", escape_form(&quickload)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::QuicklispSystemApropos { term, cont } => {
+                            let apropos = format!("(write (mapcar {} (uiop:symbol-call :ql :system-apropos-list \"{}\")))", QL_SYSTEM_TO_LIST, escape_form(term));
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayQuicklispSystems(*cont));
+                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", escape_form(&apropos)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::QuicklispInstalledSystems(cont) => {
+                            let installed = format!(r#"
+                            (write (mapcan (lambda (dist) 
+                                                (mapcar {} (uiop:symbol-call :ql-dist :installed-systems dist)))
+                                           (uiop:symbol-call :ql-dist :all-dists)))
+                            "#, QL_SYSTEM_TO_LIST);
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayQuicklispSystems(*cont));
+                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", escape_form(&installed)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::QuicklispDists(cont) => {
+                            let dists = r#"
+                            (write (mapcar (lambda (dist)
+                                                (list (uiop:symbol-call :ql-dist :name dist)
+                                                      (uiop:symbol-call :ql-dist :version dist)
+                                                      (uiop:symbol-call :ql-dist :distinfo-subscription-url dist)
+                                                      (if (uiop:symbol-call :ql-dist :enabledp dist) t nil)))
+                                           (uiop:symbol-call :ql-dist :all-dists)))
+                            "#;
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayQuicklispDists(*cont));
+                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", escape_form(dists)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::ExportSymbol { symbol, package, cont } => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::PackageFuResult(*cont));
+                            emacs_rex(&format!("(slynk:export-symbol-for-emacs \"{}\" \"{}\")", escape_form(symbol), escape_form(package)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::UnexportSymbol { symbol, package, cont } => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::PackageFuResult(*cont));
+                            emacs_rex(&format!("(slynk:unexport-symbol-for-emacs \"{}\" \"{}\")", escape_form(symbol), escape_form(package)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::ImportSymbol { symbol, destination, origin, cont } => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::PackageFuResult(*cont));
+                            emacs_rex(&format!("(slynk:import-symbol-for-emacs \"{}\" \"{}\" \"{}\")", escape_form(symbol), escape_form(destination), escape_form(origin)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::ExportStructure { name, package, cont } => {
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayExportedSymbols(*cont));
+                            emacs_rex(&format!("(slynk:export-structure \"{}\" \"{}\")", escape_form(name), escape_form(package)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::PackageInfos { package, cont } => {
+                            let packages = match package {
+                                Some(p) => format!("(let ((p (find-package \"{}\"))) (when p (list p)))", escape_form(p)),
+                                None => "(list-all-packages)".to_string()
+                            };
+                            let infos = format!(r#"
+                            (write (mapcar (lambda (pkg)
+                                                (let ((external 0) (internal 0))
+                                                    (with-package-iterator (next pkg :internal :external)
+                                                        (loop (multiple-value-bind (more sym status) (next)
+                                                                (declare (ignore sym))
+                                                                (unless more (return))
+                                                                (if (eq status :external) (incf external) (incf internal)))))
+                                                    (list (package-name pkg)
+                                                          (package-nicknames pkg)
+                                                          (mapcar #'package-name (package-use-list pkg))
+                                                          (mapcar #'package-name (package-used-by-list pkg))
+                                                          #+sbcl (mapcar (lambda (n) (list (car n) (package-name (cdr n)))) (sb-ext:package-local-nicknames pkg))
+                                                          #-sbcl nil
+                                                          (mapcar #'symbol-name (package-shadowing-symbols pkg))
+                                                          external
+                                                          internal)))
+                                           {}))
+                            "#, packages);
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayPackageInfos(*cont));
+                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", escape_form(&infos)), &package_handle.lock().unwrap(), &continuation)
+                        },
+                        SlynkMessage::GuessAndSetPackage(package) => {
+                            let (channel, thread) = mrepl_handle_in.lock().unwrap().unwrap_or((1, 1));
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::SetMreplPrompt);
+                            emacs_rex_thread(&format!("(slynk-mrepl:eval-for-mrepl {} 'slynk-mrepl:guess-and-set-package \"{}\")", channel, escape_form(package)), &package_handle.lock().unwrap(), thread, &continuation)
+                        },
+                        SlynkMessage::SyncPackageAndDefaultDirectory { package, directory } => {
+                            let (channel, thread) = mrepl_handle_in.lock().unwrap().unwrap_or((1, 1));
+                            pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::SetMreplPrompt);
+                            let args = format!(":package-name {} :directory {}", 
+                                nil_or_string(package.as_ref().map(|p| escape_form(p))), 
+                                nil_or_string(directory.as_ref().map(|d| escape_form(d))));
+                            emacs_rex_thread(&format!("(slynk-mrepl:eval-for-mrepl {} 'slynk-mrepl:sync-package-and-default-directory {})", channel, args), &package_handle.lock().unwrap(), thread, &continuation)
+                        },
+                        SlynkMessage::Apropos { pattern, external_only, case_sensitive, regex, package, cont } => {
                             pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplayApropos(*cont));
-                            // todo: handle ' or #' in front of symbol
-                            emacs_rex(&format!("(slynk:eval-and-grab-output \"(apropos {})\")", quote(symbol)), &package_handle.lock().unwrap(), &continuation)
+                            // slynk-apropos uses cl-ppcre for patterns that are not just letters, if it is loaded
+                            let matcher = if *regex { "slynk-apropos::make-cl-ppcre-matcher" } else { "slynk-apropos::make-plain-matcher" };
+                            let apropos = format!("(cl:let ((slynk-apropos:*preferred-apropos-matcher* '{})) (slynk-apropos:apropos-list-for-emacs \"{}\" {} {} {}))", 
+                                matcher, 
+                                escape_form(pattern), 
+                                bool_to_nil_or_t(external_only), 
+                                bool_to_nil_or_t(case_sensitive), 
+                                nil_or_string(package.as_ref().map(|p| escape_form(p))));
+                            emacs_rex(&apropos, &package_handle.lock().unwrap(), &continuation)
                         },
                         SlynkMessage::ListSymbolsInPackage{ package, vars, macros, functions, classes, cont} => {
+                            let mut wanted = vec![];
+                            if *functions { wanted.extend([":function", ":generic-function"]); }
+                            if *macros { wanted.push(":macro"); }
+                            if *vars { wanted.extend([":special-var", ":constant"]); }
+                            if *classes { wanted.extend([":class", ":struct", ":condition", ":type"]); }
+                            // names and docs are written as strings, so that e.g. |Weird Symbols| need no parsing
                             let lst_symbols = format!(r#"
-                            (write ((lambda (package)
-                                (let ((res (list)))
-                                    (do-all-symbols (sym package)
-                                        (when (and (or  (and {} (and (not (macro-function sym)) (fboundp sym)))
-                                                        (and {} (macro-function sym))
-                                                        (and {} (boundp sym))
-                                                        (and {} (find-class sym nil)))
-                                                    (eql (symbol-package sym)
-                                                        (find-package package)))
-                                            (push sym res)))
-                                    res)) 
-                                '{}))
+                            (write ((lambda (package wanted)
+                                (let ((pkg (find-package package)) (syms (list)))
+                                    (when pkg
+                                        (do-symbols (sym pkg)
+                                            (when (eq (symbol-package sym) pkg)
+                                                (pushnew sym syms))))
+                                    (mapcan (lambda (sym)
+                                                (let ((kinds (list)) (class (find-class sym nil)))
+                                                    (when (and (fboundp sym) (not (macro-function sym)) (not (special-operator-p sym)))
+                                                        (push (if (typep (fdefinition sym) 'generic-function) :generic-function :function) kinds))
+                                                    (when (macro-function sym) (push :macro kinds))
+                                                    (when (boundp sym) (push (if (constantp sym) :constant :special-var) kinds))
+                                                    (cond ((null class) #+sbcl (when (eq (sb-int:info :type :kind sym) :defined) (push :type kinds)))
+                                                          ((typep class 'structure-class) (push :struct kinds))
+                                                          ((subtypep class 'condition) (push :condition kinds))
+                                                          (t (push :class kinds)))
+                                                    (when (intersection kinds wanted)
+                                                        (let ((doc (or (documentation sym 'function) (documentation sym 'variable) (documentation sym 'type)))
+                                                              (arglist (when (fboundp sym) (slynk-backend:arglist sym))))
+                                                            (list (list (symbol-name sym)
+                                                                        (nreverse kinds)
+                                                                        (eq (nth-value 1 (find-symbol (symbol-name sym) pkg)) :external)
+                                                                        (when doc (subseq doc 0 (position #\Newline doc)))
+                                                                        (when (listp arglist) (let ((*print-case* :downcase)) (princ-to-string arglist)))))))))
+                                            syms)))
+                                "{}" '({})))
                             "#, 
-                                bool_to_nil_or_t(functions), 
-                                bool_to_nil_or_t(macros), 
-                                bool_to_nil_or_t(vars), 
-                                bool_to_nil_or_t(classes), 
-                                package);
+                                escape_form(package),
+                                wanted.join(" "));
                             pending_handle_in.lock().unwrap().insert(continuation, ContinuationCallback::DisplaySymbolsInPackage(*cont));
-                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", &lst_symbols), &package_handle.lock().unwrap(), &continuation)
+                            emacs_rex(&format!("(slynk:eval-and-grab-output \"{}\")", escape_form(&lst_symbols)), &package_handle.lock().unwrap(), &continuation)
                         }
                     };
                     let hex_prefix = format!("{:#08x}", message_body.chars().count())[2..].to_string();
@@ -387,10 +1026,15 @@ impl REPL {
         
         Self {
             socket,
-            path: path.to_string(),
-            args: args.to_owned().iter().map(|s| s.as_ref().to_string()).collect(),
+            port_file,
+            startup_timeout,
+            profile,
+            slynk_start_path,
 
             child,
+            stopping,
+            started: Instant::now(),
+            crash_restarts: 0,
             slynk_repl_sender,
             out_buf,
             rec_tcp,
@@ -403,27 +1047,84 @@ impl REPL {
             package: arc_package,
             prompt: arc_prompt,
 
+            diagnostics,
+            session,
+            indentation: arc_indentation,
+            watches,
+
             init_err
 
         }
     }
 
     pub fn restart(&mut self) -> BackendResult<()> {
+        self.restart_with_profile(self.profile.clone())
+    }
+    // e.g. to switch from SBCL to CCL, the compiler notes and the session record are kept
+    pub fn restart_with_profile(&mut self, profile: LaunchProfile) -> BackendResult<()> {
         self.quit()?;
-        let new_repl = Self::new(self.socket.clone(), &self.path, self.args.as_slice());
+        let new_repl = Self::start(self.socket.clone(), self.port_file.clone(), self.startup_timeout, profile, self.slynk_start_path.clone(), self.diagnostics.clone(), self.session.clone());
         let _ = std::mem::replace(self, new_repl);
         Ok(())
     }
+    ///
+    /// How long to wait before restarting the crashed image, None once MAX_CRASH_RESTARTS
+    /// restarts in a row did not help.
+    ///
+    pub fn crash_restart_delay(&self) -> Option<Duration> {
+        if self.crashes_in_a_row() >= MAX_CRASH_RESTARTS {
+            None
+        } else {
+            Some(Duration::from_secs(1 << self.crashes_in_a_row()))
+        }
+    }
+    ///
+    /// Restarts the image after it ended unexpectedly, see SlynkAnswer::ProcessExited.
+    ///
+    pub fn restart_after_crash(&mut self) -> BackendResult<()> {
+        let restarts = self.crashes_in_a_row();
+        self.restart()?;
+        self.crash_restarts = restarts + 1;
+        Ok(())
+    }
+    fn crashes_in_a_row(&self) -> u32 {
+        if self.started.elapsed() > CRASH_RESTART_RESET { 0 } else { self.crash_restarts }
+    }
+    pub fn profile(&self) -> &LaunchProfile {
+        &self.profile
+    }
+    ///
+    /// False if the image did not start, has exited, e.g. while waiting to be restarted after a crash,
+    /// or is being stopped.
+    ///
+    pub fn is_alive(&self) -> bool {
+        self.init_err.is_none()
+            && !self.stopping.load(Ordering::SeqCst)
+            && matches!(self.child.lock().unwrap().try_wait(), Ok(None))
+    }
   
     pub fn quit(&mut self) -> BackendResult<()> {
-
-        self.slynk_repl_sender.send(SlynkMessage::Stop).expect("Could not send");
+        self.stopping.store(true, Ordering::SeqCst);
+        // the writer thread is gone if the image crashed
+        let _ = self.slynk_repl_sender.send(SlynkMessage::Stop);
         // self.
         // self.writer.write(STOP_SIG.as_bytes())?;
         // self.sender.send(STOP_SIG.to_string()).unwrap();
-        self.child.kill()?;
+        let mut child = self.child.lock().unwrap();
+        if child.try_wait()?.is_none() {
+            child.kill()?;
+        }
+        let _ = fs::remove_file(&self.port_file);
         Ok(())
     }
+    // adds the step to the session record and persists it
+    fn record(&self, step: SessionStep) {
+        let mut session = self.session.lock().unwrap();
+        session.record(step);
+        if let Err(e) = session.save() {
+            println!("Could not save the session record: {}", e.0);
+        }
+    }
 
     ///
     /// Get a receiver for incoming Slynk answers
@@ -432,6 +1133,56 @@ impl REPL {
         self.rec_tcp.clone()
     }
 
+    ///
+    /// Get the store of compiler notes collected from all compilations
+    ///
+    pub fn diagnostics(&self) -> Arc<Mutex<Diagnostics>> {
+        self.diagnostics.clone()
+    }
+
+    ///
+    /// Get the record of what has been loaded into the image
+    ///
+    pub fn session(&self) -> Arc<Mutex<SessionRecord>> {
+        self.session.clone()
+    }
+
+    ///
+    /// Loads the recorded session into the image, e.g. after a restart
+    ///
+    pub fn replay_session(&self) -> BackendResult<()> {
+        let steps = self.session.lock().unwrap().steps().to_vec();
+        if !steps.is_empty() {
+            self.slynk_repl_sender.send(SlynkMessage::ReplaySession(steps))?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Get the watch list
+    ///
+    pub fn watches(&self) -> Arc<Mutex<WatchList>> {
+        self.watches.clone()
+    }
+
+    ///
+    /// Evaluates the watches now, the new values are sent as WatchesUpdated
+    ///
+    pub fn evaluate_watches(&self) -> BackendResult<()> {
+        let watches = self.watches.lock().unwrap().watches().to_vec();
+        if !watches.is_empty() {
+            self.slynk_repl_sender.send(SlynkMessage::EvaluateWatches(watches))?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Get the indentation table of the current connection
+    ///
+    pub fn indentation(&self) -> Arc<Mutex<IndentationTable>> {
+        self.indentation.clone()
+    }
+
     ///
     /// Get the content of the SBCL process output buffer
     ///
@@ -447,13 +1198,16 @@ impl REPL {
     pub fn compile_and_load_file(&mut self, path: &str) -> BackendResult<()> {
 
         self.slynk_repl_sender.send(SlynkMessage::CompileAndLoadFile(path.to_string()))?;
+        self.record(SessionStep::LoadFile(path.to_string()));
         Ok(())
     }
 
-    // e.g. C-c C-c in Emacs
-    pub fn compile_form(&mut self, form: String, buffer: String, position: EditorPosition, filename: Option<String>) -> BackendResult<()> {
+    // e.g. C-c C-c in Emacs, the package is the buffer's, see reader::in_package_at
+    pub fn compile_form(&mut self, form: String, buffer: String, position: EditorPosition, filename: Option<String>, package: Option<String>) -> BackendResult<()> {
         println!("REPL::compile_form('{}')", &form);
-        self.slynk_repl_sender.send(SlynkMessage::CompileStringForEmacs{ buffer, filename, policy: None, position, string: form })?;
+        let step = SessionStep::CompileForm { form: form.clone(), filename: filename.clone(), package: package.clone() };
+        self.slynk_repl_sender.send(SlynkMessage::CompileStringForEmacs{ buffer, filename, policy: None, position, string: form, package })?;
+        self.record(step);
         Ok(())
 
     }
@@ -463,13 +1217,23 @@ impl REPL {
         Ok(())
     }
     // e.g. C-x C-e in Emacs
-    pub fn interactive_eval_form(&mut self, form: String) -> BackendResult<()> {
-        self.slynk_repl_sender.send(SlynkMessage::InteractiveEval(form))?;
+    pub fn interactive_eval_form(&mut self, form: String, package: Option<String>) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::InteractiveEval(form, package))?;
         Ok(())
     }
     // e.g. M-x sly-edit-definition in Emacs
-    pub fn find_definition_for_symbol(&mut self, symbol: String) -> BackendResult<()> {
-        self.slynk_repl_sender.send(SlynkMessage::FindDefinitions(symbol))?;
+    pub fn find_definition_for_symbol(&mut self, symbol: String, package: Option<String>) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::FindDefinitions(symbol, package))?;
+        Ok(())
+    }
+    // e.g. sly-mrepl-set-package in Emacs
+    pub fn guess_and_set_package(&self, package: String) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::GuessAndSetPackage(package))?;
+        Ok(())
+    }
+    // e.g. sly-mrepl-sync in Emacs
+    pub fn sync_package_and_default_directory(&self, package: Option<String>, directory: Option<String>) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::SyncPackageAndDefaultDirectory { package, directory })?;
         Ok(())
     }
 
@@ -478,6 +1242,17 @@ impl REPL {
         Ok(())
     }
 
+    // answer to a (y-or-n-p ...) asked by the image
+    pub fn answer_y_or_n_p(&self, answer: bool, thread: usize, tag: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::EmacsReturnBool(answer, thread, tag))?;
+        Ok(())
+    }
+    // lets (slynk:inspect-in-emacs x :wait t) return
+    pub fn inspector_closed(&self, thread: usize, tag: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::EmacsReturnBool(false, thread, tag))?;
+        Ok(())
+    }
+
     pub fn invoke_nth_restart(&self, level: usize, n: usize, thread: usize) -> BackendResult<()> {
         self.slynk_repl_sender.send(SlynkMessage::InvokeNthRestart(level, n, thread))?;
         Ok(())
@@ -494,16 +1269,129 @@ impl REPL {
         self.slynk_repl_sender.send(SlynkMessage::ListSymbolsInPackage{ package, vars, functions, classes, macros, cont: continuation, })?;
         Ok(())
     }
+    // name, nicknames, use-list, local nicknames etc. of the package, or of all packages if None
+    pub fn get_package_infos(&self, package: Option<String>, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::PackageInfos { package, cont: continuation })?;
+        Ok(())
+    }
     pub fn describe_symbol(&self, symbol: String, continuation: usize) -> BackendResult<()> {
         self.slynk_repl_sender.send(SlynkMessage::DescribeForSymbolInfo { symbol, cont: continuation  })?;
         Ok(())
     }
-    pub fn apropos_symbol(&self, symbol: String, continuation: usize) -> BackendResult<()> {
-        self.slynk_repl_sender.send(SlynkMessage::AproposForSymbolInfo { symbol, cont: continuation  })?;
+    pub fn apropos(&self, pattern: String, external_only: bool, case_sensitive: bool, regex: bool, package: Option<String>, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::Apropos { pattern, external_only, case_sensitive, regex, package, cont: continuation })?;
+        Ok(())
+    }
+    // the printed value of the form, e.g. a special variable, to edit it and set it with commit_edited_value
+    pub fn value_for_editing(&self, form: String, package: Option<String>, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::ValueForEditing { form, package, cont: continuation })?;
+        Ok(())
+    }
+    // (setf form value), the value is read from the edited text
+    pub fn commit_edited_value(&self, form: String, value: String, package: Option<String>, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::CommitEditedValue { form, value, package, cont: continuation })?;
+        Ok(())
+    }
+    // evaluates the (defvar ...) form even though the variable is bound already
+    pub fn re_evaluate_defvar(&self, form: String, package: Option<String>, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::ReEvaluateDefvar { form, package, cont: continuation })?;
+        Ok(())
+    }
+    // the class's precedence list, slots and subclasses, None if there is no such class
+    pub fn class_info(&self, class: String, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::ClassInfo { class, cont: continuation })?;
         Ok(())
     }
-    pub fn get_completions(&self, text: String, continuation: usize) -> BackendResult<()> {
-        self.slynk_repl_sender.send(SlynkMessage::FlexCompletions { text, cont: continuation  })?;
+    // the generic function's methods with their qualifiers and specializers, None if it is not a generic function
+    pub fn generic_function_info(&self, name: String, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::GenericFunctionInfo { name, cont: continuation })?;
+        Ok(())
+    }
+    // completions in the given package (the REPL's if None), the first `arglists` ones with their arglist,
+    // followed by the offline ones the image does not know
+    pub fn get_completions(&self, text: String, package: Option<String>, arglists: usize, offline: Vec<FlexCompletion>, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender
+            .send_timeout(SlynkMessage::FlexCompletions { text, package, arglists, offline, cont: continuation }, COMPLETION_SEND_TIMEOUT)
+            .map_err(|_| BackendError("The image did not take the completion request.".to_string()))
+    }
+    ///
+    /// Removes the completion request if the image has not answered it yet and returns its offline
+    /// completions, e.g. after COMPLETION_TIMEOUT. A late answer is ignored then.
+    ///
+    pub fn take_pending_completions(&self, continuation: usize) -> Option<Vec<FlexCompletion>> {
+        let mut pending = self.pending.lock().unwrap();
+        let key = pending
+            .iter()
+            .find(|(_, c)| matches!(c, ContinuationCallback::DisplayCompletions { cont, .. } if *cont == continuation))
+            .map(|(k, _)| *k)?;
+        match pending.remove(&key) {
+            Some(ContinuationCallback::DisplayCompletions { offline, .. }) => Some(offline),
+            _ => None
+        }
+    }
+
+    // e.g. (asdf:load-system "name"), the compiler notes replace the ones of the given files
+    pub fn load_system(&self, system: String, files: Vec<String>) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::OperateOnSystem { system: system.clone(), operation: AsdfOperation::Load, files })?;
+        self.record(SessionStep::LoadSystem(system));
+        Ok(())
+    }
+    // e.g. (asdf:compile-system "name")
+    pub fn compile_system(&self, system: String, files: Vec<String>) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::OperateOnSystem { system, operation: AsdfOperation::Compile, files })?;
+        Ok(())
+    }
+    // e.g. (asdf:test-system "name")
+    pub fn test_system(&self, system: String, files: Vec<String>) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::OperateOnSystem { system, operation: AsdfOperation::Test, files })?;
+        Ok(())
+    }
+
+    // runs the tests with the framework, the results are sent as TestRun, null if they were aborted
+    pub fn run_tests(&self, request: TestRequest, locations: Vec<TestLocation>, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::RunTests { request, locations, cont: continuation })?;
+        Ok(())
+    }
+
+    // e.g. (ql:quickload '("alexandria" "cl-ppcre")), the output is sent as progress lines while loading
+    pub fn quickload(&self, systems: Vec<String>, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::Quickload { systems: systems.clone(), cont: continuation })?;
+        for system in systems {
+            self.record(SessionStep::Quickload(system));
+        }
+        Ok(())
+    }
+    // e.g. (ql:system-apropos "json")
+    pub fn quicklisp_system_apropos(&self, term: String, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::QuicklispSystemApropos { term, cont: continuation })?;
+        Ok(())
+    }
+    pub fn quicklisp_installed_systems(&self, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::QuicklispInstalledSystems(continuation))?;
+        Ok(())
+    }
+    pub fn quicklisp_dists(&self, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::QuicklispDists(continuation))?;
+        Ok(())
+    }
+
+    // the following operate on the image only, see defpackage::update_exports_in_folder to update the source
+    pub fn export_symbol(&self, symbol: String, package: String, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::ExportSymbol { symbol, package, cont: continuation })?;
+        Ok(())
+    }
+    pub fn unexport_symbol(&self, symbol: String, package: String, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::UnexportSymbol { symbol, package, cont: continuation })?;
+        Ok(())
+    }
+    // imports the symbol as read in the origin package into the destination package
+    pub fn import_symbol(&self, symbol: String, destination: String, origin: String, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::ImportSymbol { symbol, destination, origin, cont: continuation })?;
+        Ok(())
+    }
+    // exports the name, constructor, predicate, copier and accessors of a structure or class, resolves the exported symbol names
+    pub fn export_structure(&self, name: String, package: String, continuation: usize) -> BackendResult<()> {
+        self.slynk_repl_sender.send(SlynkMessage::ExportStructure { name, package, cont: continuation })?;
         Ok(())
     }
 
@@ -518,12 +1406,62 @@ impl REPL {
 fn emacs_return(form: &str, thread: usize, tag: &usize) -> String {
     format!("(:emacs-return {} {} \"{}\")\n", thread, tag, &trim_quotes(form.to_string()))
 }
+///
+/// Waits until the image has written the port Slynk listens on, see start-slynk.lisp.
+///
+fn wait_for_port_file(port_file: &str, timeout: Duration, child: &mut Child) -> BackendResult<u16> {
+    let started = Instant::now();
+    loop {
+        // complete once the newline is written
+        if let Ok(content) = fs::read_to_string(port_file) {
+            if content.ends_with('\n') {
+                return content
+                    .trim()
+                    .parse::<u16>()
+                    .map_err(|_| BackendError(format!("Invalid port in {}: {}", port_file, content.trim())));
+            }
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(BackendError(format!("The Lisp process exited before Slynk was started ({}).", status)));
+        }
+        if started.elapsed() > timeout {
+            return Err(BackendError(format!("Slynk did not write its port to {} within {}ms.", port_file, timeout.as_millis())));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
 fn emacs_rex(form: &str, package: &str, continuation: &usize) -> String {
     format!("(:emacs-rex {} \"{}\" t {}))\n", form, package, continuation)
 }
 fn emacs_rex_thread(form: &str, package: &str, thread: usize,  continuation: &usize) -> String {
     format!("(:emacs-rex {} \"{}\" {} {}))\n", form, package, thread, continuation)
 }
+///
+/// Sends the given output as progress lines to all running quickloads.
+///
+fn send_quickload_progress(pending: &HashMap<usize, ContinuationCallback>, text: &str, sender: &Sender<SlynkAnswer>) {
+    for ccb in pending.values() {
+        if let ContinuationCallback::Quickload { systems, .. } = ccb {
+            for line in text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+                sender.send(SlynkAnswer::QuickloadProgress { systems: systems.clone(), line: line.to_string() }).expect("Could not send");
+            }
+        }
+    }
+}
+// the given package of an editor buffer, or the REPL's current one
+fn package_or_current(package: &Option<String>, current: &Arc<Mutex<String>>) -> String {
+    match package {
+        Some(p) => escape_quotes(p),
+        None => current.lock().unwrap().clone()
+    }
+}
+fn asdf_operation_name(operation: &AsdfOperation) -> &'static str {
+    match operation {
+        AsdfOperation::Load => "load-op",
+        AsdfOperation::Compile => "compile-op",
+        AsdfOperation::Test => "test-op"
+    }
+}
 fn emacs_channel_send(form: &str, channel: usize) -> String {
     format!("(:emacs-channel-send {} {})\n", channel, form)
 }
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossbeam::{bounded, unbounded, Receiver, Sender};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, LaunchProfile};
+use crate::{BackendError, BackendResult};
+
+// makes the script files of concurrent run_script calls unique
+static SCRIPT_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+///
+/// What to run in a fresh process.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RunTarget {
+    File(String),
+    // the form is read in the package, CL-USER if None
+    Form {
+        form: String,
+        package: Option<String>
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub id: usize,
+    pub log_file: String
+}
+
+///
+/// Sent to subscribers while a run is going on.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RunEvent {
+    // a line written to stdout or stderr
+    Output {
+        run: usize,
+        line: String
+    },
+    Finished {
+        run: usize,
+        // None if the process was killed by a signal
+        code: Option<i32>,
+        cancelled: bool,
+        // in seconds
+        duration: f64
+    }
+}
+
+struct RunningProcess {
+    child: Child,
+    cancelled: bool
+}
+
+///
+/// Runs files and forms in new processes with --non-interactive --load, separate from the image
+/// Slynk is connected to, e.g. to check that a script works in a clean image. The output of each
+/// run is written to a log file.
+///
+pub struct Runner {
+    log_dir: String,
+    next_id: usize,
+    running: Arc<Mutex<HashMap<usize, RunningProcess>>>,
+    subscribers: Arc<Mutex<Vec<Sender<RunEvent>>>>
+}
+
+impl Runner {
+    pub fn new(log_dir: String) -> Self {
+        Self {
+            log_dir,
+            next_id: 1,
+            running: Arc::new(Mutex::new(HashMap::new())),
+            subscribers: Arc::new(Mutex::new(vec![]))
+        }
+    }
+
+    ///
+    /// Get a receiver for the output and the end of all runs.
+    ///
+    pub fn subscribe(&mut self) -> Receiver<RunEvent> {
+        let (sender, receiver) = unbounded();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    ///
+    /// Starts the target with the profile's executable, core and heap size. The init forms are not
+    /// evaluated, the image is as clean as the profile's core.
+    ///
+    pub fn start(&mut self, profile: &LaunchProfile, target: RunTarget) -> BackendResult<RunInfo> {
+        let id = self.next_id;
+        self.next_id += 1;
+        fs::create_dir_all(&self.log_dir)
+            .map_err(|e| BackendError(format!("Could not create {}: {}", &self.log_dir, e)))?;
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let log_file = Path::new(&self.log_dir).join(format!("run-{}-{}.log", stamp, id));
+        let log_file = log_file.to_str().unwrap_or_default().to_string();
+
+        // forms are written to a script next to the log
+        let (script, temporary) = match &target {
+            RunTarget::File(path) => (path.clone(), None),
+            RunTarget::Form { form, package } => {
+                let script = Path::new(&log_file).with_extension("lisp").to_str().unwrap_or_default().to_string();
+                let in_package = package.as_ref().map(|p| format!("(in-package \"{}\")\n", p.to_uppercase())).unwrap_or_default();
+                fs::write(&script, format!("{}{}\n", in_package, form))?;
+                (script.clone(), Some(script))
+            }
+        };
+        let clean = LaunchProfile { init_forms: vec![], ..profile.clone() };
+        let args = clean.batch_args(&script);
+
+        let mut log = File::create(&log_file)
+            .map_err(|e| BackendError(format!("Could not create {}: {}", &log_file, e)))?;
+        writeln!(log, "; {} {}", &profile.executable, args.join(" "))?;
+
+        let (reader, writer_out) = os_pipe::pipe()?;
+        let writer_err = writer_out.try_clone()?;
+        // the command holds the pipe's write ends, it has to be dropped to read until the process exits
+        let child = {
+            let mut c = Command::new(&profile.executable);
+            c.args(&args).envs(&profile.env).stdin(Stdio::null()).stdout(writer_out).stderr(writer_err);
+            if let Some(dir) = &profile.working_directory {
+                c.current_dir(dir);
+            }
+            c.spawn().map_err(|e| BackendError(format!("Could not start {}: {}", &profile.executable, e)))?
+        };
+        self.running.lock().unwrap().insert(id, RunningProcess { child, cancelled: false });
+
+        let started = Instant::now();
+        let log = Arc::new(Mutex::new(log));
+        let (output_done, output_done_rec) = bounded::<()>(1);
+        //
+        // thread that streams stdout & stderr
+        //
+        let log_handle = log.clone();
+        let subscribers = self.subscribers.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            let mut buf = vec![];
+            while let Ok(n) = reader.read_until(b'\n', &mut buf) {
+                if n == 0 {
+                    break;
+                }
+                let line = String::from_utf8_lossy(&buf).trim_end_matches(['\r', '\n']).to_string();
+                let _ = writeln!(log_handle.lock().unwrap(), "{}", &line);
+                send(&subscribers, RunEvent::Output { run: id, line });
+                buf.clear();
+            }
+            let _ = output_done.send(());
+        });
+        //
+        // thread that waits for the process to exit, not with wait() so that it can be cancelled meanwhile
+        //
+        let running = self.running.clone();
+        let subscribers = self.subscribers.clone();
+        thread::spawn(move || {
+            let (code, cancelled) = loop {
+                if let Some(process) = running.lock().unwrap().get_mut(&id) {
+                    match process.child.try_wait() {
+                        Ok(Some(status)) => break (status.code(), process.cancelled),
+                        Ok(None) => {},
+                        Err(_) => break (None, process.cancelled)
+                    }
+                }
+                thread::sleep(Duration::from_millis(100));
+            };
+            running.lock().unwrap().remove(&id);
+            // the pipe stays open as long as a process started by the run is alive
+            let _ = output_done_rec.recv_timeout(Duration::from_secs(1));
+            let duration = started.elapsed().as_secs_f64();
+            let mut log = log.lock().unwrap();
+            let _ = match (cancelled, code) {
+                (true, _) => writeln!(log, "; cancelled after {:.1}s", duration),
+                (false, Some(code)) => writeln!(log, "; exited with code {} after {:.1}s", code, duration),
+                (false, None) => writeln!(log, "; killed after {:.1}s", duration)
+            };
+            if let Some(script) = temporary {
+                let _ = fs::remove_file(script);
+            }
+            send(&subscribers, RunEvent::Finished { run: id, code, cancelled, duration });
+        });
+
+        Ok(RunInfo { id, log_file })
+    }
+
+    ///
+    /// Kills the run's process, its Finished event tells that it was cancelled.
+    ///
+    pub fn cancel(&mut self, run: usize) -> BackendResult<()> {
+        let mut running = self.running.lock().unwrap();
+        let process = running
+            .get_mut(&run)
+            .ok_or_else(|| BackendError(format!("Run {} is not running.", run)))?;
+        process.cancelled = true;
+        process.child.kill()?;
+        Ok(())
+    }
+
+    ///
+    /// The ids of the runs whose process has not exited yet.
+    ///
+    pub fn running(&self) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.running.lock().unwrap().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+}
+
+// drops the subscribers that are gone
+fn send(subscribers: &Mutex<Vec<Sender<RunEvent>>>, event: RunEvent) {
+    subscribers.lock().unwrap().retain(|s| s.send(event.clone()).is_ok());
+}
+
+///
+/// Runs the script with the profile's batch arguments, see LaunchProfile::batch_args, and waits
+/// for the process to exit. Returns whether it succeeded and what it wrote to stdout & stderr.
+///
+pub fn run_script(profile: &LaunchProfile, script: &str, name: &str) -> BackendResult<(bool, String)> {
+    let script_path = Path::new(&config::get_data_dir()?)
+        .join(format!("{}-{}-{}.lisp", name, std::process::id(), SCRIPT_COUNTER.fetch_add(1, Ordering::SeqCst)));
+    fs::write(&script_path, script)
+        .map_err(|e| BackendError(format!("Could not write {}: {}", script_path.display(), e)))?;
+    let result = run_script_file(profile, script_path.to_str().unwrap_or_default());
+    let _ = fs::remove_file(&script_path);
+    result
+}
+
+fn run_script_file(profile: &LaunchProfile, script_path: &str) -> BackendResult<(bool, String)> {
+    let args = profile.batch_args(script_path);
+    println!("run_script(profile={}, args={:?})", &profile.name, &args);
+
+    let (mut reader, writer_out) = os_pipe::pipe()?;
+    let writer_err = writer_out.try_clone()?;
+    let mut child = {
+        let mut c = Command::new(&profile.executable);
+        c.args(&args).envs(&profile.env).stdin(Stdio::null()).stdout(writer_out).stderr(writer_err);
+        if let Some(dir) = &profile.working_directory {
+            c.current_dir(dir);
+        }
+        c.spawn().map_err(|e| BackendError(format!("Could not start {}: {}", &profile.executable, e)))?
+    };
+    let mut output = String::new();
+    let _ = reader.read_to_string(&mut output);
+    let status = child.wait()?;
+    Ok((status.success(), output))
+}
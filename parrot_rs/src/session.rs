@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::file::normalize_path;
+use crate::models::SessionStep;
+use crate::text::escape_form;
+use crate::{state, BackendResult};
+
+// the key the record is stored under in state.json
+pub const STATE_KEY: &str = "repl-session";
+
+///
+/// What has been loaded into the image, in order, so that it can be loaded again after a restart.
+///
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionRecord {
+    steps: Vec<SessionStep>
+}
+
+impl SessionRecord {
+    pub fn new() -> Self {
+        Self { steps: vec![] }
+    }
+
+    ///
+    /// The record persisted by save, empty if there is none.
+    ///
+    pub fn load() -> BackendResult<Self> {
+        match state::get_state_value(STATE_KEY)? {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Self::new())
+        }
+    }
+
+    pub fn save(&self) -> BackendResult<()> {
+        state::set_state_value(STATE_KEY.to_string(), serde_json::to_string(self)?)
+    }
+
+    ///
+    /// Adds the step, unless it has been recorded before. Loading a file supersedes the forms
+    /// compiled from it, compiling a definition again supersedes its previous version.
+    ///
+    pub fn record(&mut self, step: SessionStep) {
+        match &step {
+            SessionStep::LoadFile(file) => {
+                let file = normalize_path(file);
+                self.steps.retain(|s| !matches!(s, SessionStep::CompileForm { filename: Some(f), .. } if normalize_path(f) == file));
+            },
+            SessionStep::CompileForm { form, package, .. } => {
+                // a redefinition has to come after everything the new version may use
+                if let Some(key) = definition_key(form) {
+                    self.steps.retain(|s| !matches!(s, SessionStep::CompileForm { form: f, package: p, .. } if p == package && definition_key(f).as_ref() == Some(&key)));
+                }
+            },
+            _ => {}
+        }
+        if !self.steps.contains(&step) {
+            self.steps.push(step);
+        }
+    }
+
+    pub fn steps(&self) -> &[SessionStep] {
+        &self.steps
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+}
+
+///
+/// A form that runs the steps in order in the image and returns a summary, e.g.
+/// "Replayed 3 steps, failed: my-app (Component MY-APP not found)". Failed steps do not stop the replay.
+///
+pub fn replay_form(steps: &[SessionStep]) -> String {
+    replay(steps, true)
+}
+
+///
+/// Like replay_form, for an image without Slynk, files are loaded from source.
+///
+pub fn replay_form_without_slynk(steps: &[SessionStep]) -> String {
+    replay(steps, false)
+}
+
+fn replay(steps: &[SessionStep], slynk: bool) -> String {
+    let calls = steps
+        .iter()
+        .map(|step| {
+            let (name, body) = match step {
+                SessionStep::LoadFile(file) if slynk => (
+                    file.clone(),
+                    format!(r#"(slynk::compilation-result-successp (slynk:compile-file-for-emacs "{}" t))"#, escape_form(file))
+                ),
+                SessionStep::LoadFile(file) => (
+                    file.clone(),
+                    format!(r#"(load "{}")"#, escape_form(file))
+                ),
+                SessionStep::LoadSystem(system) => (
+                    system.clone(),
+                    format!(r#"(progn (require :asdf) (funcall (read-from-string "asdf:load-system") "{}") t)"#, escape_form(system))
+                ),
+                SessionStep::Quickload(system) => (
+                    system.clone(),
+                    // resolved when the step runs, the replay form is read at once and uiop or quicklisp may not be loaded
+                    format!(r#"(progn (unless (find-package "QL") (error "Quicklisp is not loaded.")) (funcall (read-from-string "ql:quickload") "{}" :prompt nil) t)"#, escape_form(system))
+                ),
+                SessionStep::CompileForm { form, package, .. } => (
+                    form.lines().next().unwrap_or_default().to_string(),
+                    format!(
+                        r#"(let ((*package* (or (find-package "{}") (find-package "COMMON-LISP-USER")))) (eval (read-from-string "{}")) t)"#,
+                        escape_form(package.as_deref().unwrap_or("COMMON-LISP-USER")),
+                        escape_form(form)
+                    )
+                )
+            };
+            format!("(replay-step \"{}\" (lambda () {}))", escape_form(&name), body)
+        })
+        .collect::<Vec<String>>()
+        .join("\n    ");
+    format!(r#"(let ((failed (list)) (count 0))
+  (flet ((replay-step (name thunk)
+           (incf count)
+           (handler-case (unless (funcall thunk) (push name failed))
+             (error (e) (push (format nil "~A (~A)" name e) failed)))))
+    {})
+  (format nil "Replayed ~D step~:P~@[, failed: ~{{~A~^; ~}}~]." count (reverse failed)))"#, calls)
+}
+
+// e.g. "(defun make-point (x y) ...)" -> "defun make-point", None for methods as a generic function has many
+fn definition_key(form: &str) -> Option<String> {
+    let rest = form.trim_start().strip_prefix('(')?;
+    let head_end = rest.find(|c: char| c.is_whitespace() || c == '(' || c == ')')?;
+    let head = rest[..head_end].to_lowercase();
+    if !head.starts_with("def") || head == "defmethod" {
+        return None;
+    }
+    // e.g. (defstruct (point (:conc-name p-)) ...), but not (defun (setf point-x) ...)
+    let rest = rest[head_end..].trim_start();
+    let name = match rest.strip_prefix('(') {
+        Some(options) if head == "defstruct" => options,
+        Some(_) => return None,
+        None => rest
+    };
+    let name_end = name.find(|c: char| c.is_whitespace() || c == '(' || c == ')').unwrap_or(name.len());
+    if name_end == 0 {
+        return None;
+    }
+    Some(format!("{} {}", head, name[..name_end].to_lowercase()))
+}
@@ -0,0 +1,360 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::config::LaunchProfile;
+use crate::image::SETUP_QUICKLISP;
+use crate::models::{TestFramework, TestLocation, TestRequest, TestResult, TestRun, TestStatus};
+use crate::runner::run_script;
+use crate::text::escape_form;
+use crate::{BackendError, BackendResult};
+
+lazy_static! {
+    static ref RE_ANSI_ESCAPE: Regex = Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap();
+    // fiveam: " Running test ADD-TEST ..f"
+    static ref RE_FIVEAM_TEST: Regex = Regex::new(r"^\s*Running test (\S+) ?(.*)$").unwrap();
+    // fiveam: " ADD-TEST [Adds two numbers]: "
+    static ref RE_FIVEAM_FAILURE: Regex = Regex::new(r"^\s*(\S+) \[.*\]:\s*$").unwrap();
+    // parachute: "  0.003 ✘   ADD-TEST"
+    static ref RE_PARACHUTE_RESULT: Regex = Regex::new(r"^\s*(\d+\.\d+)?\s*([✔✘-]) (\s*)(.+)$").unwrap();
+    // parachute: "   1/   2 tests failed in ADD-TEST"
+    static ref RE_PARACHUTE_FAILURE: Regex = Regex::new(r"^\s*\d+/\s*\d+ tests? failed in (.+)$").unwrap();
+    // rove: "add-test (12ms)"
+    static ref RE_ROVE_DURATION: Regex = Regex::new(r"^(.+?) \((\d+)ms\)$").unwrap();
+    // rove: "  × 0) Expect (= 2 3) to be true."
+    static ref RE_ROVE_FAILED: Regex = Regex::new(r"^\s*× (\d+)\)").unwrap();
+    // rove: "0) my-app/tests::add-test"
+    static ref RE_ROVE_DETAIL: Regex = Regex::new(r"^\s*(\d+)\) ").unwrap();
+}
+
+// the lines around the output of a test run in a new process, the duration follows the last one
+const STARTED_MARKER: &str = ";; parrot test run started";
+const DURATION_MARKER: &str = ";; parrot test run finished in ";
+
+///
+/// A form that runs the requested tests and returns how long they took in seconds. The names
+/// are read in the request's package once the system is loaded, the frameworks print their
+/// results to *standard-output*, see parse_test_output.
+///
+pub fn test_form(request: &TestRequest) -> BackendResult<String> {
+    let names = |names: &[String]| names.iter().map(|n| format!("\"{}\"", escape_form(n))).collect::<Vec<String>>().join(" ");
+    let run = match (&request.framework, &request.suite, request.tests.is_empty()) {
+        (_, None, true) => match &request.system {
+            Some(system) => format!("(asdf:test-system \"{}\")", escape_form(system)),
+            None => return Err(BackendError("Nothing to test, neither a system nor a suite or tests given.".to_string()))
+        },
+        (TestFramework::FiveAm, Some(suite), true) =>
+            format!("(uiop:symbol-call \"FIVEAM\" \"RUN!\" (read-from-string \"{}\"))", escape_form(suite)),
+        (TestFramework::FiveAm, _, false) =>
+            format!("(dolist (name (list {})) (uiop:symbol-call \"FIVEAM\" \"RUN!\" (read-from-string name)))", names(&request.tests)),
+        (TestFramework::Parachute, Some(suite), true) =>
+            format!("(uiop:symbol-call \"PARACHUTE\" \"TEST\" (read-from-string \"{}\") :report (uiop:find-symbol* \"PLAIN\" \"PARACHUTE\"))", escape_form(suite)),
+        (TestFramework::Parachute, _, false) =>
+            format!("(uiop:symbol-call \"PARACHUTE\" \"TEST\" (mapcar #'read-from-string (list {})) :report (uiop:find-symbol* \"PLAIN\" \"PARACHUTE\"))", names(&request.tests)),
+        (TestFramework::Rove, Some(suite), true) =>
+            format!("(uiop:symbol-call \"ROVE\" \"RUN-SUITE\" (string-upcase \"{}\") :style :spec)", escape_form(suite)),
+        (TestFramework::Rove, _, false) =>
+            format!("(dolist (name (list {})) (uiop:symbol-call \"ROVE\" \"RUN-TEST\" (read-from-string name) :style :spec))", names(&request.tests))
+    };
+    // the system is loaded first, as the test package may not exist before
+    let load = match (&request.system, &request.suite, request.tests.is_empty()) {
+        (Some(system), Some(_), _) | (Some(system), _, false) => format!("(asdf:load-system \"{}\")", escape_form(system)),
+        _ => String::new()
+    };
+    let package = escape_form(request.package.as_deref().unwrap_or("COMMON-LISP-USER")).to_uppercase();
+    Ok(format!(r#"(progn
+  {}
+  (let ((*package* (or (find-package "{}") (find-package "COMMON-LISP-USER")))
+        (report-stream (uiop:find-symbol* "*REPORT-STREAM*" "ROVE" nil))
+        (start (get-internal-real-time)))
+    ;; rove writes to its own stream
+    (progv (and report-stream (list report-stream)) (list *standard-output*)
+      {})
+    (/ (- (get-internal-real-time) start) (float internal-time-units-per-second))))"#, load, package, run))
+}
+
+///
+/// Reads the suites and the results of the tests from what the framework printed: FiveAM's run!,
+/// Parachute's plain report and Rove's spec style. Tests without results, e.g. of an aborted run,
+/// are missing.
+///
+pub fn parse_test_output(framework: &TestFramework, output: &str) -> (Vec<String>, Vec<TestResult>) {
+    let output = RE_ANSI_ESCAPE.replace_all(output, "");
+    let lines: Vec<&str> = output.lines().map(|l| l.trim_end_matches('\r')).collect();
+    match framework {
+        TestFramework::FiveAm => parse_fiveam(&lines),
+        TestFramework::Parachute => parse_parachute(&lines),
+        TestFramework::Rove => parse_rove(&lines)
+    }
+}
+
+///
+/// The results of a run, with the location of each test that is defined in the project.
+///
+pub fn test_run(request: &TestRequest, output: &str, duration: f64, locations: &[TestLocation]) -> TestRun {
+    let (suites, mut tests) = parse_test_output(&request.framework, output);
+    for test in tests.iter_mut() {
+        test.location = find_location(&test.name, request.package.as_deref(), locations);
+    }
+    TestRun { request: request.clone(), suites, tests, duration, output: output.to_string() }
+}
+
+///
+/// The request to run the failed tests of the run again, None if all passed.
+///
+pub fn rerun_failed_request(run: &TestRun) -> Option<TestRequest> {
+    let failed: Vec<String> = run.tests
+        .iter()
+        .filter(|t| t.status == TestStatus::Failed)
+        .map(|t| t.name.clone())
+        .collect();
+    if failed.is_empty() {
+        return None;
+    }
+    Some(TestRequest { suite: None, tests: failed, ..run.request.clone() })
+}
+
+///
+/// Runs the tests in a new process started from the profile, with its init forms, instead of the
+/// image Slynk is connected to.
+///
+pub fn run_tests_in_process(profile: &LaunchProfile, request: &TestRequest, locations: &[TestLocation]) -> BackendResult<TestRun> {
+    let script = format!("{}\n{}\n", SETUP_QUICKLISP, test_script_form(request)?);
+    let (_, output) = run_script(profile, &script, "run-tests")?;
+    Ok(test_run_from_script_output(request, &output, locations))
+}
+
+///
+/// A form for scripts that runs the tests like test_form and prints their output between markers,
+/// see test_run_from_script_output.
+///
+pub fn test_script_form(request: &TestRequest) -> BackendResult<String> {
+    Ok(format!("(progn (format t \"~&{}~%\") (format t \"~&{}~F~%\" {}))", STARTED_MARKER, DURATION_MARKER, test_form(request)?))
+}
+
+///
+/// The results of the tests run by a script with test_script_form, leaving out what the script
+/// printed before and after.
+///
+pub fn test_run_from_script_output(request: &TestRequest, output: &str, locations: &[TestLocation]) -> TestRun {
+    let start = output.find(STARTED_MARKER).map(|i| i + STARTED_MARKER.len()).unwrap_or_default();
+    let output = &output[start..];
+    // the process failed before the tests finished if there is no end marker
+    let (output, duration) = match output.rfind(DURATION_MARKER) {
+        Some(end) => {
+            let duration = output[end + DURATION_MARKER.len()..].lines().next().unwrap_or_default().trim().parse().unwrap_or_default();
+            (&output[..end], duration)
+        },
+        None => (output, 0.0)
+    };
+    test_run(request, output.trim_start_matches(['\r', '\n']), duration, locations)
+}
+
+// the test's name may be qualified with a package, e.g. MY-APP/TESTS::ADD-TEST
+fn find_location(name: &str, package: Option<&str>, locations: &[TestLocation]) -> Option<TestLocation> {
+    let (qualifier, unqualified) = match name.rfind(':') {
+        Some(i) => (Some(name[..i].trim_end_matches(':')), &name[i + 1..]),
+        None => (None, name)
+    };
+    let package = qualifier.or(package);
+    let candidates: Vec<&TestLocation> = locations.iter().filter(|l| l.name.eq_ignore_ascii_case(unqualified)).collect();
+    candidates
+        .iter()
+        .find(|l| matches!((package, &l.package), (Some(p), Some(lp)) if p.eq_ignore_ascii_case(lp)))
+        .or_else(|| candidates.first())
+        .map(|l| (*l).clone())
+}
+
+fn test_result(name: &str, suite: Option<&String>, status: TestStatus) -> TestResult {
+    TestResult { name: name.to_string(), suite: suite.cloned(), status, duration: None, message: None, location: None }
+}
+
+// appends a line of the failure's explanation
+fn add_message(test: &mut TestResult, line: &str) {
+    let line = line.trim();
+    if line.is_empty() {
+        return;
+    }
+    match &mut test.message {
+        Some(message) => {
+            message.push('\n');
+            message.push_str(line);
+        },
+        None => test.message = Some(line.to_string())
+    }
+}
+
+// Running test suite ARITHMETIC
+//  Running test ADD-TEST ..
+//  Running test SUB-TEST .f
+//  ...
+//  Failure Details:
+//  --------------------------------
+//  SUB-TEST []:
+//       (- 2 1) evaluated to 1, which is not = to 2
+//  --------------------------------
+fn parse_fiveam(lines: &[&str]) -> (Vec<String>, Vec<TestResult>) {
+    let mut suites: Vec<String> = vec![];
+    let mut tests: Vec<TestResult> = vec![];
+    let mut in_details = false;
+    let mut detail: Option<usize> = None;
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some(suite) = trimmed.strip_prefix("Running test suite ") {
+            suites.push(suite.trim().to_string());
+            in_details = false;
+        } else if let Some(captures) = RE_FIVEAM_TEST.captures(line) {
+            let marks = &captures[2];
+            let status = if marks.contains('f') || marks.contains('X') {
+                TestStatus::Failed
+            } else if !marks.is_empty() && marks.chars().all(|c| c == 's' || c.is_whitespace()) {
+                TestStatus::Skipped
+            } else {
+                TestStatus::Passed
+            };
+            tests.push(test_result(&captures[1], suites.last(), status));
+            in_details = false;
+        } else if trimmed.ends_with("Details:") {
+            // failure and skip details
+            in_details = true;
+            detail = None;
+        } else if in_details && trimmed.starts_with("----") {
+            detail = None;
+        } else if in_details {
+            match (detail, RE_FIVEAM_FAILURE.captures(line)) {
+                (None, Some(captures)) => detail = tests.iter().rposition(|t| t.name == captures[1]),
+                (Some(i), _) => add_message(&mut tests[i], line),
+                _ => {}
+            }
+        }
+    }
+    (suites, tests)
+}
+
+//         ? ARITHMETIC
+//   0.001 ✔   (is = 2 (+ 1 1))
+//   0.002 ✘   ADD-TEST
+//   0.004 ✘ ARITHMETIC
+//
+// ;; Failures:
+//    1/   2 tests failed in ADD-TEST
+// The test form   (+ 1 1)
+// ...
+fn parse_parachute(lines: &[&str]) -> (Vec<String>, Vec<TestResult>) {
+    let mut suites: Vec<String> = vec![];
+    let mut tests: Vec<TestResult> = vec![];
+    // children are reported before their parent, indices of the tests and their depth
+    let mut pending: Vec<(usize, usize)> = vec![];
+    let mut in_failures = false;
+    let mut failure: Option<usize> = None;
+    for line in lines {
+        if line.starts_with(";; Failures:") {
+            in_failures = true;
+        } else if line.starts_with(";;") {
+            in_failures = false;
+        } else if in_failures {
+            match RE_PARACHUTE_FAILURE.captures(line) {
+                Some(captures) => failure = tests.iter().rposition(|t| t.name == captures[1].trim()),
+                None => if let Some(i) = failure {
+                    add_message(&mut tests[i], line);
+                }
+            }
+        } else if let Some(captures) = RE_PARACHUTE_RESULT.captures(line) {
+            let name = captures[4].trim();
+            // assertions are printed as forms
+            if name.starts_with('(') {
+                continue;
+            }
+            let status = match &captures[2] {
+                "✔" => TestStatus::Passed,
+                "✘" => TestStatus::Failed,
+                _ => TestStatus::Skipped
+            };
+            let depth = captures[3].len();
+            let mut test = test_result(name, None, status);
+            test.duration = captures.get(1).and_then(|d| d.as_str().parse().ok());
+            let mut has_children = false;
+            while let Some((child, _)) = pending.last().filter(|(_, d)| *d > depth) {
+                tests[*child].suite = Some(name.to_string());
+                pending.pop();
+                has_children = true;
+            }
+            if has_children {
+                suites.push(name.to_string());
+            }
+            pending.push((tests.len(), depth));
+            tests.push(test);
+        }
+    }
+    (suites, tests)
+}
+
+// ;; testing 'my-app/tests'
+// add-test
+//   ✓ Expect (= (+ 1 1) 2) to be true.
+// sub-test (12ms)
+//   × 0) Expect (= (- 2 1) 2) to be true.
+//
+// × 1 of 2 tests failed
+//
+// 0) my-app/tests::sub-test
+//    Expect (= (- 2 1) 2) to be true.
+fn parse_rove(lines: &[&str]) -> (Vec<String>, Vec<TestResult>) {
+    let mut suites: Vec<String> = vec![];
+    let mut tests: Vec<TestResult> = vec![];
+    // failure number -> test
+    let mut failures: Vec<(String, usize)> = vec![];
+    let mut current: Option<usize> = None;
+    // of the current test
+    let mut assertions = 0;
+    let mut in_summary = false;
+    let mut detail: Option<usize> = None;
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some(suite) = trimmed.strip_prefix(";; testing '").and_then(|s| s.strip_suffix('\'')) {
+            suites.push(suite.to_string());
+            in_summary = false;
+            current = None;
+        } else if trimmed.is_empty() || trimmed.starts_with(";;") || trimmed.starts_with("Testing System") {
+            continue;
+        } else if !line.starts_with(' ') && (trimmed.starts_with('✓') || trimmed.starts_with('×')) {
+            // the summary and the details of the failures follow
+            in_summary = true;
+        } else if in_summary {
+            match RE_ROVE_DETAIL.captures(line) {
+                Some(captures) => detail = failures.iter().find(|(n, _)| *n == captures[1]).map(|(_, t)| *t),
+                None => if let Some(i) = detail {
+                    add_message(&mut tests[i], line);
+                }
+            }
+        } else if line.starts_with(' ') {
+            let test = match current {
+                Some(test) => test,
+                None => continue
+            };
+            if let Some(captures) = RE_ROVE_FAILED.captures(line) {
+                tests[test].status = TestStatus::Failed;
+                failures.push((captures[1].to_string(), test));
+            } else if trimmed.starts_with('-') && assertions == 0 {
+                tests[test].status = TestStatus::Skipped;
+            } else if trimmed.starts_with('✓') && tests[test].status == TestStatus::Skipped {
+                tests[test].status = TestStatus::Passed;
+            }
+            assertions += 1;
+        } else {
+            let (name, duration) = match RE_ROVE_DURATION.captures(trimmed) {
+                Some(captures) => (captures[1].to_string(), captures[2].parse::<f64>().ok().map(|ms| ms / 1000.0)),
+                None => (trimmed.to_string(), None)
+            };
+            let name = match suites.last() {
+                Some(suite) if !name.contains(':') => format!("{}::{}", suite, name),
+                _ => name
+            };
+            let mut test = test_result(&name, suites.last(), TestStatus::Passed);
+            test.duration = duration;
+            current = Some(tests.len());
+            assertions = 0;
+            tests.push(test);
+        }
+    }
+    (suites, tests)
+}
@@ -82,6 +82,43 @@ pub fn get_line_from_byte_offset(text: &str, byte_offset: usize) -> (usize, usiz
     }
     panic!("This should not happen: byte_offset should always be inside the text.");
 }
+///
+/// Returns a tuple of (line index, column) for the given character position (0-based),
+/// or None if the position is outside of the text.
+///
+pub fn get_line_and_col_from_char_pos(text: &str, char_pos: usize) -> Option<(usize, usize)> {
+    let mut line = 0;
+    let mut col = 0;
+    for (ix, c) in text.chars().enumerate() {
+        if ix == char_pos {
+            return Some((line, col));
+        }
+        if c == '\n' {
+            line += 1;
+            col = 0;
+        } else {
+            col += 1;
+        }
+    }
+    if char_pos == text.chars().count() {
+        return Some((line, col));
+    }
+    None
+}
+///
+/// Returns the number of characters before the given byte offset (0-based), an offset inside a
+/// character counts as that character's start.
+///
+pub fn char_pos_from_byte_offset(text: &str, byte_offset: usize) -> usize {
+    text.char_indices().take_while(|(ix, _)| *ix < byte_offset).count()
+}
+///
+/// Returns the byte offset of the given character position (0-based), the text's length if the
+/// position is at or after its end.
+///
+pub fn byte_offset_from_char_pos(text: &str, char_pos: usize) -> usize {
+    text.char_indices().nth(char_pos).map(|(ix, _)| ix).unwrap_or(text.len())
+}
 pub fn get_surrounding_context(text: &str, char_pos: usize, window_size: usize) -> (String, usize) {
     let mut start_ix = if char_pos <= window_size {
         0
@@ -125,7 +162,7 @@ pub fn trim_quote_op(mut text: String) -> String {
     else if text.starts_with("'") {
         text = text
             .chars()
-            .skip(2)
+            .skip(1)
             .collect();
     }
     text
@@ -40,6 +40,22 @@ pub fn get_all_lisp_files_in_folder(folder_path: &str) -> Vec<String> {
         .collect_vec()
 }
 
+///
+/// Returns a vec containing the full file paths for all .asd files contained in the folder and its subfolders.
+///
+pub fn get_all_asd_files_in_folder(folder_path: &str) -> Vec<String> {
+    WalkDir::new(folder_path)
+        .min_depth(1)
+        .max_depth(50)
+        .into_iter()
+        .filter_entry(|e| !is_hidden(e))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.file_name().to_str().unwrap().ends_with(".asd"))
+        .map(|r| r.path().to_str().unwrap().to_string())
+        .sorted()
+        .collect_vec()
+}
+
 ///
 /// Returns an iterator over the full file paths for all .lisp files contained in the folder and its subfolders.
 ///
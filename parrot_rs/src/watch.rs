@@ -0,0 +1,133 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Watch, WatchRefresh, WatchValue};
+use crate::text::escape_form;
+use crate::{state, BackendError, BackendResult};
+
+// the key the watch list is stored under in state.json
+pub const STATE_KEY: &str = "repl-watches";
+// how many previous values of a watch are kept
+pub const MAX_HISTORY: usize = 50;
+
+///
+/// Named forms whose values are shown while developing, e.g. *state*. Only the forms are
+/// persisted, the values belong to the image they were evaluated in.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchList {
+    watches: Vec<Watch>,
+    refresh: WatchRefresh,
+    next_id: usize
+}
+
+impl Default for WatchList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WatchList {
+    pub fn new() -> Self {
+        Self { watches: vec![], refresh: WatchRefresh::AfterEvaluation, next_id: 1 }
+    }
+
+    ///
+    /// The list persisted by save without values, empty if there is none.
+    ///
+    pub fn load() -> BackendResult<Self> {
+        let mut list: Self = match state::get_state_value(STATE_KEY)? {
+            Some(json) => serde_json::from_str(&json)?,
+            None => return Ok(Self::new())
+        };
+        for watch in list.watches.iter_mut() {
+            watch.value = None;
+            watch.history.clear();
+        }
+        Ok(list)
+    }
+
+    pub fn save(&self) -> BackendResult<()> {
+        state::set_state_value(STATE_KEY.to_string(), serde_json::to_string(self)?)
+    }
+
+    ///
+    /// Adds the watch and returns its id.
+    ///
+    pub fn add(&mut self, name: String, form: String, package: Option<String>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.watches.push(Watch { id, name, form, package, value: None, history: vec![] });
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) -> BackendResult<()> {
+        let len = self.watches.len();
+        self.watches.retain(|w| w.id != id);
+        if self.watches.len() == len {
+            return Err(BackendError(format!("There is no watch {}.", id)));
+        }
+        Ok(())
+    }
+
+    pub fn watches(&self) -> &[Watch] {
+        &self.watches
+    }
+
+    pub fn refresh(&self) -> &WatchRefresh {
+        &self.refresh
+    }
+
+    pub fn set_refresh(&mut self, refresh: WatchRefresh) {
+        self.refresh = refresh;
+    }
+
+    ///
+    /// True if there are watches to evaluate after a REPL evaluation, file load or debugger return.
+    ///
+    pub fn refreshes_after_evaluation(&self) -> bool {
+        self.refresh == WatchRefresh::AfterEvaluation && !self.watches.is_empty()
+    }
+
+    ///
+    /// Sets the watch's value, the previous one goes to the history if it differs. Returns whether
+    /// the value changed, watches that have been removed meanwhile are ignored.
+    ///
+    pub fn update(&mut self, id: usize, value: String, error: bool) -> bool {
+        let watch = match self.watches.iter_mut().find(|w| w.id == id) {
+            Some(watch) => watch,
+            None => return false
+        };
+        if matches!(&watch.value, Some(v) if v.value == value && v.error == error) {
+            return false;
+        }
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        if let Some(previous) = watch.value.replace(WatchValue { value, error, time }) {
+            watch.history.push(previous);
+            if watch.history.len() > MAX_HISTORY {
+                watch.history.remove(0);
+            }
+        }
+        true
+    }
+}
+
+///
+/// A form that evaluates the watches, each in its package, and writes ((id ok "printed value") ...).
+/// An error is written with its message instead, it does not stop the other watches.
+///
+pub fn watch_form(watches: &[Watch]) -> String {
+    let evaluations = watches
+        .iter()
+        .map(|w| format!(
+            r#"(cons {} (handler-case (let ((*package* (or (find-package "{}") (find-package "COMMON-LISP-USER")))) (list t (print-value (eval (read-from-string "{}"))))) (serious-condition (e) (list nil (princ-to-string e)))))"#,
+            w.id,
+            escape_form(&w.package.as_deref().unwrap_or("COMMON-LISP-USER").to_uppercase()),
+            escape_form(&w.form)))
+        .collect::<Vec<String>>()
+        .join("\n        ");
+    // long values are abbreviated
+    format!(r#"(flet ((print-value (value) (let ((*print-length* 100) (*print-level* 10)) (prin1-to-string value))))
+  (write (list {})))"#, evaluations)
+}
@@ -0,0 +1,73 @@
+use parrot_rs::asdf::*;
+
+const ASD: &str = r#"
+(in-package :asdf-user)
+
+(defsystem "my-app"
+  :description "An app"
+  :version "0.1.0"
+  :author "Someone"
+  :license "MIT"
+  :depends-on ("alexandria" (:version "cl-ppcre" "2.0") #+sbcl (:require "sb-posix") #:my-lib)
+  :pathname "src"
+  :components ((:file "main" :depends-on ("util" "web"))
+               (:file "util")
+               (:module "web"
+                :serial t
+                :components ((:file "routes")
+                             (:file "server" :pathname "http-server")))
+               (:static-file "README.md" :pathname "../README.md")))
+
+(asdf:defsystem #:my-lib
+  :serial t
+  :components ((:file "package") (:file "lib")))
+
+(defsystem "my-app/tests"
+  :depends-on ("my-app" "fiveam")
+  :components ((:module "tests" :components ((:file "tests")))))
+"#;
+
+#[test]
+fn parse_asd_defsystems() {
+    let systems = parse_asd(ASD, "/project/my-app.asd");
+    assert_eq!(3, systems.len());
+
+    let app = &systems[0];
+    assert_eq!("my-app", app.name);
+    assert_eq!(3, app.line);
+    assert_eq!("/project/src", app.path);
+    assert_eq!(Some(String::from("An app")), app.description);
+    assert_eq!(Some(String::from("MIT")), app.license);
+    assert_eq!(vec!["alexandria", "cl-ppcre", "sb-posix", "my-lib"], app.depends_on);
+    assert_eq!(4, app.components.len());
+    assert_eq!(AsdfComponentKind::Module, app.components[2].kind);
+    assert!(app.components[2].serial);
+    assert_eq!("/project/src/web/http-server.lisp", app.components[2].components[1].path);
+    assert_eq!("/project/src/../README.md", app.components[3].path);
+
+    // dependencies are loaded first
+    assert_eq!(vec![
+        "/project/src/util.lisp",
+        "/project/src/web/routes.lisp",
+        "/project/src/web/http-server.lisp",
+        "/project/src/main.lisp"
+    ], app.source_files());
+
+    assert_eq!("my-lib", systems[1].name);
+    assert!(systems[1].serial);
+    assert_eq!("my-app", systems[2].primary_name());
+    assert_eq!(vec!["/project/tests/tests.lisp"], systems[2].source_files());
+}
+
+#[test]
+fn system_graph_load_order() {
+    let systems = parse_asd(ASD, "/project/my-app.asd");
+    let graph = SystemGraph::build(&systems);
+
+    assert!(graph.nodes.iter().any(|n| n.name == "alexandria" && !n.local));
+    assert!(graph.nodes.iter().any(|n| n.name == "my-lib" && n.local));
+    assert_eq!(vec!["my-app/tests"], graph.dependents("my-app"));
+    assert_eq!(vec![
+        "alexandria", "cl-ppcre", "sb-posix", "my-lib", "my-app", "fiveam", "my-app/tests"
+    ], graph.load_order("my-app/tests"));
+}
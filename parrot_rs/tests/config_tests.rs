@@ -0,0 +1,49 @@
+use parrot_rs::config::*;
+
+#[test]
+fn launch_profile_command_args() {
+    let sbcl = LaunchProfile {
+        name: "big heap".to_string(),
+        implementation: Implementation::Sbcl,
+        executable: "/usr/bin/sbcl".to_string(),
+        core: Some("/usr/lib/sbcl/sbcl.core".to_string()),
+        args: vec!["--noinform".to_string()],
+        dynamic_space_size: Some(4096),
+        init_forms: vec!["(ql:quickload :my-app)".to_string()],
+        ..LaunchProfile::default()
+    };
+    assert_eq!(
+        vec!["--core", "/usr/lib/sbcl/sbcl.core", "--dynamic-space-size", "4096MB", "--lose-on-corruption", "--disable-ldb", "--noinform", "--load", "start-slynk.lisp", "--eval", "(ql:quickload :my-app)"],
+        sbcl.command_args("start-slynk.lisp"));
+
+    let ccl = LaunchProfile { implementation: Implementation::Ccl, init_forms: vec![], args: vec![], ..sbcl.clone() };
+    assert_eq!(
+        vec!["--image-name", "/usr/lib/sbcl/sbcl.core", "--heap-reserve", "4096M", "--load", "start-slynk.lisp"],
+        ccl.command_args("start-slynk.lisp"));
+
+    // ECL has no cores
+    let ecl = LaunchProfile { implementation: Implementation::Ecl, dynamic_space_size: Some(1), ..ccl.clone() };
+    assert_eq!(vec!["--heap-size", "1024", "--load", "start-slynk.lisp"], ecl.command_args("start-slynk.lisp"));
+}
+
+#[test]
+fn launch_profile_batch_args_and_saved_core() {
+    let sbcl = LaunchProfile {
+        name: "default".to_string(),
+        executable: "/usr/bin/sbcl".to_string(),
+        dynamic_space_size: Some(2048),
+        init_forms: vec!["(ql:quickload :my-app)".to_string()],
+        ..LaunchProfile::default()
+    };
+    assert_eq!(
+        vec!["--dynamic-space-size", "2048MB", "--non-interactive", "--eval", "(ql:quickload :my-app)", "--load", "build.lisp"],
+        sbcl.batch_args("build.lisp"));
+
+    // quits after the script instead of entering the REPL
+    let ccl = LaunchProfile { implementation: Implementation::Ccl, executable: "ccl".to_string(), ..LaunchProfile::default() };
+    assert_eq!(vec!["--batch", "--load", "build.lisp", "--eval", "(ccl:quit)"], ccl.batch_args("build.lisp"));
+
+    // the init forms are part of the saved core
+    let saved = sbcl.with_core("my-app", "/tmp/my-app.core");
+    assert_eq!(vec!["--core", "/tmp/my-app.core", "--dynamic-space-size", "2048MB", "--lose-on-corruption", "--disable-ldb", "--load", "start-slynk.lisp"], saved.command_args("start-slynk.lisp"));
+}
@@ -0,0 +1,74 @@
+use parrot_rs::coverage::*;
+use parrot_rs::models::*;
+
+// (defun sign (x)          ; line 0
+//   (if (> x 0) 1 -1))     ; line 1, only called with positive numbers
+// (defun unused () 0)      ; line 2
+const DATA: &str = r#"(("/p/src/sign.lisp" ((0 0 14 1) (1 2 5 6) (1 6 13 1) (1 14 15 1) (1 16 18 2) (2 0 19 2))) ("/p/src/empty.lisp" NIL))"#;
+
+fn report() -> CoverageReport {
+    let files = parse_coverage(DATA).unwrap();
+    CoverageReport {
+        system: "sign".to_string(),
+        files: files.into_iter().map(|f| (f.file.trim_start_matches("/p/").to_string(), f)).collect(),
+        tests: TestRun {
+            request: request(),
+            suites: vec![],
+            tests: vec![],
+            duration: 0.0,
+            output: String::new()
+        },
+        html_report: "/data/coverage/sign/".to_string()
+    }
+}
+
+fn request() -> TestRequest {
+    TestRequest {
+        framework: TestFramework::FiveAm,
+        system: Some("sign/tests".to_string()),
+        suite: None,
+        tests: vec![],
+        package: None,
+        isolated: true
+    }
+}
+
+#[test]
+fn parse_coverage_data() {
+    let files = parse_coverage(DATA).unwrap();
+    assert_eq!(2, files.len());
+    let sign = &files[0];
+    assert_eq!("/p/src/sign.lisp", sign.file);
+    assert_eq!(6, sign.ranges.len());
+    assert_eq!(CoverageRange { line: 1, start: 2, end: 5, state: CoverageState::Branch { first_taken: false, second_taken: true } }, sign.ranges[1]);
+    assert_eq!(CoverageState::Expression { executed: false }, sign.ranges[4].state);
+    assert_eq!(vec![
+        LineCoverage { line: 0, status: LineStatus::Covered },
+        LineCoverage { line: 1, status: LineStatus::Partial },
+        LineCoverage { line: 2, status: LineStatus::NotCovered }
+    ], sign.lines);
+    assert!(files[1].ranges.is_empty());
+    assert!(parse_coverage("NIL").unwrap().is_empty());
+}
+
+#[test]
+fn export_lcov() {
+    let lcov = to_lcov(&report());
+    let sign = lcov.split("end_of_record\n").find(|r| r.contains("SF:/p/src/sign.lisp")).unwrap();
+    assert_eq!("TN:sign\nSF:/p/src/sign.lisp\nBRDA:2,1,0,0\nBRDA:2,1,1,1\nBRF:2\nBRH:1\nDA:1,1\nDA:2,1\nDA:3,0\nLF:3\nLH:2\n", sign);
+    assert!(lcov.contains("SF:/p/src/empty.lisp\nBRF:0\nBRH:0\nLF:0\nLH:0\nend_of_record\n"));
+}
+
+#[test]
+fn coverage_script_instruments_the_system_only() {
+    let request = CoverageRequest { system: "sign".to_string(), tests: request() };
+    let script = coverage_script(&request, "/data/coverage/sign/").unwrap();
+    assert!(script.contains("(asdf:load-system \"sign\" :force '(\"sign\"))"));
+    // the tests are loaded after the instrumentation is turned off
+    let off = script.find("(sb-cover:store-coverage-data 0)").unwrap();
+    assert!(script.find("(asdf:test-system \"sign/tests\")").unwrap() > off);
+    assert!(script.contains("(sb-cover:report \"/data/coverage/sign/\")"));
+    // the internal report function is not read as a symbol
+    assert!(!script.contains("sb-cover::print-report"));
+    assert!(script.contains("(find-symbol \"PRINT-REPORT\" :sb-cover)"));
+}
@@ -0,0 +1,91 @@
+use parrot_rs::fts::definitions::*;
+use parrot_rs::models::*;
+
+const SOURCE: &str = r#"(defpackage :shapes (:use :cl))
+(in-package :shapes)
+
+(defvar *unit* 1)
+(defun make-point (x y
+                   &optional z)
+  (list x y z))
+(defgeneric area (shape))
+(defmethod area :around ((s circle)) (call-next-method))
+(defclass circle () ((radius :initarg :radius)))
+(defstruct (matrix (:conc-name m-)) rows cols)
+(defun (setf point-x) (v p) v)
+#-sbcl (defun ccl-only ())
+
+(in-package :cl-user)
+(defmacro with-shapes ((var) &body body) `(let ((,var nil)) ,@body))
+"#;
+
+#[test]
+fn read_definitions_of_a_file() {
+    let definitions = read_definitions(SOURCE);
+    let names: Vec<&str> = definitions.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(vec!["SHAPES", "*UNIT*", "MAKE-POINT", "AREA", "AREA", "CIRCLE", "MATRIX", "WITH-SHAPES"], names);
+
+    assert_eq!(None, definitions[0].package);
+    assert_eq!("pak", definitions[0].kind);
+    let make_point = &definitions[2];
+    assert_eq!(Some("SHAPES".to_string()), make_point.package);
+    assert_eq!("fn", make_point.kind);
+    assert_eq!(4, make_point.line);
+    assert_eq!(Some("(make-point x y &optional z)".to_string()), make_point.arglist);
+    // the lambda list follows the method qualifiers
+    assert_eq!(Some("(area (s circle))".to_string()), definitions[4].arglist);
+    assert_eq!("cla", definitions[6].kind);
+    assert_eq!(Some("CL-USER".to_string()), definitions[7].package);
+}
+
+#[test]
+fn complete_from_definitions() {
+    let definitions = read_definitions(SOURCE);
+
+    let completions = complete(definitions.iter(), "mp", Some("SHAPES"), 10);
+    assert_eq!("make-point", completions[0].symbol);
+    assert_eq!(vec![(0, "m".to_string()), (5, "p".to_string())], completions[0].chunks);
+    assert_eq!(vec!["fn"], completions[0].kinds);
+
+    // generic function and methods are one completion
+    let completions = complete(definitions.iter(), "are", Some("shapes"), 10);
+    assert_eq!(1, completions.len());
+    assert_eq!("generic-fn", completions[0].symbol_type);
+    assert_eq!(Some("(area shape)".to_string()), completions[0].arglist);
+
+    // definitions of other packages are qualified
+    let completions = complete(definitions.iter(), "circ", None, 10);
+    assert_eq!("shapes::circle", completions[0].symbol);
+    assert_eq!(vec![(8, "circ".to_string())], completions[0].chunks);
+    let completions = complete(definitions.iter(), "with", Some("CL-USER"), 10);
+    assert_eq!("with-shapes", completions[0].symbol);
+
+    // qualified patterns only match in the package
+    let completions = complete(definitions.iter(), "SHAPES::MATR", None, 10);
+    assert_eq!(1, completions.len());
+    assert_eq!("SHAPES::MATRIX", completions[0].symbol);
+    assert!(complete(definitions.iter(), "cl-user::matr", None, 10).is_empty());
+
+    let live = vec![FlexCompletion { symbol: "make-point".to_string(), prob: 0.9, chunks: vec![], symbol_type: "fn".to_string(), kinds: vec!["fn".to_string()], arglist: None }];
+    let offline = complete(definitions.iter(), "m", Some("SHAPES"), 10);
+    let merged = merge_completions(live, offline);
+    assert_eq!(1, merged.iter().filter(|c| c.symbol == "make-point").count());
+    assert!(merged.iter().any(|c| c.symbol == "matrix"));
+}
+
+#[test]
+fn read_unit_test_definitions() {
+    let source = r#"(in-package :shapes/tests)
+(fiveam:test (area-of-circle :suite shapes) (is (= 3 (area (circle 1)))))
+(parachute:define-test make-point-test (true (make-point 1 2)))
+(deftest matrix-test (ok (m-rows (make-matrix))))
+(test 'make-point-test)
+"#;
+    // tests are not completed
+    assert!(read_definitions(source).is_empty());
+    let definitions = read_test_definitions(source);
+    let names: Vec<(&str, &str)> = definitions.iter().map(|d| (d.name.as_str(), d.kind.as_str())).collect();
+    assert_eq!(vec![("AREA-OF-CIRCLE", "test"), ("MAKE-POINT-TEST", "test"), ("MATRIX-TEST", "test")], names);
+    assert_eq!(Some("SHAPES/TESTS".to_string()), definitions[0].package);
+    assert_eq!(1, definitions[0].line);
+}
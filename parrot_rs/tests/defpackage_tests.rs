@@ -0,0 +1,48 @@
+use parrot_rs::defpackage::*;
+
+const PACKAGE: &str = r#"(in-package :cl-user)
+
+(defpackage #:my-app
+  (:use #:cl)
+  (:nicknames #:app)
+  (:export #:start
+           #:stop))
+
+(defpackage :other (:use :cl))
+"#;
+
+#[test]
+fn add_exports_in_style_of_existing_entries() {
+    let updated = add_exports(PACKAGE, "MY-APP", &["restart".to_string(), "|Weird|".to_string(), "start".to_string()]).unwrap();
+    assert!(updated.contains("  (:export #:start\n           #:stop\n           #:restart\n           #:|Weird|))"));
+
+    let by_nickname = add_exports(PACKAGE, "app", &["status".to_string()]).unwrap();
+    assert!(by_nickname.contains("#:stop\n           #:status))"));
+
+    let upcased = add_exports("(defpackage \"FOO\" (:export \"BAR\"))", "foo", &["baz".to_string()]).unwrap();
+    assert_eq!("(defpackage \"FOO\" (:export \"BAR\" \"BAZ\"))", upcased);
+
+    let without_clause = add_exports(PACKAGE, "other", &["a".to_string(), "b".to_string()]).unwrap();
+    assert!(without_clause.contains("(defpackage :other (:use :cl)\n                   (:export #:a\n                            #:b))"));
+
+    let numbers = add_exports("(defpackage :foo (:export :a))", "foo", &["INF".to_string(), "1E5".to_string()]).unwrap();
+    assert_eq!("(defpackage :foo (:export :a :inf :|1E5|))", numbers);
+
+    assert!(add_exports(PACKAGE, "missing", &["a".to_string()]).is_none());
+}
+
+#[test]
+fn remove_exports_and_empty_clauses() {
+    let updated = remove_exports(PACKAGE, "my-app", &["stop".to_string()]).unwrap();
+    assert!(updated.contains("  (:nicknames #:app)\n  (:export #:start))"));
+
+    let first_removed = remove_exports(PACKAGE, "my-app", &["start".to_string()]).unwrap();
+    assert!(first_removed.contains("  (:export #:stop))"));
+
+    let all_removed = remove_exports(PACKAGE, "my-app", &["my-app::start".to_string(), ":stop".to_string()]).unwrap();
+    assert!(all_removed.contains("  (:nicknames #:app))"));
+    assert!(!all_removed.contains(":export"));
+
+    let same_line = remove_exports("(defpackage :foo (:export :a :b :c))", "foo", &["b".to_string()]).unwrap();
+    assert_eq!("(defpackage :foo (:export :a :c))", same_line);
+}
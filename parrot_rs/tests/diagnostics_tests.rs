@@ -0,0 +1,79 @@
+use parrot_rs::diagnostics::Diagnostics;
+use parrot_rs::models::*;
+
+fn note(file: &str, line: usize, severity: &str) -> CompilerNotes {
+    CompilerNotes {
+        message: format!("{} in line {}", severity, line),
+        severity: severity.to_string(),
+        location: Location::Location {
+            buffer: LocationBuffer::File(file.to_string()),
+            position: LocationPosition::Unknown,
+            snippet: None
+        },
+        references: vec![],
+        source_context: None,
+        file: Some(file.to_string()),
+        position: None,
+        line: Some(line),
+        col: Some(0)
+    }
+}
+
+#[test]
+fn diagnostics_replace_notes_for_files() {
+    let mut d = Diagnostics::new();
+    d.replace_notes_for_files(&["a.lisp".to_string()], vec![note("a.lisp", 3, "warning"), note("b.lisp", 1, "error")]);
+    assert_eq!(1, d.get_notes_for_file("a.lisp").len());
+    assert_eq!(1, d.get_notes_for_file("b.lisp").len());
+
+    // recompiling a.lisp without problems clears its notes but keeps the others
+    d.replace_notes_for_files(&["a.lisp".to_string()], vec![]);
+    assert!(d.get_notes_for_file("a.lisp").is_empty());
+    assert_eq!(vec![String::from("b.lisp")], d.get_files());
+
+    let groups = d.get_grouped_by_severity();
+    assert_eq!(1, groups.len());
+    assert_eq!("error", groups[0].severity);
+}
+
+#[test]
+fn diagnostics_replace_notes_for_lines_and_notify() {
+    let mut d = Diagnostics::new();
+    let rec = d.subscribe();
+    d.replace_notes_for_files(&[], vec![note("a.lisp", 2, "style-warning"), note("a.lisp", 10, "warning"), note("a.lisp", 20, "error")]);
+    assert_eq!(1, rec.try_iter().count());
+
+    d.replace_notes_for_lines("a.lisp", 8, 12, vec![note("a.lisp", 9, "note")]);
+    let lines = d.get_notes_for_file("a.lisp").iter().map(|n| n.line.unwrap()).collect::<Vec<usize>>();
+    assert_eq!(vec![2, 20, 9], lines);
+
+    let update = rec.try_recv().unwrap();
+    assert_eq!("a.lisp", update.file);
+    assert_eq!(3, update.notes.len());
+
+    let groups = d.get_grouped_by_severity();
+    let severities = groups.iter().map(|g| g.severity.as_str()).collect::<Vec<&str>>();
+    assert_eq!(vec!["error", "style-warning", "note"], severities);
+}
+
+#[test]
+fn diagnostics_replace_notes_of_a_buffer_without_file() {
+    let mut d = Diagnostics::new();
+    let mut buffer_note = note("scratch", 4, "warning");
+    buffer_note.file = None;
+    buffer_note.location = Location::Location {
+        buffer: LocationBuffer::Buffer("scratch".to_string()),
+        position: LocationPosition::Offset { start: 0, offset: 3 },
+        snippet: None
+    };
+    d.replace_notes_for_lines("scratch", 4, 5, vec![buffer_note.clone()]);
+    assert_eq!(1, d.get_notes_for_file("scratch").len());
+
+    // the fixed form is recompiled, its lines are inclusive so a note after it is kept
+    let mut next = buffer_note.clone();
+    next.line = Some(6);
+    d.replace_notes_for_lines("scratch", 6, 6, vec![next]);
+    d.replace_notes_for_lines("scratch", 4, 5, vec![]);
+    let lines = d.get_notes_for_file("scratch").iter().map(|n| n.line.unwrap()).collect::<Vec<usize>>();
+    assert_eq!(vec![6], lines);
+}
@@ -0,0 +1,40 @@
+use parrot_rs::config::Implementation;
+use parrot_rs::image::*;
+use parrot_rs::models::SessionStep;
+
+#[test]
+fn save_session_as_core() {
+    let steps = vec![SessionStep::Quickload("alexandria".to_string()), SessionStep::LoadFile("/home/user/app.lisp".to_string())];
+    let options = ImageOptions { output: "/tmp/app.core".to_string(), ..ImageOptions::default() };
+    let script = session_image_script(&steps, &options, &Implementation::Sbcl).unwrap();
+    // files are loaded from source, there is no Slynk in the building process
+    assert!(script.contains(r#"(load "/home/user/app.lisp")"#));
+    assert!(!script.contains("slynk"));
+    assert!(script.trim_end().ends_with(r#"(sb-ext:save-lisp-and-die "/tmp/app.core" :executable nil)"#));
+
+    let script = session_image_script(&steps, &options, &Implementation::Ccl).unwrap();
+    assert!(script.trim_end().ends_with(r#"(ccl:save-application "/tmp/app.core")"#));
+    assert!(session_image_script(&steps, &options, &Implementation::Ecl).is_err());
+}
+
+#[test]
+fn build_executable_from_system() {
+    let options = ImageOptions {
+        output: "/tmp/my-tool".to_string(),
+        executable: true,
+        toplevel: Some("my-tool:main".to_string()),
+        compression: true,
+        compression_level: Some(9),
+        ..ImageOptions::default()
+    };
+    let script = system_image_script("my-tool", &options, &Implementation::Sbcl).unwrap();
+    assert!(script.contains(r#"(uiop:symbol-call :ql :quickload "my-tool")"#));
+    let save = script.lines().rev().find(|l| !l.is_empty()).unwrap();
+    assert!(save.starts_with(r#"(sb-ext:save-lisp-and-die "/tmp/my-tool" :executable t :compression 9 :toplevel (let ((main (read-from-string "my-tool:main")))"#));
+    assert!(save.contains("(sb-ext:exit :code (if (integerp code) code 0))"));
+
+    // CCL has no compressed cores
+    assert!(system_image_script("my-tool", &options, &Implementation::Ccl).is_err());
+    let ccl = system_image_script("my-tool", &ImageOptions { compression: false, ..options }, &Implementation::Ccl).unwrap();
+    assert!(ccl.contains(":prepend-kernel t :toplevel-function"));
+}
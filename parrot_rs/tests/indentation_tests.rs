@@ -0,0 +1,45 @@
+use parrot_rs::indentation::*;
+use parrot_rs::models::*;
+
+#[test]
+fn indent_standard_operators() {
+    let table = IndentationTable::new();
+    let col = |text: &str| indent_column(text, text.len(), &table);
+
+    assert_eq!(Some(0), col("(defun foo ()\n  1)\n"));
+    assert_eq!(Some(2), col("(defun foo (a b)\n"));
+    assert_eq!(Some(4), col("(defun\n"));
+    assert_eq!(Some(4), col("(when\n"));
+    assert_eq!(Some(4), col("(let ((a 1)\n  (when (> a 0)\n"));
+    assert_eq!(Some(6), col("(let ((a 1)\n"));
+    // function calls align with their first argument
+    assert_eq!(Some(5), col("(foo bar\n"));
+    assert_eq!(Some(1), col("(foo\n"));
+    assert_eq!(Some(11), col("(list 'a '(b\n"));
+    assert_eq!(Some(2), col("(my-pkg:with-thing (x)\n"));
+    // parens in strings, comments and characters are ignored
+    assert_eq!(Some(2), col("(when (string= \"(\" #\\( ) ; (\n"));
+    assert_eq!(None, col("(foo \"bar\n"));
+    assert_eq!(None, col("#| (foo\n"));
+}
+
+#[test]
+fn indent_macros_from_the_image() {
+    let mut table = IndentationTable::new();
+    let text = "(define-route home (:get \"/\")\n";
+    assert_eq!(Some(2), indent_column(text, text.len(), &table));
+    let text = "(route-to :get \"/\"\n";
+    assert_eq!(Some(10), indent_column(text, text.len(), &table));
+
+    table.update(vec![("route-to".to_string(), IndentSpec::Body(2))]);
+    assert_eq!(Some(2), indent_column(text, text.len(), &table));
+    table.update(vec![("ROUTE-TO".to_string(), IndentSpec::Args(vec![ArgIndent::Offset(4), ArgIndent::Rest(Box::new(ArgIndent::Offset(1)))]))]);
+    assert_eq!(Some(1), indent_column(text, text.len(), &table));
+}
+
+#[test]
+fn reindent_form() {
+    let table = IndentationTable::new();
+    let text = "(defun foo (a)\n(let ((b 1)\n(c \"x\ny\"))\n\n(when a\n(+ b c))))";
+    assert_eq!("(defun foo (a)\n  (let ((b 1)\n        (c \"x\ny\"))\n\n    (when a\n      (+ b c))))", reindent(text, &table));
+}
@@ -0,0 +1,97 @@
+use parrot_rs::reader::*;
+use parrot_rs::text::byte_offset_from_char_pos;
+
+#[test]
+fn read_all_handles_comments_strings_and_dispatch() {
+    let text = r#"; comment
+#| block #| nested |# |#
+(defun foo (x) "doc \"quoted\"" #'car '(a b) #\( #p"a/b")
+#+sbcl (sb-ext:quit) #-sbcl (other)
+"#;
+    let forms = read_all(text);
+    assert_eq!(3, forms.len());
+
+    let items = forms[0].as_list().unwrap();
+    assert!(forms[0].head_is("defun"));
+    assert_eq!(Some("doc \"quoted\""), items[3].as_str());
+    assert!(matches!(&items[4].kind, FormKind::Prefixed { prefix, .. } if prefix == "#'"));
+    assert!(matches!(&items[5].kind, FormKind::Prefixed { prefix, .. } if prefix == "'"));
+    assert_eq!(Some("#\\("), items[6].as_atom());
+    assert!(matches!(&items[7].kind, FormKind::Prefixed { prefix, form } if prefix == "#p" && form.as_str() == Some("a/b")));
+    assert_eq!("(defun foo", &forms[0].text(text)[..10]);
+
+    let included = included_forms(&forms);
+    assert_eq!(2, included.len());
+    assert!(included[1].head_is("quit"));
+}
+
+#[test]
+fn read_all_is_tolerant() {
+    let forms = read_all(") (a (b \"unterminated");
+    assert_eq!(1, forms.len());
+    let items = forms[0].as_list().unwrap();
+    assert_eq!(2, items.len());
+    assert_eq!(Some("unterminated"), items[1].as_list().unwrap()[1].as_str());
+}
+
+#[test]
+fn symbol_names() {
+    let forms = read_all(r#"asdf:defsystem :serial #:foo |Mixed Case| foo\bar "str" 42"#);
+    let names = forms.iter().map(|f| f.symbol_name()).collect::<Vec<Option<String>>>();
+    assert_eq!(vec![
+        Some(String::from("DEFSYSTEM")),
+        Some(String::from("SERIAL")),
+        Some(String::from("FOO")),
+        Some(String::from("Mixed Case")),
+        Some(String::from("FOObAR")),
+        None,
+        None
+    ], names);
+    assert!(forms[1].is_keyword("serial"));
+    assert_eq!(Some(String::from("str")), forms[5].string_designator());
+}
+
+#[test]
+fn potential_numbers_are_not_symbols() {
+    let forms = read_all("#:inf nan infinity 1+ 42 -1.5e3 1/2 .5 1e5");
+    let names = forms.iter().map(|f| f.symbol_name()).collect::<Vec<Option<String>>>();
+    assert_eq!(vec![
+        Some(String::from("INF")),
+        Some(String::from("NAN")),
+        Some(String::from("INFINITY")),
+        Some(String::from("1+")),
+        None,
+        None,
+        None,
+        None,
+        None
+    ], names);
+}
+
+#[test]
+fn in_package_at_position() {
+    let text = "(defun a ())\n(in-package :my-app)\n(defun b ())\n(cl:in-package \"OTHER\")\n(defun c ())";
+    assert_eq!(None, in_package_at(text, 5));
+    assert_eq!(Some(String::from("MY-APP")), in_package_at(text, text.find("(defun b").unwrap() + 3));
+    assert_eq!(Some(String::from("OTHER")), in_package_at(text, text.len()));
+}
+
+#[test]
+fn defvar_at_position() {
+    let text = "(defvar *a* 1)\n(defparameter *b* 2)\n(defvar *c*\n  (list 1 2))";
+    assert_eq!(Some("(defvar *a* 1)"), defvar_at(text, 3));
+    assert_eq!(None, defvar_at(text, text.find("*b*").unwrap()));
+    assert_eq!(Some("(defvar *c*\n  (list 1 2))"), defvar_at(text, text.find("(list").unwrap()));
+}
+
+#[test]
+fn defvar_at_character_position_after_multibyte_text() {
+    let text = "(in-package :my-app)\n(defvar *größe* \"äöü äöü\")\n(defvar *b* 2)";
+    // the editor's cursor in front of the second defvar, counted in characters
+    let position = text[..text.find("(defvar *b*").unwrap()].chars().count();
+    let offset = byte_offset_from_char_pos(text, position);
+    assert_eq!(Some("(defvar *b* 2)"), defvar_at(text, offset));
+    assert_ne!(Some("(defvar *b* 2)"), defvar_at(text, position));
+    assert_eq!(Some(String::from("MY-APP")), in_package_at(text, offset));
+    assert_eq!(text.len(), byte_offset_from_char_pos(text, 1000));
+}
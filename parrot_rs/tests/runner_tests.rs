@@ -0,0 +1,61 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use parrot_rs::config::LaunchProfile;
+use parrot_rs::runner::*;
+
+// sh -c '<script>' takes the arguments meant for SBCL as $0, $1, ...
+fn shell(script: &str) -> LaunchProfile {
+    LaunchProfile {
+        name: "sh".to_string(),
+        executable: "sh".to_string(),
+        args: vec!["-c".to_string(), script.to_string()],
+        init_forms: vec!["(ql:quickload :not-in-a-clean-image)".to_string()],
+        ..LaunchProfile::default()
+    }
+}
+
+fn log_dir(name: &str) -> String {
+    std::env::temp_dir().join(format!("parrot-runner-tests-{}-{}", name, std::process::id())).to_str().unwrap().to_string()
+}
+
+#[test]
+fn run_streams_output_and_exit_code() {
+    let mut runner = Runner::new(log_dir("output"));
+    let events = runner.subscribe();
+    let run = runner.start(&shell("echo \"$1 $2\"; echo oops >&2; exit 3"), RunTarget::File("/tmp/script.lisp".to_string())).unwrap();
+
+    let mut lines = vec![];
+    let finished = loop {
+        match events.recv_timeout(Duration::from_secs(10)).unwrap() {
+            RunEvent::Output { run: id, line } if id == run.id => lines.push(line),
+            RunEvent::Finished { run: id, code, cancelled, .. } if id == run.id => break (code, cancelled),
+            _ => {}
+        }
+    };
+    // the init forms are not evaluated
+    assert_eq!(vec!["--load /tmp/script.lisp", "oops"], lines);
+    assert_eq!((Some(3), false), finished);
+    assert!(runner.running().is_empty());
+
+    let log = std::fs::read_to_string(&run.log_file).unwrap();
+    assert!(log.starts_with("; sh -c"));
+    assert!(log.contains("oops\n; exited with code 3 after"));
+}
+
+#[test]
+fn cancel_run() {
+    let mut runner = Runner::new(log_dir("cancel"));
+    let events = runner.subscribe();
+    let run = runner.start(&shell("sleep 30"), RunTarget::Form { form: "(print 1)".to_string(), package: None }).unwrap();
+    assert_eq!(vec![run.id], runner.running());
+
+    runner.cancel(run.id).unwrap();
+    match events.recv_timeout(Duration::from_secs(10)).unwrap() {
+        RunEvent::Finished { cancelled, code, .. } => assert!(cancelled && code.is_none()),
+        e => panic!("unexpected {:?}", e)
+    }
+    assert!(runner.cancel(run.id).is_err());
+    assert!(std::fs::read_to_string(&run.log_file).unwrap().contains("; cancelled after"));
+}
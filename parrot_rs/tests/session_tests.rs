@@ -0,0 +1,49 @@
+use parrot_rs::models::SessionStep;
+use parrot_rs::session::*;
+
+fn form(form: &str, filename: Option<&str>) -> SessionStep {
+    SessionStep::CompileForm { form: form.to_string(), filename: filename.map(|f| f.to_string()), package: Some("MY-APP".to_string()) }
+}
+
+#[test]
+fn record_session_steps() {
+    let mut session = SessionRecord::new();
+    session.record(SessionStep::Quickload("alexandria".to_string()));
+    session.record(SessionStep::LoadSystem("my-app".to_string()));
+    session.record(form("(defun area (s) 1)", Some("C:\\app\\shapes.lisp")));
+    session.record(form("(defmethod area ((c circle)) 2)", None));
+    session.record(form("(defmethod area ((r rect)) 3)", None));
+    session.record(SessionStep::Quickload("alexandria".to_string()));
+    assert_eq!(5, session.steps().len());
+
+    // a redefinition replaces the previous version and comes last
+    session.record(form("(defun area (s)\n  (* 2 pi))", None));
+    assert_eq!(5, session.steps().len());
+    assert_eq!(form("(defun area (s)\n  (* 2 pi))", None), session.steps()[4]);
+
+    // loading the file supersedes the forms compiled from it
+    session.record(form("(defvar *unit* 1)", Some("C:/app/shapes.lisp")));
+    session.record(SessionStep::LoadFile("C:/app/shapes.lisp".to_string()));
+    assert_eq!(SessionStep::LoadFile("C:/app/shapes.lisp".to_string()), session.steps()[5]);
+    assert_eq!(6, session.steps().len());
+
+    session.clear();
+    assert!(session.steps().is_empty());
+}
+
+#[test]
+fn replay_form_runs_steps_in_order() {
+    let steps = vec![
+        SessionStep::Quickload("alexandria".to_string()),
+        SessionStep::LoadFile("/home/user/my \"app\".lisp".to_string()),
+        form("(defun greet () \"hi\")", None)
+    ];
+    let replay = replay_form(&steps);
+    let quickload = replay.find(r#"(funcall (read-from-string "ql:quickload") "alexandria" :prompt nil)"#).unwrap();
+    assert!(!replay.contains("uiop:"));
+    let load = replay.find(r#"(slynk:compile-file-for-emacs "/home/user/my \"app\".lisp" t)"#).unwrap();
+    let compile = replay.find(r#"(find-package "MY-APP")"#).unwrap();
+    assert!(quickload < load && load < compile);
+    assert!(replay.contains(r#"(read-from-string "(defun greet () \"hi\")")"#));
+    assert!(replay.contains("~{~A~^; ~}"));
+}
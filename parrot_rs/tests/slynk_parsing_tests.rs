@@ -1,7 +1,7 @@
 use std::{assert};
 
 use parrot_rs::models::*;
-use parrot_rs::parsing::parse_slynk_answer;
+use parrot_rs::parsing::{parse_slynk_answer, parse_quickload_results, parse_quicklisp_systems, parse_quicklisp_dists, parse_package_fu_result, parse_exported_symbols, parse_symbols, parse_package_infos, parse_mrepl_ids, parse_mrepl_prompt, parse_event, parse_apropos, parse_describe, parse_class_info, parse_generic_function_info, parse_flex_completions, is_heap_exhaustion, parse_watch_values, parse_returned_string};
 use parrot_rs::{self};
 
 #[test]
@@ -136,4 +136,372 @@ fn parse_write_values_list_all_packages() {
     } else {
         panic!("Wrong enum variant")
     }
-}
\ No newline at end of file
+}
+#[test]
+fn parse_compilation_result_notes_references_and_context() {
+    let parsed = parse_slynk_answer(r#"(:return (:ok (:compilation-result ((:message "Duplicate definition for TEST found in one file." :severity :warning :location (:location (:file "path/to/test_project/test.lisp") (:position 617) nil) :references ((:ansi-cl :section (3 2 2 3)) (:sbcl :node "Handling of Types")) :source-context "==>
+      (EVAL-WHEN (:COMPILE-TOPLEVEL) (SB-C:%COMPILER-DEFUN 'TEST T NIL NIL))
+    ")) t 0.05266899988055229 t "path/to/test.fasl")) 1)"#, None);
+    if let SlynkAnswer::ReturnCompilationResult { notes, success, .. } = parsed {
+        assert!(success);
+        let notes = notes.unwrap();
+        assert_eq!(1, notes.len());
+        assert_eq!("warning", notes[0].severity);
+        assert_eq!(Some(String::from("path/to/test_project/test.lisp")), notes[0].file);
+        assert_eq!(Some(617), notes[0].position);
+        assert_eq!(2, notes[0].references.len());
+        assert_eq!("ansi-cl", notes[0].references[0].manual);
+        assert_eq!("section", notes[0].references[0].kind);
+        assert_eq!("3.2.2.3", notes[0].references[0].name);
+        assert_eq!("Handling of Types", notes[0].references[1].name);
+        assert!(notes[0].source_context.as_ref().unwrap().contains("EVAL-WHEN"));
+    } else {
+        panic!("Wrong enum variant")
+    }
+}
+
+#[test]
+fn parse_compilation_result_buffer_location_with_compiled_form() {
+    let ccb = ContinuationCallback::CompileForm {
+        form: String::from("(defun test (a)\n  (+ a b))"),
+        position: EditorPosition { pos: 100, line: 10, col: 0 },
+        filename: Some(String::from("path/to/test.lisp")),
+        buffer: String::from("path/to/test.lisp")
+    };
+    let parsed = parse_slynk_answer(r#"(:return (:ok (:compilation-result ((:message "undefined variable: COMMON-LISP-USER::B" :severity :warning :location (:location (:buffer "path/to/test.lisp") (:offset 100 23) nil) :references nil)) t 0.002 nil nil)) 7)"#, Some(&ccb));
+    if let SlynkAnswer::ReturnCompilationResult { notes, fasl_file, .. } = parsed {
+        assert_eq!(None, fasl_file);
+        let notes = notes.unwrap();
+        assert!(matches!(notes[0].location, Location::Location { buffer: LocationBuffer::Buffer(_), position: LocationPosition::Offset { start: 100, offset: 23 }, .. }));
+        assert_eq!(Some(String::from("path/to/test.lisp")), notes[0].file);
+        assert_eq!(Some(11), notes[0].line);
+        assert_eq!(Some(7), notes[0].col);
+    } else {
+        panic!("Wrong enum variant")
+    }
+}
+
+#[test]
+fn parse_compilation_result_error_and_source_form_locations() {
+    let parsed = parse_slynk_answer(r#"(:return (:ok (:compilation-result ((:message "a" :severity :error :location (:error "No error location available") :references nil) (:message "b" :severity :style-warning :location (:location (:source-form "(foo)") (:position 1)) :references nil)) nil 0.001 nil nil)) 4)"#, None);
+    if let SlynkAnswer::ReturnCompilationResult { notes, .. } = parsed {
+        let notes = notes.unwrap();
+        assert!(matches!(&notes[0].location, Location::Error(e) if e == "No error location available"));
+        assert!(matches!(&notes[1].location, Location::Location { buffer: LocationBuffer::SourceForm(f), .. } if f == "(foo)"));
+        assert_eq!(None, notes[1].file);
+    } else {
+        panic!("Wrong enum variant")
+    }
+}
+#[test]
+fn parse_compilation_result_keeps_notes_with_unknown_locations() {
+    let parsed = parse_slynk_answer(r#"(:return (:ok (:compilation-result ((:message "a" :severity :warning :location nil :references nil) (:message "b" :severity :note :location (:location (:new-kind "x") (:position 1) nil) :references ((:sbcl))) (:message "c" :severity :warning :location (:location (:file "path/to/test.lisp") (:position 42) nil) :references nil)) t 0.001 nil nil)) 4)"#, None);
+    if let SlynkAnswer::ReturnCompilationResult { notes, success, .. } = parsed {
+        let notes = notes.unwrap();
+        assert!(success);
+        assert_eq!(3, notes.len());
+        assert!(matches!(&notes[0].location, Location::Error(_)));
+        assert!(matches!(&notes[1].location, Location::Error(e) if e.contains(":new-kind")));
+        assert!(notes[1].references.is_empty());
+        assert!(matches!(&notes[2].location, Location::Location { buffer: LocationBuffer::File(f), .. } if f == "path/to/test.lisp"));
+    } else {
+        panic!("Wrong enum variant")
+    }
+}
+#[test]
+fn parse_quickload_results_per_system() {
+    let results = parse_quickload_results(r#"(("alexandria" t nil) ("not-existing" nil "System \"not-existing\" not found"))"#).unwrap();
+    assert_eq!(2, results.len());
+    assert!(results[0].success);
+    assert_eq!(None, results[0].error);
+    assert_eq!("not-existing", results[1].system);
+    assert!(!results[1].success);
+    assert_eq!(Some(String::from("System \"not-existing\" not found")), results[1].error);
+}
+#[test]
+fn parse_quicklisp_systems_and_dists() {
+    let systems = parse_quicklisp_systems(r#"("((\"cl-json\" \"cl-json-20220707-git\" \"quicklisp\")
+ (\"jonathan\" \"jonathan-20200925-git\" \"quicklisp\"))" "NIL")"#).unwrap();
+    assert_eq!(2, systems.len());
+    assert_eq!("jonathan", systems[1].name);
+    assert_eq!("jonathan-20200925-git", systems[1].release);
+    assert_eq!("quicklisp", systems[1].dist);
+
+    let empty = parse_quicklisp_systems(r#"("NIL" "NIL")"#).unwrap();
+    assert!(empty.is_empty());
+
+    let dists = parse_quicklisp_dists(r#"("((\"quicklisp\" \"2023-10-21\" \"http://beta.quicklisp.org/dist/quicklisp.txt\" T) (\"local\" \"1\" NIL NIL))" "NIL")"#).unwrap();
+    assert_eq!(2, dists.len());
+    assert!(dists[0].enabled);
+    assert_eq!(Some(String::from("http://beta.quicklisp.org/dist/quicklisp.txt")), dists[0].url);
+    assert_eq!(None, dists[1].url);
+    assert!(!dists[1].enabled);
+}
+#[test]
+fn parse_package_fu_return_values() {
+    assert!(parse_package_fu_result("t"));
+    assert!(!parse_package_fu_result("nil"));
+
+    let symbols = parse_exported_symbols("(common-lisp-user::point common-lisp-user::make-point common-lisp-user::|Point-X|)");
+    assert_eq!(vec!["POINT", "MAKE-POINT", "Point-X"], symbols);
+    assert!(parse_exported_symbols("nil").is_empty());
+}
+#[test]
+fn parse_typed_symbols() {
+    let symbols = parse_symbols(r#"("((\"MAKE-POINT\" (:FUNCTION) T \"Creates a \\\"point\\\".\" \"(&key x y)\") (\"Weird Symbol\" (:SPECIAL-VAR) NIL NIL NIL) (\"POINT\" (:STRUCT :TYPE) T NIL NIL))" "NIL")"#).unwrap();
+    assert_eq!(3, symbols.len());
+    assert_eq!("MAKE-POINT", symbols[0].name);
+    assert!(matches!(symbols[0].kinds.as_slice(), [SymbolType::Function]));
+    assert!(symbols[0].exported);
+    assert_eq!(Some(String::from("Creates a \"point\".")), symbols[0].doc);
+    assert_eq!(Some(String::from("(&key x y)")), symbols[0].arglist);
+
+    assert_eq!("POINT", symbols[1].name);
+    assert!(matches!(symbols[1].kinds.as_slice(), [SymbolType::Struct, SymbolType::Type]));
+
+    assert_eq!("Weird Symbol", symbols[2].name);
+    assert!(!symbols[2].exported);
+    assert_eq!(None, symbols[2].doc);
+}
+#[test]
+fn parse_package_infos_with_relations() {
+    let packages = parse_package_infos(r#"("((\"MY-APP\" (\"APP\") (\"COMMON-LISP\") NIL ((\"A\" \"ALEXANDRIA\")) (\"REMOVE\") 12 30) (\"MY-APP/TESTS\" NIL (\"COMMON-LISP\" \"MY-APP\") NIL NIL NIL 0 4))" "NIL")"#).unwrap();
+    assert_eq!(2, packages.len());
+    let app = &packages[0];
+    assert_eq!("MY-APP", app.name);
+    assert_eq!(vec!["APP"], app.nicknames);
+    assert_eq!(vec!["COMMON-LISP"], app.use_list);
+    assert!(app.used_by.is_empty());
+    assert_eq!("A", app.local_nicknames[0].nickname);
+    assert_eq!("ALEXANDRIA", app.local_nicknames[0].package);
+    assert_eq!(vec!["REMOVE"], app.shadowing_symbols);
+    assert_eq!(12, app.external_count);
+    assert_eq!(30, app.internal_count);
+    assert_eq!(vec!["COMMON-LISP", "MY-APP"], packages[1].use_list);
+}
+#[test]
+fn parse_mrepl_returns() {
+    assert_eq!((1, 4), parse_mrepl_ids("(1 4)").unwrap());
+    match parse_mrepl_prompt(r#"(("MY-APP" "APP" 0 12) (t))"#).unwrap() {
+        ChannelMethod::Prompt { package, prompt, elevel, len_history, condition } => {
+            assert_eq!("MY-APP", package);
+            assert_eq!("APP", prompt);
+            assert_eq!(0, elevel);
+            assert_eq!(12, len_history);
+            assert_eq!(None, condition);
+        },
+        _ => panic!("Expected a prompt")
+    }
+}
+#[test]
+fn parse_unsolicited_events() {
+    match parse_slynk_answer(r#"(:ed (:filename "/home/user/app.lisp" :line 12 :column 3))"#, None) {
+        SlynkAnswer::Ed { target: Some(EdTarget::File { filename, line, column, position }) } => {
+            assert_eq!("/home/user/app.lisp", filename);
+            assert_eq!(Some(12), line);
+            assert_eq!(Some(3), column);
+            assert_eq!(None, position);
+        },
+        a => panic!("Unexpected answer: {:?}", a)
+    }
+    assert!(matches!(parse_slynk_answer(r#"(:ed (:function-name "MY-APP::START"))"#, None), SlynkAnswer::Ed { target: Some(EdTarget::FunctionName(f)) } if f == "MY-APP::START"));
+    assert!(matches!(parse_slynk_answer("(:ed nil)", None), SlynkAnswer::Ed { target: None }));
+    assert!(matches!(parse_slynk_answer("(:ping 3 17)", None), SlynkAnswer::Ping { thread: 3, tag: 17 }));
+    assert!(matches!(parse_slynk_answer(r#"(:y-or-n-p 3 5 "Really?")"#, None), SlynkAnswer::YOrNP { thread: 3, tag: 5, .. }));
+    assert!(matches!(parse_slynk_answer(r#"(:new-package "MY-APP" "APP")"#, None), SlynkAnswer::NewPackage { .. }));
+    assert!(matches!(parse_slynk_answer(r#"(:invalid-rpc 12 "Thread not found: 7")"#, None), SlynkAnswer::InvalidRpc { continuation: 12, .. }));
+    assert!(matches!(parse_slynk_answer(r#"(:presentation-start 1 :repl-result)"#, None), SlynkAnswer::Unknown(_)));
+
+    match parse_event(r##"(:inspect (:title "#<POINT {100}>" :id 0 :content (("Slots:" (:newline-ignored) (:label "X") ": " (:value "1" 1) (:action "[set value]" 0)) 6 0 500)) nil nil)"##).unwrap() {
+        SlynkAnswer::Inspect { content, thread, tag } => {
+            assert_eq!("#<POINT {100}>", content.title);
+            // (:newline-ignored) is left out
+            assert_eq!(5, content.parts.len());
+            assert!(matches!(&content.parts[0], InspectorPart::Text(t) if t == "Slots:"));
+            assert!(matches!(&content.parts[1], InspectorPart::Label(l) if l == "X"));
+            assert!(matches!(&content.parts[2], InspectorPart::Text(t) if t == ": "));
+            assert!(matches!(&content.parts[3], InspectorPart::Value { text, index: 1 } if text == "1"));
+            assert!(matches!(&content.parts[4], InspectorPart::Action { label, index: 0 } if label == "[set value]"));
+            assert_eq!(None, thread);
+            assert_eq!(None, tag);
+        },
+        a => panic!("Unexpected answer: {:?}", a)
+    }
+}
+
+#[test]
+fn parse_indentation_update() {
+    let m = r#"(:indentation-update (("define-route" 1 ("MY-APP")) ("with-db" (4 "&body") ("MY-APP" "DB")) ("def-thing" (("&whole" 4 "&rest" 1) "&rest" 2) ("MY-APP")) ("odd" "&bogus" ("MY-APP"))))"#;
+    match parse_slynk_answer(m, None) {
+        SlynkAnswer::IndentationUpdate { specs } => {
+            assert_eq!(3, specs.len());
+            assert_eq!(("define-route".to_string(), IndentSpec::Body(1)), specs[0]);
+            assert_eq!(IndentSpec::Args(vec![ArgIndent::Offset(4), ArgIndent::Body]), specs[1].1);
+            assert_eq!(IndentSpec::Args(vec![ArgIndent::Offset(4), ArgIndent::Rest(Box::new(ArgIndent::Offset(2)))]), specs[2].1);
+        },
+        a => panic!("Unexpected answer: {:?}", a)
+    }
+}
+
+#[test]
+fn parse_apropos_hits() {
+    let value = r#"((:designator ("FLATTEN" "ALEXANDRIA" t) :function "Traverses the tree in order, collecting non-null leaves into a list." :arglist "(TREE)" :bounds ((0 7))) (:designator ("*HELPER*" "MY-APP" nil) :variable :not-documented :function "Helps." :bounds ((1 7)) :flex-score "80.00%") (:designator ("TEMP" nil nil) :class nil))"#;
+    let hits = parse_apropos(value).unwrap();
+    assert_eq!(4, hits.len());
+    assert_eq!("ALEXANDRIA:FLATTEN", hits[0].designator);
+    assert_eq!("function", hits[0].kind);
+    assert!(hits[0].documentation.as_ref().unwrap().starts_with("Traverses"));
+    assert_eq!("MY-APP::*HELPER*", hits[1].designator);
+    assert_eq!("variable", hits[1].kind);
+    assert_eq!(None, hits[1].documentation);
+    assert_eq!("function", hits[2].kind);
+    assert_eq!(Some("Helps.".to_string()), hits[2].documentation);
+    assert_eq!("#:TEMP", hits[3].designator);
+}
+
+#[test]
+fn parse_symbol_description() {
+    let value = r#"("(:name \"*GREETING*\" :package \"MY-APP\" :summary \"Documentation for the symbol *GREETING*:
+
+Variable:
+ The greeting.\" :FUNCTION NIL :VARIABLE (:SPECIAL T \"\\\"hello\\\"\" \"string\" \"The greeting.\" \"MY-APP::*GREETING*
+  [symbol]\") :CLASS NIL :TYPE NIL)" "(:NAME ...)")"#;
+    let desc = parse_describe(value).unwrap().unwrap();
+    assert_eq!("*GREETING*", desc.name);
+    assert_eq!(Some("MY-APP".to_string()), desc.package);
+    assert!(desc.summary.contains("\n\nVariable:"));
+    assert!(desc.function.is_none() && desc.class.is_none() && desc.type_info.is_none());
+    let variable = desc.variable.unwrap();
+    assert_eq!("special", variable.kind);
+    assert_eq!(Some("\"hello\"".to_string()), variable.value);
+    assert_eq!(Some("string".to_string()), variable.declared_type);
+
+    // bound to nil is not unbound
+    let value = r#"("(:name \"*DEBUG*\" :package \"MY-APP\" :summary \"\" :function nil :variable (:special T \"nil\" nil nil nil) :class nil :type nil)" "")"#;
+    assert_eq!(Some("nil".to_string()), parse_describe(value).unwrap().unwrap().variable.unwrap().value);
+    let value = r#"("(:name \"*UNSET*\" :package \"MY-APP\" :summary \"\" :function nil :variable (:special NIL nil nil \"Not set yet.\" nil) :class nil :type nil)" "")"#;
+    let variable = parse_describe(value).unwrap().unwrap().variable.unwrap();
+    assert_eq!(None, variable.value);
+    assert_eq!(Some("Not set yet.".to_string()), variable.documentation);
+
+    let value = r#"("(:name \"POINT\" :package \"MY-APP\" :summary \"\" :function (:function \"(x y)\" \"(function (t t) (values point &optional))\" nil \"/home/user/point.lisp\" nil) :variable nil :class (nil nil (\"x\" \"y\") \"A point.\" nil) :type nil)" "")"#;
+    let desc = parse_describe(value).unwrap().unwrap();
+    let function = desc.function.unwrap();
+    assert_eq!("function", function.kind);
+    assert_eq!(Some("(x y)".to_string()), function.lambda_list);
+    assert_eq!(None, function.documentation);
+    assert_eq!(Some("/home/user/point.lisp".to_string()), function.source_file);
+    let class = desc.class.unwrap();
+    assert!(class.superclasses.is_empty());
+    assert_eq!(vec!["x", "y"], class.slots);
+
+    assert!(parse_describe(r#"("NIL" "NIL")"#).unwrap().is_none());
+}
+
+#[test]
+fn parse_class_and_generic_function_info() {
+    let value = r#"("(:NAME \"circle\" :METACLASS \"standard-class\" :FINALIZED T :PRECEDENCE-LIST (\"circle\" \"shape\" \"standard-object\" \"t\") :DIRECT-SUPERCLASSES (\"shape\") :DIRECT-SUBCLASSES NIL :DIRECT-SLOTS ((\"radius\" \":instance\" (\":radius\") T \"1\" \"number\" (\"radius\") (\"(setf radius)\") NIL) (\"parent\" \":instance\" NIL T \"nil\" \"t\" NIL NIL NIL)) :EFFECTIVE-SLOTS ((\"name\" \":instance\" (\":name\") NIL NIL \"t\" (\"shape-name\") NIL \"The name.\") (\"radius\" \":instance\" (\":radius\") T \"1\" \"number\" (\"radius\") (\"(setf radius)\") NIL)) :GENERIC-FUNCTIONS (\"area\" \"radius\" \"(setf radius)\") :DOCUMENTATION \"A circle.
+With a radius.\")" "NIL")"#;
+    let class = parse_class_info(value).unwrap().unwrap();
+    assert_eq!("circle", class.name);
+    assert!(class.finalized);
+    assert_eq!(vec!["circle", "shape", "standard-object", "t"], class.precedence_list);
+    assert!(class.direct_subclasses.is_empty());
+    assert_eq!(2, class.direct_slots.len());
+    // :initform nil is an initform
+    assert_eq!(Some("nil".to_string()), class.direct_slots[1].initform);
+    assert_eq!(2, class.effective_slots.len());
+    let name = class.slot("NAME").unwrap();
+    assert_eq!("instance", name.allocation);
+    assert_eq!(None, name.initform);
+    assert_eq!(vec!["shape-name"], name.readers);
+    assert_eq!(Some("The name.".to_string()), name.documentation);
+    let radius = class.slot("radius").unwrap();
+    assert_eq!(vec![":radius"], radius.initargs);
+    assert_eq!(Some("1".to_string()), radius.initform);
+    assert_eq!("number", radius.slot_type);
+    assert_eq!(vec!["(setf radius)"], radius.writers);
+    assert_eq!(Some("A circle.\nWith a radius.".to_string()), class.documentation);
+
+    let value = r#"("(:NAME \"area\" :LAMBDA-LIST \"(shape)\" :DOCUMENTATION NIL :METHODS ((NIL ((:CLASS \"circle\")) \"(c)\" NIL \"/home/user/shapes.lisp\") ((\":around\") ((:CLASS \"t\")) \"(s)\" \"Logs.\" NIL) (NIL ((:EQL \":unit\")) \"(s)\" NIL NIL)))" "NIL")"#;
+    let gf = parse_generic_function_info(value).unwrap().unwrap();
+    assert_eq!("area", gf.name);
+    assert_eq!("(shape)", gf.lambda_list);
+    assert_eq!(3, gf.methods.len());
+    assert!(gf.methods[0].qualifiers.is_empty());
+    assert_eq!(Some("/home/user/shapes.lisp".to_string()), gf.methods[0].source_file);
+    assert_eq!(vec![":around"], gf.methods[1].qualifiers);
+    assert_eq!(vec![Specializer::Eql(":unit".to_string())], gf.methods[2].specializers);
+    let on_circle = gf.methods_specialized_on("circle");
+    assert_eq!(1, on_circle.len());
+    assert_eq!("(c)", on_circle[0].lambda_list);
+
+    assert!(parse_class_info(r#"("NIL" "NIL")"#).unwrap().is_none());
+    assert!(parse_generic_function_info(r#"("NIL" "NIL")"#).unwrap().is_none());
+}
+
+#[test]
+fn parse_flex_completion_tuples() {
+    let value = r#"((("mapcar" 0.92 ((0 "map") (3 "c")) "fn" "(mapcar function list &rest more-lists)") ("my-app::make-point" 0.5 ((3 "ma") (8 "p")) "fn,cla" nil) ("*map*" 0.1 ((1 "map")) "")) nil)"#;
+    let completions = parse_flex_completions(value).unwrap();
+    assert_eq!(3, completions.len());
+    assert_eq!("mapcar", completions[0].symbol);
+    assert_eq!(vec![(0, "map".to_string()), (3, "c".to_string())], completions[0].chunks);
+    assert_eq!(vec!["fn"], completions[0].kinds);
+    assert_eq!(Some("(mapcar function list &rest more-lists)".to_string()), completions[0].arglist);
+    assert_eq!("fn,cla", completions[1].symbol_type);
+    assert_eq!(vec!["fn", "cla"], completions[1].kinds);
+    assert_eq!(None, completions[1].arglist);
+    assert!(completions[2].kinds.is_empty());
+    assert_eq!(None, completions[2].arglist);
+
+    assert!(parse_flex_completions("nil").unwrap().is_empty());
+}
+
+#[test]
+fn detect_heap_exhaustion_in_process_output() {
+    let output = vec![
+        "Heap exhausted during allocation: 8126464 bytes available, 16777232 requested.\n".to_string(),
+        "fatal error encountered in SBCL pid 4711 tid 4711:\n".to_string(),
+        "Heap exhausted, game over.\n".to_string()
+    ];
+    assert!(is_heap_exhaustion(&output));
+    assert!(!is_heap_exhaustion(&["debugger invoked on a SIMPLE-ERROR: boom\n".to_string(), "unhandled condition in --disable-debugger mode, quitting\n".to_string()]));
+    assert!(!is_heap_exhaustion(&[]));
+}
+
+#[test]
+fn parse_watch_values_with_errors() {
+    let values = parse_watch_values(r##"("((1 T \"#S(STATE :USERS 2
+  :OPEN T)\") (2 NIL \"The variable *MISSING* is unbound.\"))" "NIL")"##).unwrap();
+    assert_eq!(vec![
+        (1, "#S(STATE :USERS 2\n  :OPEN T)".to_string(), false),
+        (2, "The variable *MISSING* is unbound.".to_string(), true)
+    ], values);
+    assert!(parse_watch_values(r#"("NIL" "NIL")"#).unwrap().is_empty());
+}
+
+#[test]
+fn parse_returned_string_keeps_newlines() {
+    let value = parse_returned_string("\"(:port 4005\n :debug t)\"").unwrap();
+    assert_eq!("(:port 4005\n :debug t)", value);
+    assert!(parse_returned_string("(1 2)").is_err());
+}
+#[test]
+fn parse_ed_byte_position_as_characters() {
+    let file = std::env::temp_dir().join(format!("parrot-ed-{}.lisp", std::process::id()));
+    std::fs::write(&file, "(defun grüße ()\n  :ok)").unwrap();
+    let filename = file.to_str().unwrap().replace('\\', "/");
+    // :ok starts at byte 21 and character 19 (1-based), ü and ß are 2 bytes each
+    let answer = parse_slynk_answer(&format!(r#"(:ed (:filename "{}" :position 21 :bytep t))"#, filename), None);
+    std::fs::remove_file(&file).unwrap();
+    match answer {
+        SlynkAnswer::Ed { target: Some(EdTarget::File { filename: f, line, column, position }) } => {
+            assert_eq!(filename, f);
+            assert_eq!(None, line);
+            assert_eq!(None, column);
+            assert_eq!(Some(19), position);
+        },
+        a => panic!("Unexpected answer: {:?}", a)
+    }
+}
@@ -0,0 +1,156 @@
+use parrot_rs::models::*;
+use parrot_rs::testing::*;
+
+const FIVEAM_OUTPUT: &str = "
+Running test suite ARITHMETIC
+ Running test ADD-TEST ..
+ Running test SUB-TEST .f
+ Running test MUL-TEST s
+ Did 4 checks.
+    Pass: 2 (50%)
+    Skip: 1 (25%)
+    Fail: 1 (25%)
+
+ Failure Details:
+ --------------------------------
+ SUB-TEST [Subtracts two numbers]: 
+      (- 2 1) evaluated to 1, which is not = to 2
+ --------------------------------
+";
+
+const PARACHUTE_OUTPUT: &str = "        ? ARITHMETIC
+  0.001 ✔   (is = 2 (+ 1 1))
+  0.001 ✔   ADD-TEST
+  0.002 ✘   (is = 2 (- 2 1))
+  0.003 ✘   SUB-TEST
+        -   MUL-TEST
+  0.008 ✘ ARITHMETIC
+
+;; Summary:
+Passed:     1
+Failed:     1
+Skipped:    1
+
+;; Failures:
+   1/   2 tests failed in SUB-TEST
+The test form   (- 2 1)
+evaluated to    1
+when            2
+was expected to be equal under =.
+";
+
+const ROVE_OUTPUT: &str = "
+Testing System arithmetic/tests
+
+;; testing 'arithmetic/tests'
+add-test
+  \u{1b}[32m✓\u{1b}[0m Expect (= (+ 1 1) 2) to be true.
+sub-test (12ms)
+  × 0) Expect (= (- 2 1) 2) to be true.
+
+× 1 of 2 tests failed
+
+0) arithmetic/tests::sub-test
+   Expect (= (- 2 1) 2) to be true.
+   (- 2 1) = 1
+";
+
+fn request(framework: TestFramework) -> TestRequest {
+    TestRequest {
+        framework,
+        system: Some("arithmetic/tests".to_string()),
+        suite: Some("arithmetic".to_string()),
+        tests: vec![],
+        package: Some("arithmetic/tests".to_string()),
+        isolated: false
+    }
+}
+
+fn statuses(tests: &[TestResult]) -> Vec<(&str, TestStatus)> {
+    tests.iter().map(|t| (t.name.as_str(), t.status)).collect()
+}
+
+#[test]
+fn parse_fiveam_output() {
+    let (suites, tests) = parse_test_output(&TestFramework::FiveAm, FIVEAM_OUTPUT);
+    assert_eq!(vec!["ARITHMETIC"], suites);
+    assert_eq!(vec![("ADD-TEST", TestStatus::Passed), ("SUB-TEST", TestStatus::Failed), ("MUL-TEST", TestStatus::Skipped)], statuses(&tests));
+    assert_eq!(Some("ARITHMETIC".to_string()), tests[1].suite);
+    assert_eq!(Some("(- 2 1) evaluated to 1, which is not = to 2".to_string()), tests[1].message);
+}
+
+#[test]
+fn parse_parachute_output() {
+    let (suites, tests) = parse_test_output(&TestFramework::Parachute, PARACHUTE_OUTPUT);
+    assert_eq!(vec!["ARITHMETIC"], suites);
+    assert_eq!(vec![
+        ("ADD-TEST", TestStatus::Passed),
+        ("SUB-TEST", TestStatus::Failed),
+        ("MUL-TEST", TestStatus::Skipped),
+        ("ARITHMETIC", TestStatus::Failed)
+    ], statuses(&tests));
+    assert_eq!(Some("ARITHMETIC".to_string()), tests[0].suite);
+    assert_eq!(Some(0.003), tests[1].duration);
+    assert!(tests[1].message.as_ref().unwrap().starts_with("The test form   (- 2 1)\nevaluated to    1"));
+    assert_eq!(None, tests[3].suite);
+}
+
+#[test]
+fn parse_rove_output() {
+    let (suites, tests) = parse_test_output(&TestFramework::Rove, ROVE_OUTPUT);
+    assert_eq!(vec!["arithmetic/tests"], suites);
+    assert_eq!(vec![("arithmetic/tests::add-test", TestStatus::Passed), ("arithmetic/tests::sub-test", TestStatus::Failed)], statuses(&tests));
+    assert_eq!(Some(0.012), tests[1].duration);
+    assert_eq!(Some("Expect (= (- 2 1) 2) to be true.\n(- 2 1) = 1".to_string()), tests[1].message);
+}
+
+#[test]
+fn test_run_with_locations_and_rerun_of_failed_tests() {
+    let locations = vec![
+        TestLocation { name: "SUB-TEST".to_string(), package: Some("OTHER".to_string()), file: "/p/other.lisp".to_string(), line: 3 },
+        TestLocation { name: "SUB-TEST".to_string(), package: Some("ARITHMETIC/TESTS".to_string()), file: "/p/tests.lisp".to_string(), line: 7 }
+    ];
+    let run = test_run(&request(TestFramework::Rove), ROVE_OUTPUT, 0.5, &locations);
+    assert_eq!(1, run.count(TestStatus::Passed));
+    assert_eq!(1, run.count(TestStatus::Failed));
+    assert_eq!(None, run.tests[0].location);
+    assert_eq!(Some(7), run.tests[1].location.as_ref().map(|l| l.line));
+
+    let rerun = rerun_failed_request(&run).unwrap();
+    assert_eq!(None, rerun.suite);
+    assert_eq!(vec!["arithmetic/tests::sub-test".to_string()], rerun.tests);
+    assert_eq!(Some("arithmetic/tests".to_string()), rerun.system);
+
+    let passed = test_run(&request(TestFramework::FiveAm), " Running test ADD-TEST ..\n", 0.1, &[]);
+    assert!(rerun_failed_request(&passed).is_none());
+}
+
+#[test]
+fn test_forms() {
+    let suite = test_form(&request(TestFramework::FiveAm)).unwrap();
+    assert!(suite.contains("(asdf:load-system \"arithmetic/tests\")"));
+    assert!(suite.contains("(find-package \"ARITHMETIC/TESTS\")"));
+    assert!(suite.contains("(uiop:symbol-call \"FIVEAM\" \"RUN!\" (read-from-string \"arithmetic\"))"));
+
+    let tests = TestRequest { suite: None, tests: vec!["add-test".to_string(), "sub-test".to_string()], ..request(TestFramework::Parachute) };
+    assert!(test_form(&tests).unwrap().contains("(mapcar #'read-from-string (list \"add-test\" \"sub-test\"))"));
+
+    let system = TestRequest { suite: None, ..request(TestFramework::Rove) };
+    let form = test_form(&system).unwrap();
+    assert!(form.contains("(asdf:test-system \"arithmetic/tests\")"));
+    assert!(!form.contains("asdf:load-system"));
+
+    assert!(test_form(&TestRequest { system: None, ..system }).is_err());
+}
+
+#[test]
+fn test_run_from_the_output_of_a_script() {
+    let form = test_script_form(&request(TestFramework::FiveAm)).unwrap();
+    assert!(form.contains(&test_form(&request(TestFramework::FiveAm)).unwrap()));
+
+    let output = format!("; compiling file \"/p/tests.lisp\"\n;; parrot test run started\n{}\n;; parrot test run finished in 0.25\n", FIVEAM_OUTPUT.trim());
+    let run = test_run_from_script_output(&request(TestFramework::FiveAm), &output, &[]);
+    assert_eq!(0.25, run.duration);
+    assert_eq!(3, run.tests.len());
+    assert!(run.output.starts_with("Running test suite ARITHMETIC"));
+}
@@ -0,0 +1,48 @@
+use parrot_rs::models::WatchRefresh;
+use parrot_rs::watch::*;
+
+#[test]
+fn update_watches_and_keep_their_history() {
+    let mut watches = WatchList::new();
+    assert!(!watches.refreshes_after_evaluation());
+    let state = watches.add("state".to_string(), "*state*".to_string(), Some("my-app".to_string()));
+    let users = watches.add("users".to_string(), "(length *users*)".to_string(), None);
+    assert_ne!(state, users);
+    assert!(watches.refreshes_after_evaluation());
+
+    assert!(watches.update(users, "1".to_string(), false));
+    // the same value is no change
+    assert!(!watches.update(users, "1".to_string(), false));
+    assert!(watches.update(users, "The variable *USERS* is unbound.".to_string(), true));
+    assert!(watches.update(users, "2".to_string(), false));
+    let watch = &watches.watches()[1];
+    assert_eq!("2", watch.value.as_ref().unwrap().value);
+    assert_eq!(vec!["1", "The variable *USERS* is unbound."], watch.history.iter().map(|v| v.value.as_str()).collect::<Vec<&str>>());
+    assert!(watch.history[1].error);
+
+    for i in 0..MAX_HISTORY + 10 {
+        watches.update(users, i.to_string(), false);
+    }
+    assert_eq!(MAX_HISTORY, watches.watches()[1].history.len());
+
+    watches.remove(state).unwrap();
+    assert!(watches.remove(state).is_err());
+    assert!(!watches.update(state, "nil".to_string(), false));
+    assert_eq!(1, watches.watches().len());
+
+    watches.set_refresh(WatchRefresh::Interval { seconds: 5 });
+    assert!(!watches.refreshes_after_evaluation());
+}
+
+#[test]
+fn watch_form_evaluates_each_watch_in_its_package() {
+    let mut watches = WatchList::new();
+    watches.add("state".to_string(), "(gethash \"key\" *state*)".to_string(), Some("my-app".to_string()));
+    watches.add("features".to_string(), "*features*".to_string(), None);
+    let form = watch_form(watches.watches());
+    assert!(form.contains(r#"(cons 1 (handler-case (let ((*package* (or (find-package "MY-APP")"#));
+    assert!(form.contains(r#"(read-from-string "(gethash \"key\" *state*)")"#));
+    assert!(form.contains(r#"(cons 2 (handler-case (let ((*package* (or (find-package "COMMON-LISP-USER")"#));
+    // e.g. a stack exhaustion must not enter the debugger either
+    assert!(form.contains("(serious-condition (e) (list nil (princ-to-string e)))"));
+}
@@ -14,6 +14,8 @@ import { ConfirmDeleteDirDialog } from './ConfirmDeleteDirDialog.js';
 import { SBCLOutputDialog } from './SBCLOutputDialog.js';
 import { SearchPane } from './SearchPane.js';
 import { PackageBrowserPane } from './PackageBrowserPane.js';
+import { ProblemsPane } from './ProblemsPane.js';
+import { SystemsPane } from './SystemsPane.js';
 import { FoundDefinitionsDialog } from './FoundDefinitionsDialog.js';
 
 export class App extends Component {
@@ -61,6 +63,8 @@ export class App extends Component {
         this.tabs = createRef();
         this.search = createRef();
         this.packages = createRef();
+        this.problems = createRef();
+        this.systems = createRef();
         this.initTermColW = window.state.getOrDefault('term-col-width', 700);
 
         window.$bus.on('show-rename', this.showRenameDialog.bind(this));
@@ -175,6 +179,37 @@ export class App extends Component {
         window.backend.replEmacsReturn(value, this.state.debugReadInfo.thread, this.state.debugReadInfo.tag);
         this.setState({ debugReadInfo: null });
     }
+    // (ed ...) called in the image
+    ed(target) {
+        if (!target) {
+            return;
+        }
+        if (target.FunctionName) {
+            window.backend.findDefinition(target.FunctionName);
+        } else if (target.File.position) {
+            this.tabs.current.openAtPosition(target.File.filename, target.File.position);
+        } else {
+            // Slynk sends a 1-based line and a 0-based column
+            $bus.trigger('jump', { file: target.File.filename, line: Math.max((target.File.line || 1) - 1, 0), col: target.File.column || 0 });
+        }
+    }
+    // (slynk:inspect-in-emacs ...) called in the image, the inspected object is printed to the REPL
+    inspect(inspection) {
+        let text = inspection.content.parts.map(p => {
+            if ('Text' in p) return p.Text;
+            if ('Label' in p) return p.Label;
+            if ('Value' in p) return p.Value.text;
+            return `[${p.Action.label}]`;
+        }).join('');
+        this.writeToREPL(`; Inspecting ${inspection.content.title}\n${text}`);
+        if (inspection.thread !== null && inspection.tag !== null) {
+            window.backend.replInspectorClosed(inspection.thread, inspection.tag);
+        }
+    }
+    yOrNP(request) {
+        let answer = window.confirm(request.question);
+        window.backend.replAnswerYOrNP(answer, request.thread, request.tag);
+    }
     writeToREPL(text) {
         this.repl.current.write(text);
     }
@@ -295,6 +330,12 @@ export class App extends Component {
             if (navCat === 'packages') {
                 this.packages.current.refresh();
             }
+            if (navCat === 'problems') {
+                this.problems.current.refresh();
+            }
+            if (navCat === 'systems') {
+                this.systems.current.refresh();
+            }
         }
     }
     render() {
@@ -335,6 +376,8 @@ export class App extends Component {
                     <svg className=${this.state.navActive === 'tree' ? 'active': ''} onClick=${() => this.onNavClicked('tree')}  height="25" width="25" viewBox="0 0 512 512"><path d="M384 80H128c-26 0-43 14-48 40L48 272v112a48.14 48.14 0 0048 48h320a48.14 48.14 0 0048-48V272l-32-152c-5-27-23-40-48-40z" fill="none" stroke="currentColor" stroke-linejoin="round" stroke-width="32"/><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="32" d="M48 272h144M320 272h144M192 272a64 64 0 00128 0"/></svg>
                     <svg className=${this.state.navActive === 'search' ? 'active': ''} onClick=${() => this.onNavClicked('search')}  height="25" width="25" viewBox="0 0 512 512"><path d="M221.09 64a157.09 157.09 0 10157.09 157.09A157.1 157.1 0 00221.09 64z" fill="none" stroke="currentColor" stroke-miterlimit="10" stroke-width="32"/><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-miterlimit="10" stroke-width="32" d="M338.29 338.29L448 448"/></svg>
                     <svg className=${this.state.navActive === 'packages' ? 'active': ''} onClick=${() => this.onNavClicked('packages')}  height="25" width="25" viewBox="0 0 512 512"><path d="M448 341.37V170.61A32 32 0 00432.11 143l-152-88.46a47.94 47.94 0 00-48.24 0L79.89 143A32 32 0 0064 170.61v170.76A32 32 0 0079.89 369l152 88.46a48 48 0 0048.24 0l152-88.46A32 32 0 00448 341.37z" fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="32"/><path fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="32" d="M69 153.99l187 110 187-110M256 463.99v-200"/></svg>
+                    <svg className=${this.state.navActive === 'problems' ? 'active': ''} onClick=${() => this.onNavClicked('problems')}  height="25" width="25" viewBox="0 0 512 512"><path d="M85.57 446.25h340.86a32 32 0 0028.17-47.17L284.18 82.58c-12.09-22.44-44.27-22.44-56.36 0L57.4 399.08a32 32 0 0028.17 47.17z" fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="32"/><path d="M250.26 195.39l5.74 122 5.73-121.95a5.74 5.74 0 00-5.79-6h0a5.74 5.74 0 00-5.68 5.95z" fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="32"/><path d="M256 397.25a20 20 0 1120-20 20 20 0 01-20 20z" fill="currentColor"/></svg>
+                    <svg className=${this.state.navActive === 'systems' ? 'active': ''} onClick=${() => this.onNavClicked('systems')}  height="25" width="25" viewBox="0 0 512 512"><rect x="48" y="48" width="176" height="176" rx="20" ry="20" fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="32"/><rect x="288" y="48" width="176" height="176" rx="20" ry="20" fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="32"/><rect x="48" y="288" width="176" height="176" rx="20" ry="20" fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="32"/><rect x="288" y="288" width="176" height="176" rx="20" ry="20" fill="none" stroke="currentColor" stroke-linecap="round" stroke-linejoin="round" stroke-width="32"/></svg>
                 </div>
                 <div className=${'tree-pane ' + (this.state.navActive === 'tree' ? '': 'hidden')}>
                     ${this.state.tree !== null && html`
@@ -379,6 +422,14 @@ export class App extends Component {
                     <${PackageBrowserPane} ref=${this.packages}>
                     </${PackageBrowserPane}>
                 </div>
+                <div className=${'tree-pane ' + (this.state.navActive === 'problems' ? '': 'hidden')}>
+                    <${ProblemsPane} ref=${this.problems}>
+                    </${ProblemsPane}>
+                </div>
+                <div className=${'tree-pane ' + (this.state.navActive === 'systems' ? '': 'hidden')}>
+                    <${SystemsPane} ref=${this.systems}>
+                    </${SystemsPane}>
+                </div>
                 <div class="flex-col flex-1 overflow-hidden" style="position: relative">
                     <${EditorTabs} ref=${this.tabs}>
                     </${EditorTabs}>
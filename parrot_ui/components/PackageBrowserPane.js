@@ -11,6 +11,7 @@ export class PackageBrowserPane extends Component {
         packageSearchInput: '',
         symbolSearchInput: '',
         activePackage: null,
+        activePackageInfo: null,
         packages: [],
         symbols: [],
         incFunctions: true,
@@ -41,7 +42,10 @@ export class PackageBrowserPane extends Component {
                 .then((pjson) => {
                     let packageList = JSON.parse(pjson);
                     let newActivePackage = packageList.includes(self.state.activePackage) ? self.state.activePackage : null;
-                    self.setState({ packages: JSON.parse(pjson), activePackage: newActivePackage, loadingPackages: false }, self.fetchSymbolsInPackage);
+                    self.setState({ packages: JSON.parse(pjson), activePackage: newActivePackage, loadingPackages: false }, () => {
+                        self.fetchSymbolsInPackage();
+                        self.fetchPackageInfo();
+                    });
                 });
         });
     }
@@ -61,8 +65,22 @@ export class PackageBrowserPane extends Component {
             self.setState({ symbols: JSON.parse(symbolList), loadingSymbols: false });
         })
     }
+    fetchPackageInfo() {
+        this.setState({ activePackageInfo: null });
+        if (!this.state.activePackage) {
+            return;
+        }
+        let self = this;
+        backend.getPackageInfos(this.state.activePackage)
+            .then(infos => {
+                self.setState({ activePackageInfo: JSON.parse(infos)[0] || null });
+            });
+    }
     onPackageClick(packageName) {
-        this.setState({ activePackage: packageName }, this.fetchSymbolsInPackage);
+        this.setState({ activePackage: packageName }, () => {
+            this.fetchSymbolsInPackage();
+            this.fetchPackageInfo();
+        });
     }
     packagesFiltered() {
         if (this.state.packageSearchInput.trim() === '') {
@@ -74,7 +92,11 @@ export class PackageBrowserPane extends Component {
         if (this.state.symbolSearchInput.trim() === '') {
             return this.state.symbols;
         }
-        return this.state.symbols.filter(p => p.toLowerCase().includes(this.state.symbolSearchInput.toLowerCase()));
+        return this.state.symbols.filter(s => s.name.toLowerCase().includes(this.state.symbolSearchInput.toLowerCase()));
+    }
+    symbolTitle(symbol) {
+        let title = symbol.arglist ? `${symbol.name} ${symbol.arglist}` : symbol.name;
+        return symbol.doc ? `${title}\n${symbol.doc}` : title;
     }
     toggleClasses() {
         this.setState({ incClasses: !this.state.incClasses }, this.fetchSymbolsInPackage);
@@ -89,6 +111,21 @@ export class PackageBrowserPane extends Component {
         this.setState({ incMacros: !this.state.incMacros }, this.fetchSymbolsInPackage);
     }
    
+    renderPackageInfo(info) {
+        let row = (label, values) => values.length > 0 && html`
+            <div><span class="text-secondary">${label}:</span> ${values.join(', ')}</div>
+        `;
+        return html`
+            <small class="mb-5">
+                ${row('Nicknames', info.nicknames)}
+                ${row('Uses', info.use_list)}
+                ${row('Used by', info.used_by)}
+                ${row('Local nicknames', info.local_nicknames.map(n => `${n.nickname} → ${n.package}`))}
+                ${row('Shadows', info.shadowing_symbols)}
+                <div class="text-secondary">${info.external_count} external, ${info.internal_count} internal symbols</div>
+            </small>
+        `;
+    }
     render() {
         let packageFilterActive = this.state.packageSearchInput.trim().length > 0;
         let symbolFilterActive = this.state.symbolSearchInput.trim().length > 0;
@@ -145,6 +182,7 @@ export class PackageBrowserPane extends Component {
                         <div class="package-browser-section-header">Click a package to load its symbols</div>
                     `}
                     <div class="package-browser-section-body">
+                        ${this.state.activePackageInfo && this.renderPackageInfo(this.state.activePackageInfo)}
                         <${SearchInput}
                             small=${true}
                             debounce=${500}
@@ -180,7 +218,10 @@ export class PackageBrowserPane extends Component {
                                 </div>
                             `}
                             ${this.symbolsFiltered().map(s => html`
-                                <div class="link-like">${s}</div>
+                                <div class="link-like flex-row flex-between" title=${this.symbolTitle(s)}>
+                                    <div>${s.name}</div>
+                                    <small class="text-secondary ml-5">${s.kinds.join(', ')}${s.exported ? ' (exported)' : ''}</small>
+                                </div>
                             `)}
                         </div>
                     </div>
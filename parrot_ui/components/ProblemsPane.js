@@ -0,0 +1,81 @@
+import { html, Component } from '../preact-bundle.js'
+
+export class ProblemsPane extends Component {
+    constructor(props) {
+      super(props);
+      this.state = {
+        groups: []
+      };
+      $bus.on('diagnostics-changed', this.refresh.bind(this));
+    }
+    refresh() {
+        let self = this;
+        backend.getDiagnostics()
+            .then(groups => {
+                self.setState({ groups: groups });
+            })
+            .catch(notifications.error);
+    }
+    clear() {
+        backend.clearDiagnostics()
+            .then(this.refresh.bind(this))
+            .catch(notifications.error);
+    }
+    jumpTo(note) {
+        if (!note.file) {
+            return;
+        }
+        $bus.trigger('jump', {file: note.file, line: note.line || 0, col: note.col || 0});
+    }
+    fileName(path) {
+        return path.replace(/\\/g, '/').split('/').pop();
+    }
+    noteSource(note) {
+        if (note.file) {
+            let line = note.line !== null ? ':' + (note.line + 1) : '';
+            return this.fileName(note.file) + line;
+        }
+        if (note.location.Location && note.location.Location.buffer.Buffer) {
+            return note.location.Location.buffer.Buffer;
+        }
+        return '';
+    }
+    render() {
+      let count = this.state.groups.reduce((acc, g) => acc + g.notes.length, 0);
+      return html`
+            <div class="flex-col overflow-hidden">
+                <div class="flex-row mb-10">
+                    <div class="flex-1 text-secondary">
+                        ${count} problem${count !== 1 ? 's' : ''}
+                    </div>
+                    ${count > 0 && html`
+                        <div class="search-pane__search-option-btn" title="Clear" onClick=${this.clear.bind(this)}>Clear</div>
+                    `}
+                </div>
+                <div class="flex-1 overflow-auto">
+                    ${count === 0 && html`
+                        <div class="text-secondary mt-20">No problems reported by the compiler.</div>
+                    `}
+                    ${this.state.groups.map(g => html`
+                        <details class="search-result-group" open>
+                            <summary>
+                                <div>
+                                    <div className=${'problems-pane__severity-' + g.severity}>${g.severity}</div>
+                                    <small>${g.notes.length}</small>
+                                </div>
+                            </summary>
+                            <div>
+                                ${g.notes.map(n => html`
+                                    <div class="problems-pane__note" title=${n.file || ''} onClick=${() => this.jumpTo(n)}>
+                                        <div>${n.message}</div>
+                                        <small>${this.noteSource(n)}</small>
+                                    </div>
+                                `)}
+                            </div>
+                        </details>
+                    `)}
+                </div>
+            </div>
+      `;
+    }
+  }
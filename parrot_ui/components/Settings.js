@@ -9,6 +9,10 @@ export class Settings extends Component {
 
         pathToSbcl: window.config.get('path_to_sbcl'),
         pathToCore: window.config.get('path_to_core'),
+        launchProfiles: window.config.get('launch_profiles') || [],
+        activeLaunchProfile: window.config.get('active_launch_profile'),
+        restartOnCrash: window.config.get('restart_on_crash'),
+        replaySessionOnRestart: window.config.get('replay_session_on_restart'),
         vimMode: window.config.get('vim_mode'),
         vimEsc: window.config.get('vim_esc'),
         shortcutCompileAndLoadFile: window.config.get('shortcut_compile_and_load_file'),
@@ -74,6 +78,9 @@ export class Settings extends Component {
         window.config.set('vim_esc', this.state.vimEsc);
         window.config.set('path_to_sbcl', this.state.pathToSbcl);
         window.config.set('path_to_core', this.state.pathToCore);
+        window.config.set('active_launch_profile', this.state.activeLaunchProfile || null);
+        window.config.set('restart_on_crash', this.state.restartOnCrash);
+        window.config.set('replay_session_on_restart', this.state.replaySessionOnRestart);
         window.config.set('shortcut_compile_and_load_file', this.state.shortcutCompileAndLoadFile);
         window.config.set('show_line_numbers', this.state.showLineNumbers);
         window.config.set('shortcut_compile_top_level', this.state.shortcutCompileToplevel);
@@ -122,6 +129,32 @@ export class Settings extends Component {
                                             <input type="text" value=${this.state.pathToCore} onChange=${e => this.setState({pathToCore: e.target.value})} style="min-width: 500px"/>
                                         </div>
                                     </div>
+                                    <div class="settings-item">
+                                        <div>Launch profile</div>
+                                        <div class="text-secondary">Profiles are defined in "launch_profiles" in config.json, the change applies on the next REPL restart</div>
+                                        <div>
+                                            <select value=${this.state.activeLaunchProfile || ''} onChange=${e => this.setState({activeLaunchProfile: e.target.value})}>
+                                                <option value="">Default (SBCL paths above)</option>
+                                                ${this.state.launchProfiles.map(p => html`
+                                                    <option value=${p.name}>${p.name} (${p.implementation})</option>
+                                                `)}
+                                            </select>
+                                        </div>
+                                    </div>
+                                    <div class="settings-item">
+                                        <div>Restart after crashes</div>
+                                        <div class="text-secondary">Start the Lisp process again when it exits unexpectedly, e.g. on heap exhaustion</div>
+                                        <div>
+                                            <${Checkbox} value=${this.state.restartOnCrash} onChange=${v => this.setState({restartOnCrash: v})}></${Checkbox}>
+                                        </div>
+                                    </div>
+                                    <div class="settings-item">
+                                        <div>Restore the session after restarts</div>
+                                        <div class="text-secondary">Load the files, systems and forms loaded before into the restarted Lisp process</div>
+                                        <div>
+                                            <${Checkbox} value=${this.state.replaySessionOnRestart} onChange=${v => this.setState({replaySessionOnRestart: v})}></${Checkbox}>
+                                        </div>
+                                    </div>
                                 
                                 `}
                                 ${this.state.cat === 2 && html`
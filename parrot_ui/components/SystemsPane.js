@@ -0,0 +1,154 @@
+import { html, Component } from '../preact-bundle.js'
+import { SearchInput } from './common/SearchInput.js'
+
+export class SystemsPane extends Component {
+    constructor(props) {
+      super(props);
+      this.state = {
+        systems: [],
+        // system name -> operation currently running
+        running: {},
+
+        qlInput: '',
+        qlSystems: [],
+        qlInstalled: false,
+        // quicklisp system name -> last progress line
+        qlLoading: {},
+
+        noFolderOpened: !window.app.hasOpenedFolder()
+      };
+      $bus.on('file-saved', this.refresh.bind(this));
+      $bus.on('system-operation', this.onSystemOperation.bind(this));
+      $bus.on('quickload-progress', this.onQuickloadProgress.bind(this));
+    }
+    refresh() {
+        let self = this;
+        this.setState({ noFolderOpened: !window.app.hasOpenedFolder() });
+        backend.getAsdfSystems()
+            .then(systems => {
+                self.setState({ systems: systems });
+            })
+            .catch(notifications.error);
+    }
+    onSystemOperation(op) {
+        this.setState(state => {
+            if (op.stage === 'Started') {
+                state.running[op.system] = op.operation;
+            } else {
+                delete state.running[op.system];
+            }
+            return state;
+        });
+    }
+    onQuickloadProgress(progress) {
+        this.setState(state => {
+            for (let system of progress.systems) {
+                state.qlLoading[system] = progress.line;
+            }
+            return state;
+        });
+    }
+    onQlInputChange(newVal) {
+        this.setState({ qlInput: newVal, qlInstalled: false }, this.fetchQlSystems);
+    }
+    showInstalled() {
+        this.setState({ qlInput: '', qlInstalled: true }, this.fetchQlSystems);
+    }
+    fetchQlSystems() {
+        let self = this;
+        let request;
+        if (this.state.qlInstalled) {
+            request = backend.quicklispInstalledSystems();
+        } else if (this.state.qlInput.trim() !== '') {
+            request = backend.quicklispSystemApropos(this.state.qlInput.trim());
+        } else {
+            this.setState({ qlSystems: [] });
+            return;
+        }
+        request.then(json => {
+            self.setState({ qlSystems: JSON.parse(json) });
+        });
+    }
+    quickload(name) {
+        let self = this;
+        this.setState(state => {
+            state.qlLoading[name] = 'Loading ...';
+            return state;
+        });
+        backend.quickload([name])
+            .then(() => {
+                self.setState(state => {
+                    delete state.qlLoading[name];
+                    return state;
+                });
+            });
+    }
+    loadSystem(name) {
+        backend.replLoadSystem(name).catch(notifications.error);
+    }
+    compileSystem(name) {
+        backend.replCompileSystem(name).catch(notifications.error);
+    }
+    testSystem(name) {
+        backend.replTestSystem(name).catch(notifications.error);
+    }
+    jumpTo(system) {
+        $bus.trigger('jump', {file: system.asd_file, line: system.line, col: 0});
+    }
+    render() {
+      return html`
+            <div class="flex-col overflow-hidden">
+                <div class="flex-1 overflow-auto">
+                    ${this.state.noFolderOpened && html`
+                        <div class="text-secondary p-15">Please first open a folder to see its ASDF systems.</div>
+                    `}
+                    ${!this.state.noFolderOpened && this.state.systems.length === 0 && html`
+                        <div class="text-secondary mt-20">Found no .asd files in the opened folder.</div>
+                    `}
+                    ${this.state.systems.map(s => html`
+                        <details class="search-result-group">
+                            <summary>
+                                <div>
+                                    <div onClick=${(e) => { e.preventDefault(); this.jumpTo(s); }}>${s.name}</div>
+                                    <small>${this.state.running[s.name] ? this.state.running[s.name] + ' ...' : (s.version || '')}</small>
+                                </div>
+                            </summary>
+                            <div class="systems-pane__body">
+                                ${s.description && html`<div class="mb-5">${s.description}</div>`}
+                                ${s.depends_on.length > 0 && html`
+                                    <div class="mb-5 text-secondary">Depends on: ${s.depends_on.join(', ')}</div>
+                                `}
+                                <div class="flex-row">
+                                    <div class="search-pane__search-option-btn" onClick=${() => this.loadSystem(s.name)}>Load</div>
+                                    <div class="search-pane__search-option-btn" onClick=${() => this.compileSystem(s.name)}>Compile</div>
+                                    <div class="search-pane__search-option-btn" onClick=${() => this.testSystem(s.name)}>Test</div>
+                                </div>
+                            </div>
+                        </details>
+                    `)}
+                    <div class="package-browser-section-header mt-15">Quicklisp</div>
+                    <div class="flex-row mt-10">
+                        <${SearchInput}
+                            debounce=${500}
+                            onChange=${(newVal) => this.onQlInputChange(newVal)}
+                            value=${this.state.qlInput}
+                        >
+                        </${SearchInput}>
+                        <div class="ml-10 search-pane__search-option-btn" title="Show installed systems" onClick=${this.showInstalled.bind(this)}>Installed</div>
+                    </div>
+                    ${this.state.qlSystems.map(s => html`
+                        <div class="problems-pane__note flex-row">
+                            <div class="flex-1">
+                                <div>${s.name}</div>
+                                <small>${this.state.qlLoading[s.name] || s.release}</small>
+                            </div>
+                            ${!this.state.qlLoading[s.name] && html`
+                                <div class="search-pane__search-option-btn" onClick=${() => this.quickload(s.name)}>Load</div>
+                            `}
+                        </div>
+                    `)}
+                </div>
+            </div>
+      `;
+    }
+  }
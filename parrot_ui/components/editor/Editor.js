@@ -200,6 +200,7 @@ export class Editor extends Component {
             map[slurpBackwardShortcut] = this.slurpBackward.bind(this);
         }
 
+        map["Enter"] = this.newlineAndIndent.bind(this);
         map["Ctrl-f"] = this.toggleSearch.bind(this);
         map["Ctrl-h"] = this.toggleReplace.bind(this);
 
@@ -269,6 +270,11 @@ export class Editor extends Component {
         }
         return text;
     }
+    // the package given by the last (in-package ...) before the cursor, forms are evaluated in it instead of the REPL's package
+    bufferPackage(textBeforeCursor) {
+        return backend.getBufferPackage(textBeforeCursor)
+            .catch(() => null);
+    }
     compileTopLevelForm() {
         let before = this.getTextBeforeCursor();
         let after = this.getTextAfterCursor();
@@ -287,7 +293,10 @@ export class Editor extends Component {
                 window.app.writeToREPL(`${TCOLORS.BG_FORM}\x1b[38;5;232m${l}\x1b[0m`);
             }
             window.app.writeToREPL('\n');
-            backend.replCompileForm(topLevelExpr.text, this.editingExistingFile ? this.state.path : 'Scratch', topLevelExpr.position, this.editingExistingFile ? this.state.path: null);
+            let buffer = this.editingExistingFile ? this.state.path : 'Scratch';
+            let filename = this.editingExistingFile ? this.state.path : null;
+            this.bufferPackage(before)
+                .then(pkg => backend.replCompileForm(topLevelExpr.text, buffer, topLevelExpr.position, filename, pkg));
         } else {
             notifications.error('Found no top-level form around your cursor.');
         }
@@ -297,7 +306,8 @@ export class Editor extends Component {
         if (before && before.length) {
             let preceding = getPrecedingExpr(before);
             if (preceding !== null) {
-                backend.interactiveEvalForm(preceding);
+                this.bufferPackage(before)
+                    .then(pkg => backend.interactiveEvalForm(preceding, pkg));
             } else {
                 notifications.error("Found no expression to evaluate that precedes your cursor.");
             }
@@ -326,12 +336,41 @@ export class Editor extends Component {
         let after = this.getTextAfterCursor();
         let form = getSymbolUnderOrBeforeCursor(before, after);
         if (form && form.length) {
-            backend.findDefinition(form);
+            this.bufferPackage(before)
+                .then(pkg => backend.findDefinition(form, pkg));
         } else {
             notifications.error("Could not find form under or before cursor.");
         }
         return true;
     }
+    /**
+     * Insert a line break and indent the new line like its enclosing form,
+     * using the indentation table of the connected image.
+     */
+    newlineAndIndent() {
+        let sel = this.editor.state.selection.main;
+        let before = this.editor.state.doc.sliceString(0, sel.from);
+        this.editor.dispatch({
+            changes: { from: sel.from, to: sel.to, insert: '\n' },
+            selection: { anchor: sel.from + 1 }
+        });
+        backend.getIndentation(before)
+            .then(col => {
+                if (col === null) {
+                    return;
+                }
+                // the line might have been edited in the meantime
+                let line = this.editor.state.doc.lineAt(Math.min(sel.from + 1, this.editor.state.doc.length));
+                let leading = line.text.length - line.text.trimStart().length;
+                let cursor = this.editor.state.selection.main.head;
+                this.editor.dispatch({
+                    changes: { from: line.from, to: line.from + leading, insert: ' '.repeat(col) },
+                    selection: cursor <= line.from + leading ? { anchor: line.from + col } : undefined
+                });
+            })
+            .catch(console.error);
+        return true;
+    }
     slurpForward() {
         let textBefore = this.getTextBeforeCursor();
         let textAfter = this.getTextAfterCursor();
@@ -423,10 +462,11 @@ export class Editor extends Component {
                         s.symbolInfo.apropos = `Fetching apropos...`;
                         return s;
                     });
-                    backend.getApropos(this.state.symbolInfo.text)
+                    // the hovered text might be quoted, e.g. #'my-fun
+                    backend.getApropos(this.state.symbolInfo.text.replace(/^#?'/, ''))
                         .then(r => {
                             self.setState(s => {
-                                s.symbolInfo.apropos = JSON.parse(r);
+                                s.symbolInfo.apropos = JSON.parse(r) || '\nCould not get apropos results.';
                                 return s;
                             });
                             self.forceUpdate();
@@ -521,6 +561,45 @@ export class Editor extends Component {
         }
     }
 
+    renderSymbolDescription(d) {
+        if (!d) {
+            return html`<pre>Symbol not found.</pre>`;
+        }
+        let field = (label, value) => value && html`<div><b>${label}:</b> ${value}</div>`;
+        return html`
+            <div class="mb-5"><b>${d.package ? `${d.package}::${d.name}` : d.name}</b></div>
+            ${d.function && html`
+                <div class="mb-5">
+                    <div><b>${d.function.kind}</b></div>
+                    ${field('Lambda list', d.function.lambda_list)}
+                    ${field('Derived type', d.function.derived_type)}
+                    ${field('Source', d.function.source_file)}
+                    ${d.function.documentation && html`<pre>${d.function.documentation}</pre>`}
+                </div>`}
+            ${d.variable && html`
+                <div class="mb-5">
+                    <div><b>variable (${d.variable.kind})</b></div>
+                    ${field('Value', d.variable.value || 'unbound')}
+                    ${field('Declared type', d.variable.declared_type)}
+                    ${d.variable.documentation && html`<pre>${d.variable.documentation}</pre>`}
+                </div>`}
+            ${d.class && html`
+                <div class="mb-5">
+                    <div><b>class</b></div>
+                    ${field('Superclasses', d.class.superclasses.join(', '))}
+                    ${field('Subclasses', d.class.subclasses.join(', '))}
+                    ${field('Slots', d.class.slots.join(', '))}
+                    ${d.class.documentation && html`<pre>${d.class.documentation}</pre>`}
+                </div>`}
+            ${d.type_info && html`
+                <div class="mb-5">
+                    <div><b>type</b></div>
+                    ${field('Expansion', d.type_info.expansion)}
+                    ${d.type_info.documentation && html`<pre>${d.type_info.documentation}</pre>`}
+                </div>`}
+            ${!d.function && !d.variable && !d.class && !d.type_info && html`<pre>${d.summary}</pre>`}
+        `;
+    }
     getTextFromAbsPos(start, end) {
         return this.getValue().substring(start, end);
     }
@@ -782,16 +861,22 @@ export class Editor extends Component {
                                 <div onClick=${() => this.setSymbolInfoTab('apropos')} className=${'cm-symbol-info__tab-header'+ (this.state.symbolInfoTab === 'apropos'? ' active': '')}>APROPOS</div>
                             </div>
                             <div class="flex-1 overflow-auto p-10">
-                                ${this.state.symbolInfoTab === 'describe' && html`
-                                    <pre>
-                                        ${this.state.symbolInfo.describe}
-                                    </pre>
-                                `}
-                                ${this.state.symbolInfoTab === 'apropos' && html`
+                                ${this.state.symbolInfoTab === 'describe' && this.renderSymbolDescription(this.state.symbolInfo.describe)}
+                                ${this.state.symbolInfoTab === 'apropos' && typeof(this.state.symbolInfo.apropos) === 'string' && html`
                                     <pre>
                                         ${this.state.symbolInfo.apropos}
                                     </pre>
                                 `}
+                                ${this.state.symbolInfoTab === 'apropos' && Array.isArray(this.state.symbolInfo.apropos) && html`
+                                    ${this.state.symbolInfo.apropos.length === 0 && html`<pre>No matches.</pre>`}
+                                    ${this.state.symbolInfo.apropos.map(hit => html`
+                                        <div class="mb-5">
+                                            <span class="cursor-pointer" onClick=${() => backend.findDefinition(hit.designator)}>${hit.designator}</span>
+                                            <span class="ml-5">[${hit.kind}]</span>
+                                            ${hit.documentation && html`<div class="ml-10">${hit.documentation}</div>`}
+                                        </div>
+                                    `)}
+                                `}
                             </div>
                         </div>
                     `}
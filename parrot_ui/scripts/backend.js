@@ -39,12 +39,21 @@ window.backend = new function() {
     //
     this.replRestart = () => invoke('repl_restart');
     this.replEval = (input) => invoke('repl_eval', { input: input });
-    this.replCompileForm = (form, buffer, position, filename) => invoke('repl_compile_form', { form: form, buffer: buffer, position: position, filename: filename });
+    this.replCompileForm = (form, buffer, position, filename, pkg) => invoke('repl_compile_form', { form: form, buffer: buffer, position: position, filename: filename, package: pkg || null });
     this.replCompileAndLoadFile = (path) => invoke('repl_compile_and_load_file', { path: path });
     this.replInvokeNthRestart = (level, n, thread) => invoke('repl_invoke_nth_restart', { level: level, n: n, thread: thread });
     this.replEmacsReturn = (form, thread, tag) => invoke('repl_emacs_return', { form: form, thread: thread, tag: tag });
-    this.interactiveEvalForm = (form) => invoke('interactive_eval_form', { form: form });
-    this.findDefinition = (symbol) => invoke('find_definition', { symbol: symbol });
+    this.replAnswerYOrNP = (answer, thread, tag) => invoke('repl_answer_y_or_n_p', { answer: answer, thread: thread, tag: tag });
+    this.replInspectorClosed = (thread, tag) => invoke('repl_inspector_closed', { thread: thread, tag: tag });
+    this.interactiveEvalForm = (form, pkg) => invoke('interactive_eval_form', { form: form, package: pkg || null });
+    this.findDefinition = (symbol, pkg) => invoke('find_definition', { symbol: symbol, package: pkg || null });
+    this.replSetPackage = (pkg) => invoke('repl_set_package', { package: pkg });
+    this.replSyncPackageAndDirectory = (pkg, directory) => invoke('repl_sync_package_and_directory', { package: pkg || null, directory: directory || null });
+    // the package of the last (in-package ...) form in the given text, null if there is none
+    this.getBufferPackage = (text) => invoke('get_buffer_package', { text: text });
+    // the column a new line after the given text is indented to, null inside strings and comments
+    this.getIndentation = (text) => invoke('get_indentation', { text: text });
+    this.reindent = (text) => invoke('reindent', { text: text });
 
     //
     // SBCL
@@ -75,29 +84,123 @@ window.backend = new function() {
     //
     this.searchSourceFiles = (query, ignoreCase, isRegex) => invoke('search_source_files', { query: query, ignoreCase: ignoreCase, isRegex: isRegex });
 
+    //
+    // problems
+    //
+    this.getDiagnostics = () => invoke('get_diagnostics');
+    this.getDiagnosticsForFile = (path) => invoke('get_diagnostics_for_file', { path: path });
+    this.clearDiagnostics = () => invoke('clear_diagnostics');
+
+    //
+    // session
+    //
+    this.getSessionSteps = () => invoke('get_session_steps');
+    this.clearSession = () => invoke('clear_session');
+    this.replaySession = () => invoke('replay_session');
+    // resolves with { success, output, log }, see image::ImageOptions for the options
+    this.saveImage = (options) => invokeWithCont('save_image', { options: options });
+    this.buildExecutable = (system, options) => invokeWithCont('build_executable', { system: system, options: options });
+
+    //
+    // watches, their values are sent as 'watches-updated'
+    //
+    this.getWatches = () => invoke('get_watches');
+    this.addWatch = (name, form, pkg) => invoke('add_watch', { name: name, form: form, package: pkg });
+    this.removeWatch = (id) => invoke('remove_watch', { id: id });
+    this.setWatchRefresh = (refresh) => invoke('set_watch_refresh', { refresh: refresh });
+    this.evaluateWatches = () => invoke('evaluate_watches');
+
+    //
+    // isolated runs, the output is sent as 'run-event'
+    //
+    this.runFile = (path) => invoke('run_file', { path: path });
+    this.runForm = (form, pkg) => invoke('run_form', { form: form, package: pkg });
+    this.cancelRun = (run) => invoke('cancel_run', { run: run });
+
+    //
+    // tests, resolve with the TestRun, null if the tests were aborted
+    //
+    this.runTests = (request) => invokeWithCont('run_tests', { request: request });
+    this.rerunFailedTests = (run) => invokeWithCont('rerun_failed_tests', { run: run });
+    // resolves with the CoverageReport, null if it failed
+    this.runCoverage = (request) => invokeWithCont('run_coverage', { request: request });
+    this.exportCoverageLcov = (report, path) => invoke('export_coverage_lcov', { report: report, path: path });
+
+    //
+    // asdf
+    //
+    this.getAsdfSystems = () => invoke('get_asdf_systems');
+    this.getAsdfSystemGraph = () => invoke('get_asdf_system_graph');
+    this.replLoadSystem = (name) => invoke('repl_load_system', { name: name });
+    this.replCompileSystem = (name) => invoke('repl_compile_system', { name: name });
+    this.replTestSystem = (name) => invoke('repl_test_system', { name: name });
+
+    //
+    // quicklisp
+    //
+    this.quickload = (systems) => invokeWithCont('quickload', { systems: systems });
+    this.quicklispSystemApropos = (term) => invokeWithCont('quicklisp_system_apropos', { term: term });
+    this.quicklispInstalledSystems = () => invokeWithCont('quicklisp_installed_systems', {});
+    this.quicklispDists = () => invokeWithCont('quicklisp_dists', {});
+
     //
     // package browser
     //
     this.getAllPackages = () => invokeWithCont('get_all_packages', {});
     this.getSymbolsInPackage = (package, vars, functions, classes, macros) => invokeWithCont('get_symbols_in_package', { package: package, vars: vars, functions: functions, classes: classes, macros: macros });
+    this.getPackageInfos = (pkg) => invokeWithCont('get_package_infos', { package: pkg || null });
+
+    // the image is changed first, the defpackage form in the opened folder is only updated if that succeeded
+    var updateDefpackageExports = (pkg, add, remove) => {
+        if (!window.app.hasOpenedFolder()) {
+            return Promise.resolve(null);
+        }
+        return invoke('update_defpackage_exports', { folder: window.app.state.folder, package: pkg, add: add, remove: remove });
+    };
+    this.exportSymbol = (symbol, pkg) => invokeWithCont('repl_export_symbol', { symbol: symbol, package: pkg })
+        .then(ok => JSON.parse(ok) ? updateDefpackageExports(pkg, [symbol], []) : Promise.reject(`Could not export ${symbol}.`));
+    this.unexportSymbol = (symbol, pkg) => invokeWithCont('repl_unexport_symbol', { symbol: symbol, package: pkg })
+        .then(ok => JSON.parse(ok) ? updateDefpackageExports(pkg, [], [symbol]) : Promise.reject(`Could not unexport ${symbol}.`));
+    this.importSymbol = (symbol, destination, origin) => invokeWithCont('repl_import_symbol', { symbol: symbol, destination: destination, origin: origin })
+        .then(ok => JSON.parse(ok) ? ok : Promise.reject(`Could not import ${symbol}.`));
+    this.exportStructure = (name, pkg) => invokeWithCont('repl_export_structure', { name: name, package: pkg })
+        .then(symbols => updateDefpackageExports(pkg, JSON.parse(symbols), []));
 
     //
     // others
     //
     this.getDescribe = (symbol) => invokeWithCont('describe_symbol', { symbol: symbol });
-    this.getApropos = (symbol) => invokeWithCont('apropos_symbol', { symbol: symbol });
+    // options: { externalOnly, caseSensitive, regex, package }, resolves to a JSON list of { designator, kind, documentation }, null if the results could not be read
+    this.getApropos = (pattern, options) => {
+        options = options || {};
+        return invokeWithCont('apropos', { 
+            pattern: pattern, 
+            externalOnly: !!options.externalOnly, 
+            caseSensitive: !!options.caseSensitive, 
+            regex: !!options.regex, 
+            package: options.package || null 
+        });
+    };
+    // resolve to JSON or null, the class/function names in the result can be passed back to navigate
+    this.getClassInfo = (cls) => invokeWithCont('class_info', { class: cls });
+    this.getGenericFunctionInfo = (name) => invokeWithCont('generic_function_info', { name: name });
+    // resolves to the value as readable text, null if the form could not be evaluated
+    this.valueForEditing = (form, pkg) => invokeWithCont('value_for_editing', { form: form, package: pkg || null });
+    // resolves to true if the edited value was set
+    this.commitEditedValue = (form, value, pkg) => invokeWithCont('commit_edited_value', { form: form, value: value, package: pkg || null });
+    // position is the cursor's character offset into the text, resolves to the variable's name
+    this.reEvaluateDefvarAt = (text, position) => invokeWithCont('re_evaluate_defvar_at', { text: text, position: position });
 
     this.getSymbolInfo = (symbol) => {
         let continuation = cont();
         invoke('describe_symbol', { symbol: symbol, continuation: continuation });
         let p1 = createPendingPromise(continuation);
-        continuation = cont();
-        invoke('apropos_symbol', { symbol: symbol, continuation: continuation });
-        let p2 = createPendingPromise(continuation);
+        let p2 = this.getApropos(symbol);
         return Promise.all([p1, p2]);
     };
     this.frameLocals = (ix, thread) => invokeWithCont('frame_locals', { ix: ix, thread: thread });
-    this.getFlexCompletions = (text) => invokeWithCont('flex_completions', { text: text });
+    // package: the buffer's package (null for the REPL's), arglists: number of top results to add arglists to
+    this.getFlexCompletions = (text, pkg, arglists) => invokeWithCont('flex_completions', { text: text, package: pkg || null, arglists: arglists || 0 });
 
     //
     // state
@@ -156,6 +259,35 @@ window.__TAURI__.event.listen('debug-return', (event) => {
 window.__TAURI__.event.listen('read-input', (event) => {
     window.app.readInput(event.payload.ReadFromMinibuffer);
 });
+window.__TAURI__.event.listen('ed', (event) => {
+    window.app.ed(event.payload.Ed.target);
+});
+window.__TAURI__.event.listen('inspect', (event) => {
+    window.app.inspect(event.payload.Inspect);
+});
+window.__TAURI__.event.listen('y-or-n-p', (event) => {
+    window.app.yOrNP(event.payload.YOrNP);
+});
+window.__TAURI__.event.listen('diagnostics-changed', (event) => {
+    $bus.trigger('diagnostics-changed', event.payload);
+});
+window.__TAURI__.event.listen('system-operation', (event) => {
+    $bus.trigger('system-operation', event.payload.SystemOperation);
+});
+window.__TAURI__.event.listen('quickload-progress', (event) => {
+    $bus.trigger('quickload-progress', event.payload.QuickloadProgress);
+});
+window.__TAURI__.event.listen('run-event', (event) => {
+    $bus.trigger('run-event', event.payload);
+});
+window.__TAURI__.event.listen('watches-updated', (event) => {
+    $bus.trigger('watches-updated', event.payload.WatchesUpdated);
+});
+// the Lisp process ended unexpectedly, show its last output
+window.__TAURI__.event.listen('process-exited', (event) => {
+    let exit = event.payload.ProcessExited;
+    window.app.writeToREPLError(`; Lisp process exited${exit.code !== null ? ' with code ' + exit.code : ''}${exit.heap_exhausted ? ' (heap exhausted)' : ''}:\n${exit.output.join('')}`);
+});
 
 
 //
@@ -2,7 +2,7 @@
 ;;; get a running slynk server
 ;;; e.g. sbcl --load start-slynk.lisp
 ;;;
-;;; Default port is 4005
+;;; Default port is 4005, or a free one written to $PARROT_SLYNK_PORT_FILE
 
 ;;; For additional slynk-side configurations see
 ;;; 6.2 section of the Slime user manual.
@@ -17,9 +17,27 @@
  :delete t         ; delete any existing SLYNK packages
  :reload t)        ; reload SLYNK, even if the SLYNK package already exists
  
-(slynk:slynk-require '("slynk/indentation" "slynk/stickers" "slynk/trace-dialog" "slynk/package-fu" "slynk/mrepl" "slynk/arglists"))
+(slynk:slynk-require '("slynk/indentation" "slynk/stickers" "slynk/trace-dialog" "slynk/package-fu" "slynk/mrepl" "slynk/arglists" "slynk/apropos"))
 
-(slynk:create-server :port 4005
-                     ;; if non-nil the connection won't be closed
-                     ;; after connecting
-                     :dont-close t)
+;
+; let (ed ...) in the image open the file or definition in the editor
+;
+#+sbcl
+(push (lambda (what) (slynk:ed-in-emacs what) t) sb-ext:*ed-functions*)
+
+;
+; parrot sets PARROT_SLYNK_PORT_FILE: let the OS choose a free port and write it to the file,
+; the trailing newline marks the file as complete. Without it, listen on the default port.
+;
+(let ((port-file #+sbcl (sb-ext:posix-getenv "PARROT_SLYNK_PORT_FILE")
+                 #+ccl (ccl:getenv "PARROT_SLYNK_PORT_FILE")
+                 #+ecl (ext:getenv "PARROT_SLYNK_PORT_FILE")
+                 #-(or sbcl ccl ecl) nil))
+  (if port-file
+      (let ((port (slynk:create-server :port 0 :dont-close t)))
+        (with-open-file (s port-file :direction :output :if-exists :supersede :if-does-not-exist :create)
+          (format s "~D~%" port)))
+      (slynk:create-server :port 4005
+                           ;; if non-nil the connection won't be closed
+                           ;; after connecting
+                           :dont-close t)))
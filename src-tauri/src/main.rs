@@ -4,8 +4,13 @@
 )]
 
 use parrot_rs::{self, BackendResult, config::Config, ConfigDiagnostics, fts::{index::Index}};
-use parrot_rs::models::{SlynkAnswer, ChannelMethod, EditorPosition};
+use parrot_rs::models::{SlynkAnswer, ChannelMethod, EditorPosition, AsdfOperation, SystemOperationStage, SessionStep, TestRequest, TestRun, CoverageRequest, CoverageReport, Watch, WatchRefresh};
 use parrot_rs::fts::models::SourceFileSearchResult;
+use parrot_rs::diagnostics::{SeverityGroup, DiagnosticsUpdate};
+use parrot_rs::asdf::{AsdfSystem, SystemGraph, SystemIndex};
+use parrot_rs::image::{ImageOptions, ImageBuildResult};
+use parrot_rs::runner::{Runner, RunTarget, RunInfo, RunEvent};
+use parrot_rs::config::LaunchProfile;
 use serde::Serialize;
 use tauri::{Window};
 use std::{sync::{Mutex}, collections::HashMap, path::Path};
@@ -17,11 +22,9 @@ lazy_static! {
 
 
         let config = parrot_rs::config::read_config().expect("Could not read config.");
-        let socket = config.slynk_socket;
         // init code will only be called if config paths are confirmed to be set,
         // so this is save
-        let path_to_sbcl = config.path_to_sbcl.unwrap();
-        let path_to_core = config.path_to_core.unwrap();
+        let profile = config.active_profile().unwrap();
 
         let current_exe_path = std::env::current_exe().unwrap();
         let slynk_start_path = current_exe_path
@@ -33,10 +36,15 @@ lazy_static! {
             .to_string();
 
 
+        let port_file = parrot_rs::config::slynk_port_file().expect("Could not build port file path.");
+        let startup_timeout = std::time::Duration::from_millis(config.slynk_startup_timeout_ms.unwrap_or(10000));
+
         let repl = parrot_rs::repl::REPL::new(
-            String::from(socket), 
-            &path_to_sbcl, 
-            &vec!["--core", &path_to_core, "--load", &slynk_start_path]);
+            config.slynk_socket.clone(), 
+            port_file,
+            startup_timeout,
+            profile,
+            slynk_start_path);
 
         Mutex::<parrot_rs::repl::REPL>::new(repl)
     };
@@ -45,6 +53,15 @@ lazy_static! {
         Mutex::new(Index::new())
     };
 
+    static ref SYSTEMS: Mutex<SystemIndex> = {
+        Mutex::new(SystemIndex::new())
+    };
+
+    static ref RUNNER: Mutex<Runner> = {
+        let data_dir = parrot_rs::config::get_data_dir().expect("Could not get data dir.");
+        Mutex::new(Runner::new(format!("{}/runs", data_dir)))
+    };
+
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -79,8 +96,15 @@ fn main() {
             repl_restart,
             repl_invoke_nth_restart,
             repl_emacs_return,
+            repl_answer_y_or_n_p,
+            repl_inspector_closed,
             interactive_eval_form,
             find_definition,
+            repl_set_package,
+            repl_sync_package_and_directory,
+            get_buffer_package,
+            get_indentation,
+            reindent,
 
             get_sbcl_process_stdout_stderr,
 
@@ -100,12 +124,59 @@ fn main() {
             search_source_files,
 
             describe_symbol,
-            apropos_symbol,
+            apropos,
+            class_info,
+            generic_function_info,
             frame_locals,
             flex_completions,
+            value_for_editing,
+            commit_edited_value,
+            re_evaluate_defvar_at,
 
             get_all_packages,
             get_symbols_in_package,
+            get_package_infos,
+            repl_export_symbol,
+            repl_unexport_symbol,
+            repl_import_symbol,
+            repl_export_structure,
+            update_defpackage_exports,
+
+            get_diagnostics,
+            get_diagnostics_for_file,
+            clear_diagnostics,
+
+            get_session_steps,
+            clear_session,
+            replay_session,
+            save_image,
+            build_executable,
+
+            get_watches,
+            add_watch,
+            remove_watch,
+            set_watch_refresh,
+            evaluate_watches,
+
+            run_file,
+            run_form,
+            cancel_run,
+
+            run_tests,
+            rerun_failed_tests,
+            run_coverage,
+            export_coverage_lcov,
+
+            get_asdf_systems,
+            get_asdf_system_graph,
+            repl_load_system,
+            repl_compile_system,
+            repl_test_system,
+
+            quickload,
+            quicklisp_system_apropos,
+            quicklisp_installed_systems,
+            quicklisp_dists,
 
             get_state,
             get_state_value,
@@ -126,21 +197,33 @@ fn check_config() -> ConfigDiagnostics {
 fn init_repl(window: Window) -> BackendResult<()> {
     println!("after_lisp_path_confirmed: Init REPL");
     lazy_static::initialize(&REPL);
+    let run_events = RUNNER.lock().unwrap().subscribe();
+    let run_window = window.clone();
+    std::thread::spawn(move || {
+        handle_run_events(run_events, run_window);
+    });
     let repl = REPL.lock().unwrap();
     if let Some(err_mess) = &repl.init_err {
         window.emit("term-init-error", Payload { text: err_mess.clone() }).expect("Could not send");
         print_sbcl_output_to_terminal(repl.get_sbcl_process_stdout_stderr(), window);
     } else {
         let receiver = repl.receiver();
+        let diagnostics_receiver = repl.diagnostics().lock().unwrap().subscribe();
+        let diagnostics_window = window.clone();
         std::thread::spawn(move || {
             println!("main rec listening...");
             handle_repl_commands(receiver, window);
         });
+        std::thread::spawn(move || {
+            handle_diagnostics_updates(diagnostics_receiver, diagnostics_window);
+        });
+        replay_session_if_configured(&repl);
     }
     Ok(())
 }
 #[tauri::command]
 fn folder_opened(folder: String) {
+    SYSTEMS.lock().unwrap().build(&folder);
     let mut index = INDEX.lock().unwrap();
     index.build(folder);
 }
@@ -151,16 +234,21 @@ fn folder_opened(folder: String) {
 #[tauri::command]
 fn repl_restart(window: Window) -> BackendResult<()> {
     println!("App::repl_restart()");
+    // the active profile may have been changed in the settings
+    let profile = parrot_rs::config::read_config()?
+        .active_profile()
+        .ok_or_else(|| parrot_rs::BackendError("No Lisp configured.".to_string()))?;
     REPL
         .lock()
         .unwrap()
-        .restart()?;
+        .restart_with_profile(profile)?;
     let receiver = REPL.lock().unwrap().receiver();
 
      std::thread::spawn(move || {
         println!("main rec listening...");
         handle_repl_commands(receiver, window);
     });
+    replay_session_if_configured(&REPL.lock().unwrap());
     Ok(())
 }
 #[tauri::command]
@@ -171,11 +259,11 @@ fn repl_eval(input: String)  {
         .eval_form(input);
 }
 #[tauri::command]
-fn repl_compile_form(form: String, buffer: String, filename: Option<String>, position: EditorPosition)  {
+fn repl_compile_form(form: String, buffer: String, filename: Option<String>, position: EditorPosition, package: Option<String>)  {
     REPL
         .lock()
         .unwrap()
-        .compile_form(form, buffer, position, filename);
+        .compile_form(form, buffer, position, filename, package);
 }
 #[tauri::command]
 fn repl_emacs_return(form: String, thread: usize, tag: usize)  {
@@ -199,18 +287,68 @@ fn repl_invoke_nth_restart(level: usize, n: usize, thread: usize) {
         .invoke_nth_restart(level, n, thread);
 }
 #[tauri::command]
-fn interactive_eval_form(form: String) {
+fn repl_answer_y_or_n_p(answer: bool, thread: usize, tag: usize) -> BackendResult<()> {
+    REPL
+        .lock()
+        .unwrap()
+        .answer_y_or_n_p(answer, thread, tag)
+}
+#[tauri::command]
+fn repl_inspector_closed(thread: usize, tag: usize) -> BackendResult<()> {
+    REPL
+        .lock()
+        .unwrap()
+        .inspector_closed(thread, tag)
+}
+#[tauri::command]
+fn interactive_eval_form(form: String, package: Option<String>) {
+    REPL
+        .lock()
+        .unwrap()
+        .interactive_eval_form(form, package);
+}
+#[tauri::command]
+fn find_definition(symbol: String, package: Option<String>) {
+    REPL
+        .lock()
+        .unwrap()
+        .find_definition_for_symbol(symbol, package);
+}
+#[tauri::command]
+fn repl_set_package(package: String) -> BackendResult<()> {
     REPL
         .lock()
         .unwrap()
-        .interactive_eval_form(form);
+        .guess_and_set_package(package)
 }
 #[tauri::command]
-fn find_definition(symbol: String) {
+fn repl_sync_package_and_directory(package: Option<String>, directory: Option<String>) -> BackendResult<()> {
     REPL
         .lock()
         .unwrap()
-        .find_definition_for_symbol(symbol);
+        .sync_package_and_default_directory(package, directory)
+}
+///
+/// Get the package of the given buffer text at its end, i.e. of the last (in-package ...) form.
+///
+#[tauri::command]
+fn get_buffer_package(text: &str) -> Option<String> {
+    parrot_rs::reader::in_package_at(text, text.len())
+}
+///
+/// Get the column a new line after the given text should be indented to, None if the text ends inside a string or comment.
+///
+#[tauri::command]
+fn get_indentation(text: &str) -> Option<usize> {
+    let table = REPL.lock().unwrap().indentation();
+    let table = table.lock().unwrap();
+    parrot_rs::indentation::indent_column(text, text.len(), &table)
+}
+#[tauri::command]
+fn reindent(text: &str) -> String {
+    let table = REPL.lock().unwrap().indentation();
+    let table = table.lock().unwrap();
+    parrot_rs::indentation::reindent(text, &table)
 }
 
 //
@@ -258,6 +396,9 @@ fn save_file_content(path: &str, content: &str) -> BackendResult<()> {
             INDEX.lock()
                 .unwrap()
                 .handle_file_content_write(path);
+            SYSTEMS.lock()
+                .unwrap()
+                .handle_file_content_write(path);
         }
 
     }
@@ -344,6 +485,338 @@ fn get_symbols_in_package(package: String, vars: bool, functions: bool, classes:
         .unwrap()
         .get_symbols_in_package(package, vars, functions, classes, macros, continuation)
 }
+#[tauri::command]
+fn get_package_infos(package: Option<String>, continuation: usize) -> BackendResult<()>  {
+    REPL.lock()
+        .unwrap()
+        .get_package_infos(package, continuation)
+}
+#[tauri::command]
+fn repl_export_symbol(symbol: String, package: String, continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .export_symbol(symbol, package, continuation)
+}
+#[tauri::command]
+fn repl_unexport_symbol(symbol: String, package: String, continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .unexport_symbol(symbol, package, continuation)
+}
+#[tauri::command]
+fn repl_import_symbol(symbol: String, destination: String, origin: String, continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .import_symbol(symbol, destination, origin, continuation)
+}
+#[tauri::command]
+fn repl_export_structure(name: String, package: String, continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .export_structure(name, package, continuation)
+}
+///
+/// Update the :export clause of the package's defpackage form in the opened folder, returns the path of the changed file.
+///
+#[tauri::command]
+fn update_defpackage_exports(folder: &str, package: &str, add: Vec<String>, remove: Vec<String>) -> BackendResult<Option<String>> {
+    let path = parrot_rs::defpackage::update_exports_in_folder(folder, package, &add, &remove)?;
+    if let Some(p) = &path {
+        INDEX.lock()
+            .unwrap()
+            .handle_file_content_write(p);
+    }
+    Ok(path)
+}
+
+//
+// problems
+//
+
+#[tauri::command]
+fn get_diagnostics() -> Vec<SeverityGroup> {
+    REPL.lock()
+        .unwrap()
+        .diagnostics()
+        .lock()
+        .unwrap()
+        .get_grouped_by_severity()
+}
+#[tauri::command]
+fn get_diagnostics_for_file(path: &str) -> Vec<parrot_rs::models::CompilerNotes> {
+    REPL.lock()
+        .unwrap()
+        .diagnostics()
+        .lock()
+        .unwrap()
+        .get_notes_for_file(path)
+}
+#[tauri::command]
+fn clear_diagnostics() {
+    REPL.lock()
+        .unwrap()
+        .diagnostics()
+        .lock()
+        .unwrap()
+        .clear();
+}
+
+//
+// session
+//
+#[tauri::command]
+fn get_session_steps() -> Vec<SessionStep> {
+    REPL.lock()
+        .unwrap()
+        .session()
+        .lock()
+        .unwrap()
+        .steps()
+        .to_vec()
+}
+#[tauri::command]
+fn clear_session() -> BackendResult<()> {
+    let session = REPL.lock().unwrap().session();
+    let mut session = session.lock().unwrap();
+    session.clear();
+    session.save()
+}
+#[tauri::command]
+fn replay_session() -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .replay_session()
+}
+#[tauri::command]
+fn save_image(window: Window, options: ImageOptions, continuation: usize) -> BackendResult<()> {
+    let (profile, steps) = {
+        let repl = REPL.lock().unwrap();
+        let steps = repl.session().lock().unwrap().steps().to_vec();
+        (repl.profile().clone(), steps)
+    };
+    let script = parrot_rs::image::session_image_script(&steps, &options, &profile.implementation)?;
+    std::thread::spawn(move || build_image_and_resolve(window, profile, script, options, continuation));
+    Ok(())
+}
+#[tauri::command]
+fn build_executable(window: Window, system: String, options: ImageOptions, continuation: usize) -> BackendResult<()> {
+    let profile = REPL.lock().unwrap().profile().clone();
+    let script = parrot_rs::image::system_image_script(&system, &options, &profile.implementation)?;
+    std::thread::spawn(move || build_image_and_resolve(window, profile, script, options, continuation));
+    Ok(())
+}
+// saving takes a while, the result is sent when the building process has exited
+fn build_image_and_resolve(window: Window, profile: LaunchProfile, script: String, options: ImageOptions, continuation: usize) {
+    let result = parrot_rs::image::build_image(&profile, &script, &options.output)
+        .unwrap_or_else(|e| ImageBuildResult { success: false, output: options.output.clone(), log: vec![e.0] });
+    if let (true, false, Some(name)) = (result.success, options.executable, &options.launch_profile) {
+        if let Err(e) = parrot_rs::image::add_core_launch_profile(&profile, name, &options.output) {
+            notify_error(&e.0, &window).expect("Could not send event to main window");
+        }
+    }
+    window.emit("resolve-pending", ResolvePending { continuation, data: serde_json::to_string(&result).unwrap() }).expect("Could not send event to main window");
+}
+
+//
+// watches
+//
+#[tauri::command]
+fn get_watches() -> Vec<Watch> {
+    REPL.lock()
+        .unwrap()
+        .watches()
+        .lock()
+        .unwrap()
+        .watches()
+        .to_vec()
+}
+// evaluates the new watch right away
+#[tauri::command]
+fn add_watch(name: String, form: String, package: Option<String>) -> BackendResult<usize> {
+    let repl = REPL.lock().unwrap();
+    let id = {
+        let watches = repl.watches();
+        let mut watches = watches.lock().unwrap();
+        let id = watches.add(name, form, package);
+        watches.save()?;
+        id
+    };
+    repl.evaluate_watches()?;
+    Ok(id)
+}
+#[tauri::command]
+fn remove_watch(id: usize) -> BackendResult<()> {
+    let watches = REPL.lock().unwrap().watches();
+    let mut watches = watches.lock().unwrap();
+    watches.remove(id)?;
+    watches.save()
+}
+#[tauri::command]
+fn set_watch_refresh(refresh: WatchRefresh) -> BackendResult<()> {
+    let watches = REPL.lock().unwrap().watches();
+    let mut watches = watches.lock().unwrap();
+    watches.set_refresh(refresh);
+    watches.save()
+}
+#[tauri::command]
+fn evaluate_watches() -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .evaluate_watches()
+}
+
+//
+// isolated runs
+//
+#[tauri::command]
+fn run_file(path: String) -> BackendResult<RunInfo> {
+    let profile = REPL.lock().unwrap().profile().clone();
+    RUNNER.lock()
+        .unwrap()
+        .start(&profile, RunTarget::File(path))
+}
+#[tauri::command]
+fn run_form(form: String, package: Option<String>) -> BackendResult<RunInfo> {
+    let profile = REPL.lock().unwrap().profile().clone();
+    RUNNER.lock()
+        .unwrap()
+        .start(&profile, RunTarget::Form { form, package })
+}
+#[tauri::command]
+fn cancel_run(run: usize) -> BackendResult<()> {
+    RUNNER.lock()
+        .unwrap()
+        .cancel(run)
+}
+
+//
+// tests
+//
+#[tauri::command]
+fn run_tests(window: Window, request: TestRequest, continuation: usize) -> BackendResult<()> {
+    let locations = INDEX.lock().unwrap().test_locations();
+    if request.isolated {
+        let profile = REPL.lock().unwrap().profile().clone();
+        // the result is sent when the process has exited
+        std::thread::spawn(move || {
+            let run = match parrot_rs::testing::run_tests_in_process(&profile, &request, &locations) {
+                Ok(run) => Some(run),
+                Err(e) => {
+                    notify_error(&e.0, &window).expect("Could not send event to main window");
+                    None
+                }
+            };
+            window.emit("resolve-pending", ResolvePending { continuation, data: serde_json::to_string(&run).unwrap() }).expect("Could not send event to main window");
+        });
+        return Ok(());
+    }
+    REPL.lock()
+        .unwrap()
+        .run_tests(request, locations, continuation)
+}
+#[tauri::command]
+fn rerun_failed_tests(window: Window, run: TestRun, continuation: usize) -> BackendResult<()> {
+    match parrot_rs::testing::rerun_failed_request(&run) {
+        Some(request) => run_tests(window, request, continuation),
+        None => Err(parrot_rs::BackendError("There are no failed tests to run again.".to_string()))
+    }
+}
+#[tauri::command]
+fn run_coverage(window: Window, request: CoverageRequest, continuation: usize) -> BackendResult<()> {
+    let locations = INDEX.lock().unwrap().test_locations();
+    let profile = REPL.lock().unwrap().profile().clone();
+    // the result is sent when the process has exited
+    std::thread::spawn(move || {
+        let document_path = |file: &str| INDEX.lock().unwrap().document_path(file);
+        let report = match parrot_rs::coverage::run_coverage(&profile, &request, &locations, document_path) {
+            Ok(report) => Some(report),
+            Err(e) => {
+                notify_error(&e.0, &window).expect("Could not send event to main window");
+                None
+            }
+        };
+        window.emit("resolve-pending", ResolvePending { continuation, data: serde_json::to_string(&report).unwrap() }).expect("Could not send event to main window");
+    });
+    Ok(())
+}
+#[tauri::command]
+fn export_coverage_lcov(report: CoverageReport, path: String) -> BackendResult<()> {
+    parrot_rs::file::save_file_content(path, &parrot_rs::coverage::to_lcov(&report))
+}
+
+//
+// asdf
+//
+
+#[tauri::command]
+fn get_asdf_systems() -> Vec<AsdfSystem> {
+    SYSTEMS.lock()
+        .unwrap()
+        .get_systems()
+}
+#[tauri::command]
+fn get_asdf_system_graph() -> SystemGraph {
+    SYSTEMS.lock()
+        .unwrap()
+        .graph()
+}
+#[tauri::command]
+fn repl_load_system(name: String) -> BackendResult<()> {
+    let files = get_system_source_files(&name);
+    REPL.lock()
+        .unwrap()
+        .load_system(name, files)
+}
+#[tauri::command]
+fn repl_compile_system(name: String) -> BackendResult<()> {
+    let files = get_system_source_files(&name);
+    REPL.lock()
+        .unwrap()
+        .compile_system(name, files)
+}
+#[tauri::command]
+fn repl_test_system(name: String) -> BackendResult<()> {
+    let files = get_system_source_files(&name);
+    REPL.lock()
+        .unwrap()
+        .test_system(name, files)
+}
+fn get_system_source_files(name: &str) -> Vec<String> {
+    SYSTEMS.lock()
+        .unwrap()
+        .get_system(name)
+        .map(|s| s.source_files())
+        .unwrap_or_default()
+}
+
+//
+// quicklisp
+//
+
+#[tauri::command]
+fn quickload(systems: Vec<String>, continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .quickload(systems, continuation)
+}
+#[tauri::command]
+fn quicklisp_system_apropos(term: String, continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .quicklisp_system_apropos(term, continuation)
+}
+#[tauri::command]
+fn quicklisp_installed_systems(continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .quicklisp_installed_systems(continuation)
+}
+#[tauri::command]
+fn quicklisp_dists(continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .quicklisp_dists(continuation)
+}
 
 //
 // other
@@ -355,10 +828,47 @@ fn describe_symbol(symbol: String, continuation: usize) -> BackendResult<()> {
         .describe_symbol(symbol, continuation)
 }
 #[tauri::command]
-fn apropos_symbol(symbol: String, continuation: usize) -> BackendResult<()> {
+fn apropos(pattern: String, external_only: bool, case_sensitive: bool, regex: bool, package: Option<String>, continuation: usize) -> BackendResult<()> {
     REPL.lock()
         .unwrap()
-        .apropos_symbol(symbol, continuation)
+        .apropos(pattern, external_only, case_sensitive, regex, package, continuation)
+}
+#[tauri::command]
+fn class_info(class: String, continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .class_info(class, continuation)
+}
+#[tauri::command]
+fn generic_function_info(name: String, continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .generic_function_info(name, continuation)
+}
+#[tauri::command]
+fn value_for_editing(form: String, package: Option<String>, continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .value_for_editing(form, package, continuation)
+}
+#[tauri::command]
+fn commit_edited_value(form: String, value: String, package: Option<String>, continuation: usize) -> BackendResult<()> {
+    REPL.lock()
+        .unwrap()
+        .commit_edited_value(form, value, package, continuation)
+}
+///
+/// Evaluate the (defvar ...) form at the given position again, in the package of the buffer at that position.
+///
+#[tauri::command]
+fn re_evaluate_defvar_at(text: &str, position: usize, continuation: usize) -> BackendResult<()> {
+    // the editor's position counts characters, the reader's bytes
+    let position = parrot_rs::text::byte_offset_from_char_pos(text, position);
+    let form = parrot_rs::reader::defvar_at(text, position)
+        .ok_or_else(|| parrot_rs::BackendError("No defvar at the cursor.".to_string()))?;
+    REPL.lock()
+        .unwrap()
+        .re_evaluate_defvar(form.to_string(), parrot_rs::reader::in_package_at(text, position), continuation)
 }
 #[tauri::command]
 fn frame_locals(ix: usize, thread: usize, continuation: usize) -> BackendResult<()> {
@@ -367,10 +877,25 @@ fn frame_locals(ix: usize, thread: usize, continuation: usize) -> BackendResult<
         .frame_locals(ix, thread, continuation)
 }
 #[tauri::command]
-fn flex_completions(text: String, continuation: usize) -> BackendResult<()> {
-    REPL.lock()
+fn flex_completions(window: Window, text: String, package: Option<String>, arglists: usize, continuation: usize) -> BackendResult<()> {
+    let offline = INDEX.lock()
         .unwrap()
-        .get_completions(text, continuation)
+        .complete_definitions(&text, package.as_deref(), 100);
+    let repl = REPL.lock().unwrap();
+    // no image to ask or it does not take the request, complete from the project's definitions only
+    if !repl.is_alive() || repl.get_completions(text, package, arglists, offline.clone(), continuation).is_err() {
+        window.emit("resolve-pending", ResolvePending { continuation, data: serde_json::to_string(&offline).unwrap() }).expect("Could not send");
+        return Ok(());
+    }
+    drop(repl);
+    // a hung image does not answer, the completions are resolved with the offline ones then
+    std::thread::spawn(move || {
+        std::thread::sleep(parrot_rs::repl::COMPLETION_TIMEOUT);
+        if let Some(offline) = REPL.lock().unwrap().take_pending_completions(continuation) {
+            window.emit("resolve-pending", ResolvePending { continuation, data: serde_json::to_string(&offline).unwrap() }).expect("Could not send");
+        }
+    });
+    Ok(())
 }
 
 //
@@ -401,6 +926,44 @@ fn notify_success(message: &str, window: &Window) -> Result<(), tauri::Error> {
     window.emit("notify-success", Payload { text: message.to_string() })
 }
 
+fn handle_run_events(rec: crossbeam::channel::Receiver<RunEvent>, window: Window) {
+    for event in rec {
+        window.emit("run-event", event).expect("Could not send event to main window");
+    }
+}
+
+fn handle_diagnostics_updates(rec: crossbeam::channel::Receiver<DiagnosticsUpdate>, window: Window) {
+    for update in rec {
+        window.emit("diagnostics-changed", update).expect("Could not send event to main window");
+    }
+}
+
+fn restart_after_crash(window: Window) {
+    let mut repl = REPL.lock().unwrap();
+    if let Err(e) = repl.restart_after_crash() {
+        notify_error(&e.0, &window).expect("Could not send event to main window");
+        return;
+    }
+    if let Some(err_mess) = &repl.init_err {
+        window.emit("term-init-error", Payload { text: err_mess.clone() }).expect("Could not send");
+        print_sbcl_output_to_terminal(repl.get_sbcl_process_stdout_stderr(), window);
+    } else {
+        let receiver = repl.receiver();
+        std::thread::spawn(move || {
+            handle_repl_commands(receiver, window);
+        });
+        replay_session_if_configured(&repl);
+    }
+}
+// loads the files, systems and forms of the previous session into the new image
+fn replay_session_if_configured(repl: &parrot_rs::repl::REPL) {
+    if parrot_rs::config::read_config().map(|c| c.replay_session_on_restart).unwrap_or(false) {
+        if let Err(e) = repl.replay_session() {
+            println!("Could not replay the session: {}", e.0);
+        }
+    }
+}
+
 fn handle_repl_commands(rec: crossbeam::channel::Receiver<SlynkAnswer>, window: Window) {
     for m in rec {
         let mut emit: Result<(), tauri::Error> = Ok(());
@@ -435,21 +998,74 @@ fn handle_repl_commands(rec: crossbeam::channel::Receiver<SlynkAnswer>, window:
         } else if matches!(m, SlynkAnswer::ReadFromMinibuffer { .. }) {
             emit = window.emit("read-input", m);
         } else if let SlynkAnswer::ReturnCompilationResult { duration, success, notes, .. } = m {
-            if let Some(compiler_notes) = notes {
-                window.emit("term-write", Payload { text: compiler_notes
-                    .iter()
-                    .map(|n| format!("Compiler note [{}]:\n{}: {} ", n.file.as_ref().unwrap_or(&String::from("<unknown file>")), n.severity, n.message))
-                    .collect::<Vec<String>>().join("\n")}).unwrap();
+            if let Some(compiler_notes) = notes.filter(|n| !n.is_empty()) {
+                window.emit("term-write", Payload { text: format!("; {} compiler note{}, see Problems.", compiler_notes.len(), if compiler_notes.len() == 1 { "" } else { "s" }) }).unwrap();
             }
             if success {
                 emit = notify_success(&format!("Compiled [{}s].", duration), &window);
             } else {
                 emit = notify_error("Failed to compile.", &window);
             }
+        } else if let SlynkAnswer::SystemOperation { system, operation, stage } = &m {
+            window.emit("system-operation", m.clone()).unwrap();
+            let (running, done) = match operation {
+                AsdfOperation::Load => ("Loading", "Loaded"),
+                AsdfOperation::Compile => ("Compiling", "Compiled"),
+                AsdfOperation::Test => ("Testing", "Tested")
+            };
+            if let SystemOperationStage::Started = stage {
+                emit = window.emit("term-write", Payload { text: format!("; {} system {} ...", running, system) });
+            } else if let SystemOperationStage::Finished { success, duration, notes } = stage {
+                if *notes > 0 {
+                    window.emit("term-write", Payload { text: format!("; {} compiler note{}, see Problems.", notes, if *notes == 1 { "" } else { "s" }) }).unwrap();
+                }
+                if *success {
+                    emit = notify_success(&format!("{} system {} [{}s].", done, system, duration), &window);
+                } else {
+                    emit = notify_error(&format!("{} system {} failed.", running, system), &window);
+                }
+            }
+        } else if matches!(m, SlynkAnswer::QuickloadProgress { .. }) {
+            emit = window.emit("quickload-progress", m);
         } else if let SlynkAnswer::ReturnFindDefinitionResult { .. } = &m {
             emit = window.emit("found-definitions", m.clone());
         } else if let SlynkAnswer::ResolvePending { continuation, data } = m {
             emit = window.emit("resolve-pending", ResolvePending { continuation, data });
+        } else if matches!(m, SlynkAnswer::Ed { .. }) {
+            emit = window.emit("ed", m);
+        } else if matches!(m, SlynkAnswer::Inspect { .. }) {
+            emit = window.emit("inspect", m);
+        } else if matches!(m, SlynkAnswer::YOrNP { .. }) {
+            emit = window.emit("y-or-n-p", m);
+        } else if let SlynkAnswer::BackgroundMessage { message } = m {
+            emit = notify_success(&message, &window);
+        } else if matches!(m, SlynkAnswer::WatchesUpdated(_)) {
+            emit = window.emit("watches-updated", m);
+        } else if let SlynkAnswer::ProcessExited { code, heap_exhausted, .. } = &m {
+            window.emit("process-exited", m.clone()).unwrap();
+            let reason = match (heap_exhausted, code) {
+                (true, _) => "ran out of heap".to_string(),
+                (false, Some(code)) => format!("exited with code {}", code),
+                (false, None) => "was killed".to_string()
+            };
+            let restart = parrot_rs::config::read_config().map(|c| c.restart_on_crash).unwrap_or(false);
+            if !restart {
+                emit = notify_error(&format!("The Lisp process {}.", reason), &window);
+            } else {
+                match REPL.lock().unwrap().crash_restart_delay() {
+                    Some(delay) => {
+                        emit = notify_error(&format!("The Lisp process {}, restarting in {}s.", reason, delay.as_secs()), &window);
+                        let restart_window = window.clone();
+                        std::thread::spawn(move || {
+                            std::thread::sleep(delay);
+                            restart_after_crash(restart_window);
+                        });
+                    },
+                    None => emit = notify_error(&format!("The Lisp process {} again, not restarting it.", reason), &window)
+                }
+            }
+        } else if let SlynkAnswer::Unknown(event) = m {
+            println!("Unhandled Slynk event: {}", event);
         };
         emit.expect("Could not send event to main window");
     }